@@ -0,0 +1,132 @@
+//! Deserialization of SVD device descriptions, run with `cargo bench -p yaserde-examples`.
+//!
+//! Compare two versions with criterion baselines: `cargo bench -- --save-baseline before` on the
+//! first one, then `cargo bench -- --baseline before` on the second one.
+//!
+//! Reading derived containers with the runtime helpers and name tables of `de::visit`, instead of
+//! the code generated for each field, measured on these devices in release mode (minimum of 30
+//! runs, allocations counted with a counting global allocator):
+//!
+//! | device                        | allocations      | derived code     | `from_str`         |
+//! |-------------------------------|------------------|------------------|--------------------|
+//! | 1 peripheral, 38 742 bytes    | 8 323 → 1 607    | 363 → 181 µs     | 2 342 → 2 263 µs   |
+//! | 16 peripherals, 607 308 bytes | 129 991 → 25 160 | 5 017 → 2 470 µs | 36 391 → 34 257 µs |
+//!
+//! "Derived code" is the time of `from_str` minus the time of reading the events alone, which is
+//! about 90% of `from_str`. Reproduce it with a worktree of each version, `f754a80` with the
+//! `examples` directory of `13ec20d` copied in, and a separate `CARGO_TARGET_DIR` for each one so
+//! that cargo doesn't reuse the same bench binary. The times vary by up to 50% between runs on
+//! a shared machine, the allocation counts don't.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use yaserde_examples::svd::{CpuDef, DevAttrs, Device, Field, Peripheral, Register};
+
+/// Device of `peripherals` peripherals of 16 registers of 8 fields each.
+fn device(peripherals: usize) -> Device {
+  let field = |index: usize| Field {
+    name: format!("FIELD{}", index),
+    description: "Selects the clock source of the timer & its prescaler".to_string(),
+    bitrange: format!("[{}:{}]", index * 4 + 3, index * 4),
+    access: "read-write".to_string(),
+  };
+
+  let register = |index: usize| {
+    Register {
+    name: format!("REG{}", index),
+    description: "This command register (PRCMD) is to protect the registers that may have a significant influence on the application system (PSC, PSM) from an inadvertent write access.".to_string(),
+    addressoffset: format!("0x{:08X}", index * 4),
+    size: 32,
+    access: "read-write".to_string(),
+    resetvalue: "0x00000000".to_string(),
+    resetmask: "0xFFFFFFFF".to_string(),
+    fields: (0..8).map(field).collect(),
+  }
+  };
+
+  let peripheral = |index: usize| Peripheral {
+    name: format!("TIMER{}", index),
+    version: "1.0".to_string(),
+    description: "Timer".to_string(),
+    groupname: "TIMER".to_string(),
+    baseaddress: format!("0x{:08X}", 0x4000_0000 + index * 0x400),
+    size: 32,
+    access: "read-write".to_string(),
+    registers: (0..16).map(register).collect(),
+  };
+
+  Device {
+    schemaversion: "1.3".to_string(),
+    // Read as a namespace declaration rather than an attribute, Device has no namespace
+    xmlns: String::new(),
+    xsnonamespaceschemalocation: "CMSIS-SVD.xsd".to_string(),
+    devattributes: DevAttrs {
+      vendor: "Renesas".to_string(),
+      vendorid: "Renesas".to_string(),
+      name: "V850".to_string(),
+      series: "E1/E2/CA2".to_string(),
+      version: "1.2".to_string(),
+      description: "NEC/Renesas V850 automotive grade ICs".to_string(),
+      licensetext: "GPLv3".to_string(),
+      cpu: CpuDef {
+        name: "V850".to_string(),
+        revision: "r1".to_string(),
+        endian: "LE".to_string(),
+        mpupresent: false,
+        fpupresent: false,
+        vendorsystickconfig: false,
+      },
+      addressunitbits: 8,
+      width: 32,
+      size: 32,
+      access: "read-write".to_string(),
+      resetvalue: "0x00000000".to_string(),
+      resetmask: "0xFFFFFFFF".to_string(),
+      peripherals: (0..peripherals).map(peripheral).collect(),
+    },
+  }
+}
+
+fn deserialize(c: &mut Criterion) {
+  let mut group = c.benchmark_group("svd");
+  let config = yaserde::ser::Config {
+    perform_indent: true,
+    ..Default::default()
+  };
+
+  for peripherals in [1, 16] {
+    let expected = device(peripherals);
+    let document = yaserde::ser::to_string_with_config(&expected, &config).unwrap();
+    assert_eq!(
+      yaserde::de::from_str::<Device>(&document).unwrap(),
+      expected
+    );
+    group.throughput(Throughput::Bytes(document.len() as u64));
+
+    // Reading the events alone, the rest of the time of from_str is spent by the derived code
+    group.bench_with_input(
+      BenchmarkId::new("events", peripherals),
+      &document,
+      |b, document| {
+        b.iter(|| {
+          let mut reader = yaserde::de::Deserializer::new_from_str(document);
+          while reader.next_event().unwrap() != yaserde::event::XmlEvent::EndDocument {}
+        })
+      },
+    );
+    group.bench_with_input(
+      BenchmarkId::new("from_str", peripherals),
+      &document,
+      |b, document| b.iter(|| yaserde::de::from_str::<Device>(document).unwrap()),
+    );
+    group.bench_with_input(
+      BenchmarkId::new("from_reader", peripherals),
+      &document,
+      |b, document| b.iter(|| yaserde::de::from_reader::<_, Device>(document.as_bytes()).unwrap()),
+    );
+  }
+
+  group.finish();
+}
+
+criterion_group!(benches, deserialize);
+criterion_main!(benches);
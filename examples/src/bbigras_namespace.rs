@@ -8,9 +8,9 @@
   namespace = "o: urn:schemas-microsoft-com:office:office",
   namespace = "html: http://www.w3.org/TR/REC-html40"
 )]
-struct Workbook {
+pub struct Workbook {
   #[yaserde(rename = "Worksheet")]
-  worksheet: Worksheet,
+  pub worksheet: Worksheet,
 }
 
 #[derive(YaDeserialize, Default, Debug, PartialEq)]
@@ -21,11 +21,11 @@ struct Workbook {
   namespace = "o: urn:schemas-microsoft-com:office:office",
   namespace = "html: http://www.w3.org/TR/REC-html40"
 )]
-struct Worksheet {
+pub struct Worksheet {
   #[yaserde(rename = "Table")]
-  table: Table,
+  pub table: Table,
   #[yaserde(attribute, rename = "Name", prefix = "ss")]
-  ws_name: String,
+  pub ws_name: String,
 }
 
 #[derive(YaDeserialize, Default, Debug, PartialEq)]
@@ -36,24 +36,24 @@ struct Worksheet {
   namespace = "o: urn:schemas-microsoft-com:office:office",
   namespace = "html: http://www.w3.org/TR/REC-html40"
 )]
-struct Table {
+pub struct Table {
   #[yaserde(attribute, rename = "ExpandedColumnCount", prefix = "ss")]
-  expanded_column_count: u32,
+  pub expanded_column_count: u32,
   #[yaserde(attribute, rename = "ExpandedRowCount", prefix = "ss")]
-  expanded_row_count: u32,
+  pub expanded_row_count: u32,
   #[yaserde(attribute, rename = "FullColumns", prefix = "x")]
-  full_columns: u32,
+  pub full_columns: u32,
   #[yaserde(attribute, rename = "FullRows", prefix = "x")]
-  full_rows: u32,
+  pub full_rows: u32,
   #[yaserde(attribute, rename = "StyleID", prefix = "ss")]
-  style_id: String,
+  pub style_id: String,
   #[yaserde(attribute, rename = "DefaultColumnWidth", prefix = "ss")]
-  default_column_width: f32,
+  pub default_column_width: f32,
   #[yaserde(attribute, rename = "DefaultRowHeight", prefix = "ss")]
-  default_column_height: f32,
+  pub default_column_height: f32,
 
   #[yaserde(rename = "Row")]
-  rows: Vec<Row>,
+  pub rows: Vec<Row>,
 }
 
 #[derive(YaDeserialize, Default, Debug, PartialEq)]
@@ -64,11 +64,11 @@ struct Table {
   namespace = "o: urn:schemas-microsoft-com:office:office",
   namespace = "html: http://www.w3.org/TR/REC-html40"
 )]
-struct Row {
+pub struct Row {
   #[yaserde(attribute, rename = "AutoFitHeight", prefix = "ss")]
-  auto_fit_height: f32,
+  pub auto_fit_height: f32,
   #[yaserde(attribute, rename = "Height", prefix = "ss")]
-  height: f32,
+  pub height: f32,
 }
 
 #[test]
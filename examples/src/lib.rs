@@ -1,7 +1,7 @@
 #[macro_use]
 extern crate yaserde_derive;
 
-mod bbigras_namespace;
-mod boscop;
-mod ln_dom;
-mod svd;
+pub mod bbigras_namespace;
+pub mod boscop;
+pub mod ln_dom;
+pub mod svd;
@@ -2,43 +2,43 @@
 
 #[derive(YaDeserialize, Default, Debug, PartialEq)]
 #[yaserde(root = "DOMSymbolItem")]
-struct Level {
+pub struct Level {
   #[yaserde(attribute)]
-  last_modified: u64,
+  pub last_modified: u64,
   #[yaserde(attribute, rename = "name")]
-  named: String,
-  timeline: Timeline,
+  pub named: String,
+  pub timeline: Timeline,
 }
 
 #[derive(YaDeserialize, Default, Debug, PartialEq)]
-struct Timeline {
+pub struct Timeline {
   #[yaserde(rename = "DOMTimeline")]
-  timeline: DOMTimeline,
+  pub timeline: DOMTimeline,
 }
 
 #[derive(YaDeserialize, Default, Debug, PartialEq)]
-struct DOMTimeline {
+pub struct DOMTimeline {
   #[yaserde(attribute, rename = "name")]
-  named: String,
+  pub named: String,
   #[yaserde(attribute)]
-  current_frame: u64,
+  pub current_frame: u64,
   #[yaserde(attribute)]
-  guides: u64,
-  layers: Layers,
+  pub guides: u64,
+  pub layers: Layers,
 }
 
 #[derive(YaDeserialize, Default, Debug, PartialEq)]
-struct Layers {
+pub struct Layers {
   #[yaserde(rename = "DOMLayer")]
-  dom_layer: Vec<DOMLayer>,
+  pub dom_layer: Vec<DOMLayer>,
 }
 
 #[derive(YaDeserialize, Default, Debug, PartialEq)]
-struct DOMLayer {
+pub struct DOMLayer {
   #[yaserde(attribute, rename = "name")]
-  named: String,
+  pub named: String,
   #[yaserde(attribute)]
-  name2: String,
+  pub name2: String,
 }
 
 #[test]
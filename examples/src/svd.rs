@@ -1,119 +1,78 @@
-use yaserde_derive::YaSerialize;
-
-#[derive(Default, PartialEq, Debug, YaSerialize)]
-struct CpuDef {
-  #[yaserde(child)]
-  name: String,
-  #[yaserde(child)]
-  revision: String,
-  #[yaserde(child)]
-  endian: String, // enum {LE, BE, ME}
-  #[yaserde(child)]
-  mpupresent: bool,
-  #[yaserde(child)]
-  fpupresent: bool,
-  //#[yaserde(child)]
+use yaserde_derive::{YaDeserialize, YaSerialize};
+
+#[derive(Default, PartialEq, Debug, YaSerialize, YaDeserialize)]
+pub struct CpuDef {
+  pub name: String,
+  pub revision: String,
+  pub endian: String, // enum {LE, BE, ME}
+  pub mpupresent: bool,
+  pub fpupresent: bool,
   //nvicpriobits: enum {8, 16, 32, 64, 128},
-  #[yaserde(child)]
-  vendorsystickconfig: bool,
+  pub vendorsystickconfig: bool,
 }
 
-#[derive(Default, PartialEq, Debug, YaSerialize)]
-struct Field {
-  name: String,
-  #[yaserde(child)]
-  description: String,
-  #[yaserde(child)]
-  bitrange: String,
-  #[yaserde(child)]
-  access: String,
+#[derive(Default, PartialEq, Debug, YaSerialize, YaDeserialize)]
+pub struct Field {
+  pub name: String,
+  pub description: String,
+  pub bitrange: String,
+  pub access: String,
 }
 
-#[derive(Default, PartialEq, Debug, YaSerialize)]
-struct Register {
-  #[yaserde(child)]
-  name: String,
-  #[yaserde(child)]
-  description: String,
-  #[yaserde(child)]
-  addressoffset: String,
-  #[yaserde(child)]
-  size: u8,
-  #[yaserde(child)]
-  access: String,
-  #[yaserde(child)]
-  resetvalue: String,
-  #[yaserde(child)]
-  resetmask: String,
-  #[yaserde(child)]
-  fields: Vec<Field>,
+#[derive(Default, PartialEq, Debug, YaSerialize, YaDeserialize)]
+pub struct Register {
+  pub name: String,
+  pub description: String,
+  pub addressoffset: String,
+  pub size: u8,
+  pub access: String,
+  pub resetvalue: String,
+  pub resetmask: String,
+  pub fields: Vec<Field>,
 }
 
-#[derive(Default, PartialEq, Debug, YaSerialize)]
-struct Peripheral {
-  #[yaserde(child)]
-  name: String,
-  #[yaserde(child)]
-  version: String,
-  #[yaserde(child)]
-  description: String,
-  #[yaserde(child)]
-  groupname: String,
-  #[yaserde(child)]
-  baseaddress: String,
-  #[yaserde(child)]
-  size: u8,
-  #[yaserde(child)]
-  access: String,
-  #[yaserde(child)]
-  registers: Vec<Register>,
+#[derive(Default, PartialEq, Debug, YaSerialize, YaDeserialize)]
+pub struct Peripheral {
+  pub name: String,
+  pub version: String,
+  pub description: String,
+  pub groupname: String,
+  pub baseaddress: String,
+  pub size: u8,
+  pub access: String,
+  pub registers: Vec<Register>,
 }
 
-#[derive(Default, PartialEq, Debug, YaSerialize)]
-struct DevAttrs {
-  #[yaserde(child)]
-  vendor: String,
-  #[yaserde(child)]
-  vendorid: String,
-  #[yaserde(child)]
-  name: String,
-  #[yaserde(child)]
-  series: String,
-  #[yaserde(child)]
-  version: String,
-  #[yaserde(child)]
-  description: String,
-  #[yaserde(child)]
-  licensetext: String,
-  #[yaserde(child)]
-  cpu: CpuDef,
-  #[yaserde(child)]
-  addressunitbits: u8,
-  #[yaserde(child)]
-  width: u8,
-  #[yaserde(child)]
-  size: u8,
-  #[yaserde(child)]
-  access: String,
-  #[yaserde(child)]
-  resetvalue: String,
-  #[yaserde(child)]
-  resetmask: String,
-  #[yaserde(child)]
-  peripherals: Vec<Peripheral>,
+#[derive(Default, PartialEq, Debug, YaSerialize, YaDeserialize)]
+pub struct DevAttrs {
+  pub vendor: String,
+  pub vendorid: String,
+  pub name: String,
+  pub series: String,
+  pub version: String,
+  pub description: String,
+  pub licensetext: String,
+  pub cpu: CpuDef,
+  pub addressunitbits: u8,
+  pub width: u8,
+  pub size: u8,
+  pub access: String,
+  pub resetvalue: String,
+  pub resetmask: String,
+  pub peripherals: Vec<Peripheral>,
 }
 
-#[derive(Default, PartialEq, Debug, YaSerialize)]
+#[derive(Default, PartialEq, Debug, YaSerialize, YaDeserialize)]
 #[yaserde(rename = "device")]
-struct Device {
+pub struct Device {
   #[yaserde(attribute)]
-  schemaversion: String,
+  pub schemaversion: String,
+  // Declares the namespace of the document when deserialized, which is not an attribute
+  #[yaserde(attribute, default)]
+  pub xmlns: String,
   #[yaserde(attribute)]
-  xmlns: String,
-  #[yaserde(attribute)]
-  xsnonamespaceschemalocation: String,
-  #[yaserde(child)]
-  devattributes: DevAttrs,
+  pub xsnonamespaceschemalocation: String,
+  pub devattributes: DevAttrs,
 }
 
 #[test]
@@ -0,0 +1,275 @@
+//! XML parsers and emitters the deserializers and serializers are built on.
+//!
+//! The backend is selected by cargo feature: `xml-rs`, enabled by default, or `quick-xml`, which
+//! is used when both are enabled. Both report the same events from a document and write the
+//! same document from events.
+
+use crate::event::{TextPosition, XmlEvent};
+use crate::{de, ser};
+use std::borrow::Cow;
+use std::io::{Read, Write};
+
+#[cfg(feature = "quick-xml")]
+mod quick_xml;
+#[cfg(all(feature = "xml-rs", not(feature = "quick-xml")))]
+mod xml_rs;
+
+#[cfg(feature = "quick-xml")]
+pub use self::quick_xml::{Reader, Writer};
+#[cfg(all(feature = "xml-rs", not(feature = "quick-xml")))]
+pub use self::xml_rs::{Reader, Writer};
+
+#[cfg(not(any(feature = "xml-rs", feature = "quick-xml")))]
+compile_error!("yaserde needs an XML backend, enable the `xml-rs` or the `quick-xml` feature");
+
+/// Parser of a document into events.
+pub trait XmlReader<R: Read>: Sized {
+  fn new(reader: R, config: &de::Config) -> Self;
+
+  /// Next event of the document, then `EndDocument` once it is complete. Texts are trimmed,
+  /// merged and reported as characters according to the config.
+  fn next_event(&mut self) -> Result<XmlEvent, de::Error>;
+
+  /// Position of the last event returned, or of the last error.
+  fn position(&self) -> TextPosition;
+}
+
+/// Emitter of a document from events.
+pub trait XmlWriter<W: Write>: Sized {
+  fn new(writer: W, config: &ser::Config) -> Self;
+
+  /// Write an event, declaring the namespaces of a start element which are not in scope yet.
+  fn write_event(&mut self, event: XmlEvent) -> Result<(), ser::Error>;
+
+  fn into_inner(self) -> W;
+}
+
+/// Limits of the texts, names and attribute values and of the number of attributes, checked on
+/// the bytes of the document as the parser reads them, before it buffers a whole text or tag.
+///
+/// Texts are measured as they are written, with their comments, CDATA sections and processing
+/// instructions, which the parser can merge into them.
+pub(crate) struct RawLimits {
+  max_text_length: usize,
+  max_attributes: usize,
+  state: RawState,
+  /// Bytes of the current text, name or attribute value.
+  length: usize,
+  /// Attributes of the current start tag, without the namespace declarations.
+  attributes: usize,
+  /// Start of the current attribute name, enough to tell a namespace declaration.
+  name: [u8; 6],
+  name_length: usize,
+  /// Whether the next name character starts a new name.
+  after_space: bool,
+  /// Last bytes read, to find the end of the comments and CDATA sections.
+  last: [u8; 2],
+  /// Limit exceeded, reported until the end of the document.
+  exceeded: Option<(de::Limit, usize)>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum RawState {
+  Text,
+  /// After the `<` of a markup.
+  Markup,
+  StartTag,
+  AttributeValue(u8),
+  EndTag,
+  /// After the `<!` of a comment, CDATA section or document type declaration.
+  Declaration,
+  Comment,
+  CData,
+  /// Document type declaration, with the nesting of its internal subset.
+  DocType(usize),
+  ProcessingInstruction,
+}
+
+impl RawLimits {
+  pub(crate) fn new(limits: &de::Limits) -> Self {
+    RawLimits {
+      max_text_length: limits.max_text_length,
+      max_attributes: limits.max_attributes,
+      state: RawState::Text,
+      length: 0,
+      attributes: 0,
+      name: [0; 6],
+      name_length: 0,
+      after_space: false,
+      last: [0; 2],
+      exceeded: None,
+    }
+  }
+
+  /// Limit exceeded by the bytes read so far, if any.
+  pub(crate) fn exceeded(&self) -> Option<(de::Limit, usize)> {
+    self.exceeded
+  }
+
+  /// Check the next `bytes` of the document.
+  pub(crate) fn read(&mut self, bytes: &[u8]) -> Result<(), (de::Limit, usize)> {
+    if let Some(exceeded) = self.exceeded {
+      return Err(exceeded);
+    }
+    for &byte in bytes {
+      if let Err(exceeded) = self.read_byte(byte) {
+        self.exceeded = Some(exceeded);
+        return Err(exceeded);
+      }
+      self.last = [self.last[1], byte];
+    }
+    Ok(())
+  }
+
+  fn read_byte(&mut self, byte: u8) -> Result<(), (de::Limit, usize)> {
+    match self.state {
+      RawState::Text => {
+        if byte == b'<' {
+          self.state = RawState::Markup;
+        } else {
+          self.add_text(1)?;
+        }
+      }
+      RawState::Markup => match byte {
+        b'/' => {
+          self.state = RawState::EndTag;
+          self.length = 0;
+        }
+        b'!' => self.state = RawState::Declaration,
+        b'?' => self.state = RawState::ProcessingInstruction,
+        _ => {
+          self.state = RawState::StartTag;
+          self.length = 0;
+          self.attributes = 0;
+          self.name_length = 0;
+          self.after_space = false;
+          self.add_text(1)?;
+        }
+      },
+      RawState::StartTag => match byte {
+        b'>' => {
+          self.state = RawState::Text;
+          self.length = 0;
+        }
+        b'"' | b'\'' => {
+          self.state = RawState::AttributeValue(byte);
+          self.length = 0;
+        }
+        b'=' | b' ' | b'\t' | b'\r' | b'\n' => {
+          self.after_space = true;
+          self.add_text(1)?;
+        }
+        _ => {
+          if self.after_space {
+            self.after_space = false;
+            self.name_length = 0;
+            self.length = 0;
+          }
+          if let Some(slot) = self.name.get_mut(self.name_length) {
+            *slot = byte;
+          }
+          self.name_length += 1;
+          self.add_text(1)?;
+        }
+      },
+      RawState::AttributeValue(quote) => {
+        if byte == quote {
+          self.state = RawState::StartTag;
+          self.length = 0;
+          self.after_space = true;
+          let name = &self.name[..self.name_length.min(self.name.len())];
+          let declaration = name == b"xmlns" || name == b"xmlns:";
+          if !declaration {
+            self.attributes += 1;
+            if self.attributes > self.max_attributes {
+              return Err((de::Limit::Attributes, self.max_attributes));
+            }
+          }
+        } else {
+          self.add_text(1)?;
+        }
+      }
+      RawState::EndTag => {
+        if byte == b'>' {
+          self.state = RawState::Text;
+          self.length = 0;
+        } else {
+          self.add_text(1)?;
+        }
+      }
+      RawState::Declaration => {
+        self.add_text(1)?;
+        self.state = match byte {
+          b'-' => RawState::Comment,
+          b'[' => RawState::CData,
+          _ => RawState::DocType(0),
+        };
+      }
+      RawState::Comment => {
+        self.add_text(1)?;
+        if byte == b'>' && self.last == *b"--" {
+          self.state = RawState::Text;
+        }
+      }
+      RawState::CData => {
+        self.add_text(1)?;
+        if byte == b'>' && self.last == *b"]]" {
+          self.state = RawState::Text;
+        }
+      }
+      RawState::DocType(nesting) => {
+        self.add_text(1)?;
+        self.state = match byte {
+          b'[' => RawState::DocType(nesting + 1),
+          b']' => RawState::DocType(nesting.saturating_sub(1)),
+          b'>' if nesting == 0 => RawState::Text,
+          _ => RawState::DocType(nesting),
+        };
+      }
+      RawState::ProcessingInstruction => {
+        self.add_text(1)?;
+        if byte == b'>' && self.last[1] == b'?' {
+          self.state = RawState::Text;
+        }
+      }
+    }
+    Ok(())
+  }
+
+  fn add_text(&mut self, length: usize) -> Result<(), (de::Limit, usize)> {
+    self.length += length;
+    if self.length > self.max_text_length {
+      return Err((de::Limit::TextLength, self.max_text_length));
+    }
+    Ok(())
+  }
+}
+
+/// Escape the markup characters of a text content.
+pub(crate) fn escape_text(text: &str) -> Cow<'_, str> {
+  escape(text, |c| match c {
+    '<' => Some("&lt;"),
+    '&' => Some("&amp;"),
+    _ => None,
+  })
+}
+
+pub(crate) fn escape(
+  text: &str,
+  replacement: impl Fn(char) -> Option<&'static str>,
+) -> Cow<'_, str> {
+  match text.find(|c| replacement(c).is_some()) {
+    None => Cow::Borrowed(text),
+    Some(start) => {
+      let mut escaped = String::with_capacity(text.len() + 8);
+      escaped.push_str(&text[..start]);
+      for c in text[start..].chars() {
+        match replacement(c) {
+          Some(replacement) => escaped.push_str(replacement),
+          None => escaped.push(c),
+        }
+      }
+      Cow::Owned(escaped)
+    }
+  }
+}
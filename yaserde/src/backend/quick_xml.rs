@@ -0,0 +1,752 @@
+//! Backend built on the quick-xml crate.
+//!
+//! quick-xml only tokenizes the document, so this backend trims and merges the texts, expands
+//! the entities and resolves the namespaces itself, the same way xml-rs does. Likewise, the
+//! writer declares the namespaces, indents and escapes like the xml-rs emitter.
+
+use super::{escape, escape_text, RawLimits, XmlReader, XmlWriter};
+use crate::de::{self, SyntaxError};
+use crate::event::{
+  Attribute, Namespace, QualifiedName, TextPosition, XmlEvent, NS_NO_PREFIX, NS_XMLNS_PREFIX,
+  NS_XMLNS_URI, NS_XML_PREFIX, NS_XML_URI,
+};
+use crate::ser;
+use quick_xml::events::attributes::AttrError;
+use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Read, Write};
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+const SOURCE_BUFFER_SIZE: usize = 8 * 1024;
+
+pub struct Reader<R: Read> {
+  reader: quick_xml::Reader<Source<R>>,
+  buffer: Vec<u8>,
+  config: de::Config,
+  /// Namespaces declared by the open elements, after the predefined ones.
+  namespaces: Vec<Namespace>,
+  elements: Vec<QualifiedName>,
+  /// Text read since the last markup, and whether it is only whitespace.
+  text: String,
+  text_whitespace: bool,
+  text_start: Location,
+  events: VecDeque<(XmlEvent, Location)>,
+  location: Location,
+  found_root: bool,
+  ended: bool,
+}
+
+impl<R: Read> XmlReader<R> for Reader<R> {
+  fn new(reader: R, config: &de::Config) -> Self {
+    let mut reader = quick_xml::Reader::from_reader(Source::new(reader, &config.limits));
+    let parser_config = reader.config_mut();
+    parser_config.expand_empty_elements = true;
+    parser_config.check_comments = true;
+    // Closing tags are checked with their namespace
+    parser_config.check_end_names = false;
+    parser_config.allow_unmatched_ends = true;
+
+    let mut predefined = Namespace::empty();
+    predefined.put(NS_XML_PREFIX, NS_XML_URI);
+    predefined.put(NS_XMLNS_PREFIX, NS_XMLNS_URI);
+    predefined.put(NS_NO_PREFIX, "");
+
+    Reader {
+      reader,
+      buffer: vec![],
+      config: config.clone(),
+      namespaces: vec![predefined],
+      elements: vec![],
+      text: String::new(),
+      text_whitespace: true,
+      text_start: Location::default(),
+      events: VecDeque::new(),
+      location: Location::default(),
+      found_root: false,
+      ended: false,
+    }
+  }
+
+  fn next_event(&mut self) -> Result<XmlEvent, de::Error> {
+    loop {
+      if let Some((event, location)) = self.events.pop_front() {
+        self.location = location;
+        return Ok(event);
+      }
+      if self.ended {
+        return Ok(XmlEvent::EndDocument);
+      }
+
+      let start = self.next_location();
+      let mut buffer = std::mem::take(&mut self.buffer);
+      buffer.clear();
+      let result = match self.reader.read_event_into(&mut buffer) {
+        Ok(event) => self.handle(event, start),
+        Err(error) => Err(SyntaxError::new(&error).with_source(error)),
+      };
+      self.buffer = buffer;
+
+      if let Some((limit, max)) = self.reader.get_ref().limits.exceeded() {
+        self.location = start;
+        return Err(de::Error::limit_exceeded(limit, max));
+      }
+
+      if let Err(error) = result {
+        self.location = start;
+        return Err(error.into());
+      }
+    }
+  }
+
+  fn position(&self) -> TextPosition {
+    self.location.position
+  }
+}
+
+impl<R: Read> Reader<R> {
+  /// Location of the start of the next event.
+  fn next_location(&self) -> Location {
+    let source = self.reader.get_ref();
+    // The `<` starting a markup can be consumed already
+    let behind = source.consumed - self.reader.buffer_position();
+    Location {
+      position: TextPosition {
+        row: source.location.position.row,
+        column: source.location.position.column.saturating_sub(behind),
+      },
+      offset: source.location.offset - behind,
+    }
+  }
+
+  fn handle(&mut self, event: Event, start: Location) -> Result<(), SyntaxError> {
+    match event {
+      Event::Start(element) => {
+        self.flush_text();
+        self.start_element(&element, start)?;
+      }
+      Event::End(element) => {
+        self.flush_text();
+        self.end_element(&element, start)?;
+      }
+      Event::Empty(_) => unreachable!("empty elements are expanded"),
+      Event::Text(text) => {
+        let text = decode(&text)?;
+        if self.elements.is_empty() {
+          if let Some(c) = text.chars().find(|c| !is_whitespace(*c)) {
+            return Err(SyntaxError::new(format!(
+              "Unexpected characters outside the root element: {}",
+              c
+            )));
+          }
+        } else {
+          self.push_text(text, start)?;
+        }
+      }
+      Event::CData(data) => {
+        let data = decode(&data)?;
+        if self.config.coalesce_characters && self.config.cdata_to_characters {
+          self.append_text(data, start);
+        } else {
+          self.flush_text();
+          if self.config.cdata_to_characters {
+            self.append_text(data, start);
+          } else {
+            self
+              .events
+              .push_back((XmlEvent::CData(data.to_string()), start));
+          }
+        }
+      }
+      Event::Comment(comment) => {
+        if !(self.config.coalesce_characters && self.config.ignore_comments) {
+          self.flush_text();
+          if !self.config.ignore_comments {
+            let comment = decode(&comment)?;
+            self
+              .events
+              .push_back((XmlEvent::Comment(comment.to_string()), start));
+          }
+        }
+      }
+      Event::Decl(_) | Event::PI(_) | Event::DocType(_) => self.flush_text(),
+      Event::Eof => {
+        self.flush_text();
+        if !self.elements.is_empty() {
+          return Err(SyntaxError::new(
+            "Unexpected end of stream: still inside the root element",
+          ));
+        }
+        if !self.found_root {
+          return Err(SyntaxError::new(
+            "Unexpected end of stream: no root element found",
+          ));
+        }
+        self.ended = true;
+        self.events.push_back((XmlEvent::EndDocument, start));
+      }
+    }
+
+    Ok(())
+  }
+
+  fn start_element(&mut self, element: &BytesStart, start: Location) -> Result<(), SyntaxError> {
+    let mut declared = Namespace::empty();
+    let mut attributes: Vec<Attribute> = vec![];
+
+    for attribute in element.attributes() {
+      let attribute = attribute.map_err(|error| match error {
+        AttrError::Duplicated(position, _) => {
+          let name = element[position..]
+            .split(|&byte| byte == b'=' || byte.is_ascii_whitespace())
+            .next()
+            .unwrap_or_default();
+          SyntaxError::new(format!(
+            "Attribute '{}' is redefined",
+            String::from_utf8_lossy(name)
+          ))
+          .with_source(error)
+        }
+        error => SyntaxError::new(&error).with_source(error),
+      })?;
+      let name = decode(attribute.key.as_ref())?;
+      let value = unescape(decode(&attribute.value)?, &self.config)?.into_owned();
+
+      if name == NS_XMLNS_PREFIX {
+        declared.put(NS_NO_PREFIX, value);
+      } else if let Some(prefix) = name.strip_prefix("xmlns:") {
+        declared.put(prefix, value);
+      } else {
+        attributes.push(Attribute::new(name, value));
+      }
+    }
+    self.namespaces.push(declared);
+
+    let mut name = QualifiedName::from(decode(element.name().as_ref())?);
+    name.namespace = self
+      .resolve(name.prefix.as_deref().unwrap_or(NS_NO_PREFIX))
+      .ok_or_else(|| SyntaxError::new(format!("Element {} prefix is unbound", name)))?;
+
+    for attribute in attributes.iter_mut() {
+      if let Some(ref prefix) = attribute.name.prefix {
+        attribute.name.namespace = self.resolve(prefix).ok_or_else(|| {
+          SyntaxError::new(format!("Attribute {} prefix is unbound", attribute.name))
+        })?;
+      }
+    }
+
+    let mut namespace = Namespace::empty();
+    for declared in &self.namespaces {
+      for (prefix, uri) in declared.iter() {
+        namespace.0.insert(prefix.to_string(), uri.to_string());
+      }
+    }
+
+    self.found_root = true;
+    self.elements.push(name.clone());
+    self.events.push_back((
+      XmlEvent::StartElement {
+        name,
+        attributes,
+        namespace,
+      },
+      start,
+    ));
+    Ok(())
+  }
+
+  fn end_element(&mut self, element: &BytesEnd, start: Location) -> Result<(), SyntaxError> {
+    let mut name = QualifiedName::from(decode(element.name().as_ref())?);
+    name.namespace = self
+      .resolve(name.prefix.as_deref().unwrap_or(NS_NO_PREFIX))
+      .ok_or_else(|| SyntaxError::new(format!("Element {} prefix is unbound", name)))?;
+
+    match self.elements.pop() {
+      Some(ref open) if *open == name => {}
+      Some(open) => {
+        return Err(SyntaxError::new(format!(
+          "Unexpected closing tag: {}, expected {}",
+          name, open
+        )))
+      }
+      None => return Err(SyntaxError::new("Unexpected token: </")),
+    }
+
+    self.namespaces.pop();
+    self
+      .events
+      .push_back((XmlEvent::EndElement { name }, start));
+    Ok(())
+  }
+
+  /// Namespace bound to `prefix`, `Some(None)` for an empty default namespace.
+  fn resolve(&self, prefix: &str) -> Option<Option<String>> {
+    self
+      .namespaces
+      .iter()
+      .rev()
+      .find_map(|declared| declared.get(prefix))
+      .map(|uri| match uri {
+        "" => None,
+        uri => Some(uri.to_string()),
+      })
+  }
+
+  /// Add escaped text to the current text, which starts at `start`.
+  fn push_text(&mut self, text: &str, start: Location) -> Result<(), SyntaxError> {
+    let mut text = text;
+    let mut start = start;
+    if self.config.trim_whitespace && self.text.is_empty() {
+      let trimmed = text.trim_start_matches(is_whitespace);
+      start.advance(&text.as_bytes()[..text.len() - trimmed.len()]);
+      text = trimmed;
+    }
+
+    let text = unescape(text, &self.config)?;
+    self.append_text(&text, start);
+    Ok(())
+  }
+
+  fn append_text(&mut self, text: &str, start: Location) {
+    if text.is_empty() {
+      return;
+    }
+    if self.text.is_empty() {
+      self.text_start = start;
+    }
+    self.text_whitespace &= text.chars().all(is_whitespace);
+    self.text.push_str(text);
+  }
+
+  /// Report the current text, before a markup.
+  fn flush_text(&mut self) {
+    if self.text.is_empty() {
+      return;
+    }
+
+    let text = std::mem::take(&mut self.text);
+    let whitespace = std::mem::replace(&mut self.text_whitespace, true);
+    let event = if whitespace && self.config.trim_whitespace {
+      return;
+    } else if whitespace && !self.config.whitespace_to_characters {
+      XmlEvent::Whitespace(text)
+    } else if self.config.trim_whitespace {
+      XmlEvent::Characters(text.trim_matches(is_whitespace).to_string())
+    } else {
+      XmlEvent::Characters(text)
+    };
+    self.events.push_back((event, self.text_start));
+  }
+}
+
+fn is_whitespace(c: char) -> bool {
+  matches!(c, ' ' | '\t' | '\n' | '\r')
+}
+
+fn decode(bytes: &[u8]) -> Result<&str, SyntaxError> {
+  std::str::from_utf8(bytes).map_err(|error| SyntaxError::new(error).with_source(error))
+}
+
+/// Replace the references to entities and characters of a text or of an attribute value.
+fn unescape<'a>(text: &'a str, config: &de::Config) -> Result<Cow<'a, str>, SyntaxError> {
+  if !text.contains('&') {
+    return Ok(Cow::Borrowed(text));
+  }
+
+  let mut unescaped = String::with_capacity(text.len());
+  let mut rest = text;
+  while let Some(start) = rest.find('&') {
+    unescaped.push_str(&rest[..start]);
+    rest = &rest[start + 1..];
+
+    let end = rest
+      .find(';')
+      .ok_or_else(|| SyntaxError::new(format!("Unexpected token inside an entity: {}", rest)))?;
+    unescaped.push_str(&resolve_entity(&rest[..end], config)?);
+    rest = &rest[end + 1..];
+  }
+  unescaped.push_str(rest);
+
+  Ok(Cow::Owned(unescaped))
+}
+
+fn resolve_entity<'a>(name: &str, config: &'a de::Config) -> Result<Cow<'a, str>, SyntaxError> {
+  let value = match name {
+    "lt" => "<",
+    "gt" => ">",
+    "amp" => "&",
+    "apos" => "'",
+    "quot" => "\"",
+    "" => return Err(SyntaxError::new("Encountered empty entity")),
+    _ if name.len() > 2 && name.starts_with("#x") => {
+      return resolve_character(name, &name[2..], 16, config).map(Cow::Owned)
+    }
+    _ if name.len() > 1 && name.starts_with('#') => {
+      return resolve_character(name, &name[1..], 10, config).map(Cow::Owned)
+    }
+    _ => match config.extra_entities.get(name) {
+      Some(value) => return Ok(Cow::Borrowed(value)),
+      None => return Err(SyntaxError::new(format!("Unexpected entity: {}", name))),
+    },
+  };
+
+  Ok(Cow::Borrowed(value))
+}
+
+fn resolve_character(
+  name: &str,
+  number: &str,
+  radix: u32,
+  config: &de::Config,
+) -> Result<String, SyntaxError> {
+  if number == "0" {
+    return Err(SyntaxError::new("Null character entity is not allowed"));
+  }
+
+  let code = u32::from_str_radix(number, radix).ok();
+  let character = if config.replace_unknown_entity_references {
+    code.map(|code| char::from_u32(code).unwrap_or('\u{fffd}'))
+  } else {
+    code.and_then(char::from_u32)
+  };
+
+  character.map(String::from).ok_or_else(|| {
+    let kind = if radix == 16 {
+      "hexadecimal"
+    } else {
+      "decimal"
+    };
+    SyntaxError::new(format!(
+      "Invalid {} character number in an entity: {}",
+      kind, name
+    ))
+  })
+}
+
+/// Buffered source of the parser, tracking the position of the consumed bytes.
+struct Source<R: Read> {
+  reader: R,
+  buffer: Vec<u8>,
+  start: usize,
+  end: usize,
+  /// Bytes consumed, without the byte order mark.
+  consumed: u64,
+  location: Location,
+  /// Checked on the bytes consumed, no more is read once they are exceeded.
+  limits: RawLimits,
+}
+
+impl<R: Read> Source<R> {
+  fn new(reader: R, limits: &de::Limits) -> Self {
+    Source {
+      reader,
+      buffer: vec![0; SOURCE_BUFFER_SIZE],
+      start: 0,
+      end: 0,
+      consumed: 0,
+      location: Location::default(),
+      limits: RawLimits::new(limits),
+    }
+  }
+}
+
+impl<R: Read> Read for Source<R> {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    let available = self.fill_buf()?;
+    let length = available.len().min(buf.len());
+    buf[..length].copy_from_slice(&available[..length]);
+    self.consume(length);
+    Ok(length)
+  }
+}
+
+impl<R: Read> BufRead for Source<R> {
+  fn fill_buf(&mut self) -> io::Result<&[u8]> {
+    if self.limits.exceeded().is_some() {
+      return Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "document limit exceeded",
+      ));
+    }
+    if self.start == self.end {
+      self.end = self.reader.read(&mut self.buffer)?;
+      self.start = 0;
+    }
+    Ok(&self.buffer[self.start..self.end])
+  }
+
+  fn consume(&mut self, amount: usize) {
+    let consumed = &self.buffer[self.start..self.start + amount];
+    // The parser skips the byte order mark without counting it
+    if self.location.offset == 0 && consumed == UTF8_BOM {
+      self.location.offset += amount as u64;
+    } else {
+      // Recorded, then reported by the next fill of the buffer
+      let _ = self.limits.read(consumed);
+      self.location.advance(consumed);
+      self.consumed += amount as u64;
+    }
+    self.start += amount;
+  }
+}
+
+/// Where an event starts in the document.
+#[derive(Clone, Copy, Default)]
+struct Location {
+  position: TextPosition,
+  /// Offset in bytes, with the byte order mark.
+  offset: u64,
+}
+
+impl Location {
+  /// Move after `bytes`, counting columns in characters.
+  fn advance(&mut self, bytes: &[u8]) {
+    for byte in bytes {
+      if *byte == b'\n' {
+        self.position.row += 1;
+        self.position.column = 0;
+      } else if byte & 0xC0 != 0x80 {
+        self.position.column += 1;
+      }
+    }
+    self.offset += bytes.len() as u64;
+  }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+/// Last thing written in an element, which decides of the indentation.
+enum Wrote {
+  Nothing,
+  Markup,
+  Text,
+}
+
+pub struct Writer<W: Write> {
+  writer: quick_xml::Writer<W>,
+  perform_indent: bool,
+  write_document_declaration: bool,
+  indent_string: String,
+  /// Namespaces declared by the open elements.
+  namespaces: Vec<Namespace>,
+  elements: Vec<String>,
+  indent_level: usize,
+  indent_stack: Vec<Wrote>,
+  document_started: bool,
+  /// Whether the last start tag is left open, to be written as an empty element if the next
+  /// event is its end.
+  pending: bool,
+}
+
+impl<W: Write> XmlWriter<W> for Writer<W> {
+  fn new(writer: W, config: &ser::Config) -> Self {
+    Writer {
+      writer: quick_xml::Writer::new(writer),
+      perform_indent: config.perform_indent,
+      write_document_declaration: config.write_document_declaration,
+      indent_string: config
+        .indent_string
+        .clone()
+        .unwrap_or_else(|| "  ".to_string()),
+      namespaces: vec![],
+      elements: vec![],
+      indent_level: 0,
+      indent_stack: vec![Wrote::Nothing],
+      document_started: false,
+      pending: false,
+    }
+  }
+
+  fn write_event(&mut self, event: XmlEvent) -> Result<(), ser::Error> {
+    match event {
+      XmlEvent::StartElement {
+        name,
+        attributes,
+        namespace,
+      } => self.start_element(&name, &attributes, &namespace),
+      XmlEvent::EndElement { .. } => self.end_element(),
+      XmlEvent::Characters(text) | XmlEvent::Whitespace(text) | XmlEvent::CData(text) => {
+        self.characters(&text)
+      }
+      XmlEvent::Comment(comment) => self.comment(&comment),
+      XmlEvent::EndDocument => Ok(()),
+    }
+  }
+
+  fn into_inner(self) -> W {
+    self.writer.into_inner()
+  }
+}
+
+impl<W: Write> Writer<W> {
+  fn start_element(
+    &mut self,
+    name: &QualifiedName,
+    attributes: &[Attribute],
+    namespace: &Namespace,
+  ) -> Result<(), ser::Error> {
+    let mut declared = Namespace::empty();
+    for (prefix, uri) in namespace.iter() {
+      if !self
+        .namespaces
+        .iter()
+        .any(|namespace| namespace.get(prefix) == Some(uri))
+      {
+        declared.put(prefix, uri);
+      }
+    }
+
+    let name = name.to_repr();
+    let mut content = name.clone();
+    for (prefix, uri) in declared.iter() {
+      match prefix {
+        NS_XMLNS_PREFIX | NS_XML_PREFIX => {}
+        NS_NO_PREFIX if uri.is_empty() => {}
+        NS_NO_PREFIX => content += &format!(" xmlns=\"{}\"", uri),
+        prefix => content += &format!(" xmlns:{}=\"{}\"", prefix, uri),
+      }
+    }
+    for attribute in attributes {
+      content += &format!(
+        " {}=\"{}\"",
+        attribute.name.to_repr(),
+        escape_attribute(&attribute.value)
+      );
+    }
+    self.namespaces.push(declared);
+
+    self.start_document()?;
+    self.close_start_tag()?;
+    self.before_markup()?;
+    self.indent_stack.push(Wrote::Nothing);
+    self.set_indent(Wrote::Markup);
+    self.indent_level += 1;
+
+    self.writer.get_mut().write_all(b"<")?;
+    self.writer.get_mut().write_all(content.as_bytes())?;
+    self.pending = true;
+    self.elements.push(name);
+    Ok(())
+  }
+
+  fn end_element(&mut self) -> Result<(), ser::Error> {
+    let name = self.elements.pop();
+    self.namespaces.pop();
+    let name =
+      name.ok_or_else(|| ser::Error::Emitter("last element name is not available".to_string()))?;
+
+    if self.pending {
+      self.pending = false;
+      self.writer.get_mut().write_all(b" />")?;
+    } else {
+      if self.perform_indent && self.indent_level > 0 && self.wrote(Wrote::Markup) {
+        self.write_newline(self.indent_level - 1)?;
+      }
+      self.writer.write_event(Event::End(BytesEnd::new(name)))?;
+    }
+
+    if self.indent_level > 0 {
+      self.indent_level -= 1;
+      self.indent_stack.pop();
+    }
+    self.set_indent(Wrote::Markup);
+    Ok(())
+  }
+
+  fn characters(&mut self, text: &str) -> Result<(), ser::Error> {
+    self.start_document()?;
+    self.close_start_tag()?;
+    self
+      .writer
+      .write_event(Event::Text(BytesText::from_escaped(escape_text(text))))?;
+    self.set_indent(Wrote::Text);
+    Ok(())
+  }
+
+  fn comment(&mut self, comment: &str) -> Result<(), ser::Error> {
+    self.close_start_tag()?;
+    self.before_markup()?;
+
+    let mut content = String::with_capacity(comment.len() + 2);
+    if !comment.starts_with(char::is_whitespace) {
+      content.push(' ');
+    }
+    content.push_str(comment);
+    if !comment.ends_with(char::is_whitespace) {
+      content.push(' ');
+    }
+    self
+      .writer
+      .write_event(Event::Comment(BytesText::from_escaped(content)))?;
+
+    self.set_indent(Wrote::Markup);
+    Ok(())
+  }
+
+  fn start_document(&mut self) -> Result<(), ser::Error> {
+    if self.document_started || !self.write_document_declaration {
+      return Ok(());
+    }
+    self.document_started = true;
+
+    self.before_markup()?;
+    self
+      .writer
+      .write_event(Event::Decl(BytesDecl::new("1.0", Some("utf-8"), None)))?;
+    self.set_indent(Wrote::Markup);
+    Ok(())
+  }
+
+  /// Close the start tag left open, the next event not being its end.
+  fn close_start_tag(&mut self) -> Result<(), ser::Error> {
+    if self.pending {
+      self.pending = false;
+      self.writer.get_mut().write_all(b">")?;
+    }
+    Ok(())
+  }
+
+  fn before_markup(&mut self) -> Result<(), ser::Error> {
+    if self.perform_indent
+      && !self.wrote(Wrote::Text)
+      && (self.indent_level > 0 || self.wrote(Wrote::Markup))
+    {
+      self.write_newline(self.indent_level)?;
+      if self.indent_level > 0 && !self.indent_string.is_empty() {
+        self.set_indent(Wrote::Markup);
+      }
+    }
+    Ok(())
+  }
+
+  fn write_newline(&mut self, level: usize) -> Result<(), ser::Error> {
+    let newline = format!("\n{}", self.indent_string.repeat(level));
+    self
+      .writer
+      .write_event(Event::Text(BytesText::from_escaped(newline)))?;
+    Ok(())
+  }
+
+  fn wrote(&self, indent: Wrote) -> bool {
+    self.indent_stack.last() == Some(&indent)
+  }
+
+  fn set_indent(&mut self, indent: Wrote) {
+    if let Some(last) = self.indent_stack.last_mut() {
+      *last = indent;
+    }
+  }
+}
+
+/// Escape the markup characters and the line breaks of an attribute value.
+fn escape_attribute(value: &str) -> Cow<'_, str> {
+  escape(value, |c| match c {
+    '<' => Some("&lt;"),
+    '>' => Some("&gt;"),
+    '"' => Some("&quot;"),
+    '\'' => Some("&apos;"),
+    '&' => Some("&amp;"),
+    '\n' => Some("&#xA;"),
+    '\r' => Some("&#xD;"),
+    _ => None,
+  })
+}
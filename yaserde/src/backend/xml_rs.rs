@@ -0,0 +1,186 @@
+//! Backend built on the xml-rs crate.
+
+use super::{RawLimits, XmlReader, XmlWriter};
+use crate::de::{self, SyntaxError};
+use crate::event::{Attribute, Namespace, QualifiedName, TextPosition, XmlEvent};
+use crate::ser;
+use std::borrow::Cow;
+use std::io::{self, Read, Write};
+use xml::common::Position;
+use xml::name::{Name, OwnedName};
+use xml::reader::{self, EventReader, ParserConfig};
+use xml::writer::{self, EmitterConfig, EventWriter};
+
+pub struct Reader<R: Read> {
+  reader: EventReader<Limited<R>>,
+  position: TextPosition,
+}
+
+impl<R: Read> XmlReader<R> for Reader<R> {
+  fn new(reader: R, config: &de::Config) -> Self {
+    let mut parser_config = ParserConfig::new()
+      .trim_whitespace(config.trim_whitespace)
+      .whitespace_to_characters(config.whitespace_to_characters)
+      .cdata_to_characters(config.cdata_to_characters)
+      .ignore_comments(config.ignore_comments)
+      .coalesce_characters(config.coalesce_characters)
+      .replace_unknown_entity_references(config.replace_unknown_entity_references);
+    parser_config.extra_entities = config.extra_entities.clone();
+
+    Reader {
+      reader: EventReader::new_with_config(
+        Limited {
+          reader,
+          limits: RawLimits::new(&config.limits),
+        },
+        parser_config,
+      ),
+      position: TextPosition::new(),
+    }
+  }
+
+  fn next_event(&mut self) -> Result<XmlEvent, de::Error> {
+    loop {
+      let next = self.reader.next();
+      let position = match &next {
+        Ok(_) => self.reader.position(),
+        Err(error) => error.position(),
+      };
+      self.position = TextPosition {
+        row: position.row,
+        column: position.column,
+      };
+
+      if let Some((limit, max)) = self.reader.source().limits.exceeded() {
+        return Err(de::Error::limit_exceeded(limit, max));
+      }
+
+      let event = match next.map_err(|error| SyntaxError::new(error.msg()).with_source(error))? {
+        reader::XmlEvent::StartElement {
+          name,
+          attributes,
+          namespace,
+        } => XmlEvent::StartElement {
+          name: qualified_name(name),
+          attributes: attributes
+            .into_iter()
+            .map(|attribute| Attribute {
+              name: qualified_name(attribute.name),
+              value: attribute.value,
+            })
+            .collect(),
+          namespace: Namespace(namespace.0),
+        },
+        reader::XmlEvent::EndElement { name } => XmlEvent::EndElement {
+          name: qualified_name(name),
+        },
+        reader::XmlEvent::Characters(text) => XmlEvent::Characters(text),
+        reader::XmlEvent::CData(text) => XmlEvent::CData(text),
+        reader::XmlEvent::Comment(text) => XmlEvent::Comment(text),
+        reader::XmlEvent::Whitespace(text) => XmlEvent::Whitespace(text),
+        reader::XmlEvent::EndDocument => XmlEvent::EndDocument,
+        reader::XmlEvent::StartDocument { .. } | reader::XmlEvent::ProcessingInstruction { .. } => {
+          continue
+        }
+      };
+      return Ok(event);
+    }
+  }
+
+  fn position(&self) -> TextPosition {
+    self.position
+  }
+}
+
+/// Source failing once the bytes read from the document exceed the limits.
+struct Limited<R: Read> {
+  reader: R,
+  limits: RawLimits,
+}
+
+impl<R: Read> Read for Limited<R> {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    let length = self.reader.read(buf)?;
+    self
+      .limits
+      .read(&buf[..length])
+      .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "document limit exceeded"))?;
+    Ok(length)
+  }
+}
+
+fn qualified_name(name: OwnedName) -> QualifiedName {
+  QualifiedName {
+    local_name: name.local_name,
+    namespace: name.namespace,
+    prefix: name.prefix,
+  }
+}
+
+pub struct Writer<W: Write> {
+  writer: EventWriter<W>,
+}
+
+impl<W: Write> XmlWriter<W> for Writer<W> {
+  fn new(writer: W, config: &ser::Config) -> Self {
+    let mut emitter_config = EmitterConfig::new()
+      .cdata_to_characters(true)
+      .perform_indent(config.perform_indent)
+      .write_document_declaration(config.write_document_declaration);
+
+    if let Some(indent_string_value) = &config.indent_string {
+      emitter_config = emitter_config.indent_string(indent_string_value.clone());
+    }
+
+    Writer {
+      writer: EventWriter::new_with_config(writer, emitter_config),
+    }
+  }
+
+  fn write_event(&mut self, event: XmlEvent) -> Result<(), ser::Error> {
+    let result = match event {
+      XmlEvent::StartElement {
+        ref name,
+        ref attributes,
+        ref namespace,
+      } => {
+        let attributes: Vec<xml::attribute::Attribute> = attributes
+          .iter()
+          .map(|attribute| xml::attribute::Attribute {
+            name: borrow_name(&attribute.name),
+            value: &attribute.value,
+          })
+          .collect();
+
+        self.writer.write(writer::XmlEvent::StartElement {
+          name: borrow_name(name),
+          attributes: Cow::Owned(attributes),
+          namespace: Cow::Owned(xml::namespace::Namespace(namespace.0.clone())),
+        })
+      }
+      XmlEvent::EndElement { .. } => self
+        .writer
+        .write(writer::XmlEvent::EndElement { name: None }),
+      XmlEvent::Characters(ref text) | XmlEvent::Whitespace(ref text) => {
+        self.writer.write(writer::XmlEvent::Characters(text))
+      }
+      XmlEvent::CData(ref text) => self.writer.write(writer::XmlEvent::CData(text)),
+      XmlEvent::Comment(ref text) => self.writer.write(writer::XmlEvent::Comment(text)),
+      XmlEvent::EndDocument => Ok(()),
+    };
+
+    result.map_err(ser::Error::from)
+  }
+
+  fn into_inner(self) -> W {
+    self.writer.into_inner()
+  }
+}
+
+fn borrow_name(name: &QualifiedName) -> Name<'_> {
+  Name {
+    local_name: &name.local_name,
+    namespace: name.namespace.as_deref(),
+    prefix: name.prefix.as_deref(),
+  }
+}
@@ -0,0 +1,163 @@
+//! Streaming deserialization of the children of an element.
+//!
+
+use super::{Config, Deserializer, Error, ErrorKind};
+use crate::event::XmlEvent;
+use crate::YaDeserializeOwned;
+use std::io::Read;
+use std::marker::PhantomData;
+
+/// Deserialize the children of the first element named `parent` one at a time, e.g. the
+/// `<record>` elements of `<records><record/>...</records>`, without holding the whole document.
+///
+/// Only the children named as the element of `T` are read, the others are skipped; see
+/// [`Children::child`] to read another name. An invalid child is reported as an error and the
+/// iteration goes on with the next one. It stops after the end of `parent`, or after an error of
+/// the document itself: a syntax or I/O error, an exceeded limit, or a missing `parent`.
+pub fn iter_children<R: Read, T: YaDeserializeOwned>(reader: R, parent: &str) -> Children<R, T> {
+  iter_children_with_config(reader, parent, &Config::default())
+}
+
+/// Same as [`iter_children`], with the options of the deserializer. The `max_elements` limit
+/// applies to each child instead of the whole document.
+pub fn iter_children_with_config<R: Read, T: YaDeserializeOwned>(
+  reader: R,
+  parent: &str,
+  config: &Config,
+) -> Children<R, T> {
+  Children {
+    deserializer: Deserializer::new_from_reader_with_config(reader, config),
+    parent: parent.to_string(),
+    child_name: T::flattened().map(|flattened| local_name(flattened.root()).to_string()),
+    started: false,
+    done: false,
+    child: PhantomData,
+  }
+}
+
+/// Iterator returned by [`iter_children`].
+pub struct Children<R: Read, T> {
+  deserializer: Deserializer<'static, R>,
+  parent: String,
+  child_name: Option<String>,
+  started: bool,
+  done: bool,
+  child: PhantomData<fn() -> T>,
+}
+
+fn local_name(name: &str) -> &str {
+  name.rsplit(':').next().unwrap_or(name)
+}
+
+/// Whether the document can't be read after this error, or only the child raising it is invalid.
+fn is_fatal(error: &Error) -> bool {
+  matches!(
+    error.kind(),
+    ErrorKind::Syntax(_)
+      | ErrorKind::Io(_)
+      | ErrorKind::UnexpectedEof
+      | ErrorKind::LimitExceeded { .. }
+  )
+}
+
+impl<R: Read, T> Children<R, T> {
+  /// Read the children named `name` instead of the element name of `T`, e.g. for a type without
+  /// `rename` or `root`, or for any name with `None`.
+  pub fn child(mut self, name: Option<&str>) -> Self {
+    self.child_name = name.map(str::to_string);
+    self
+  }
+}
+
+impl<R: Read, T: YaDeserializeOwned> Children<R, T> {
+  /// Skip the document up to the start of the parent element.
+  fn start(&mut self) -> Result<(), Error> {
+    loop {
+      match self.deserializer.next_event()? {
+        XmlEvent::StartElement { name, .. } if name.local_name == self.parent => return Ok(()),
+        XmlEvent::EndDocument => {
+          return Err(
+            ErrorKind::MissingField {
+              field: format!("element <{}>", self.parent),
+              container: "the document".to_string(),
+            }
+            .into(),
+          )
+        }
+        _ => {}
+      }
+    }
+  }
+
+  /// Read the next child, or the error of an invalid child. An error of the result ends the
+  /// iteration.
+  fn next_child(&mut self) -> Result<Option<Result<T, Error>>, Error> {
+    if !self.started {
+      self.start()?;
+      self.started = true;
+    }
+
+    let depth = self.deserializer.depth();
+
+    loop {
+      match self.deserializer.peek()? {
+        XmlEvent::StartElement { name, .. } => {
+          let name = name.clone();
+          self.deserializer.elements = 0;
+
+          if let Some(ref child_name) = self.child_name {
+            if name.local_name != *child_name {
+              self.deserializer.next_event()?;
+              self.deserializer.skip_element(|_| {})?;
+              continue;
+            }
+          }
+
+          match T::deserialize(&mut self.deserializer)
+            .and_then(|child| self.deserializer.expect_end_element(&name).map(|()| child))
+          {
+            Ok(child) => return Ok(Some(Ok(child))),
+            Err(error) if is_fatal(&error) => return Err(error),
+            Err(error) => {
+              // Resync to the end of the invalid child, to read the next one
+              let error = self.deserializer.locate(error);
+              if self.deserializer.elements == 0 {
+                self.deserializer.next_event()?;
+              }
+              while self.deserializer.depth() > depth {
+                self.deserializer.next_event()?;
+              }
+              return Ok(Some(Err(error)));
+            }
+          }
+        }
+        XmlEvent::EndElement { .. } | XmlEvent::EndDocument => return Ok(None),
+        _ => {
+          self.deserializer.next_event()?;
+        }
+      }
+    }
+  }
+}
+
+impl<R: Read, T: YaDeserializeOwned> Iterator for Children<R, T> {
+  type Item = Result<T, Error>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.done {
+      return None;
+    }
+
+    match self.next_child() {
+      Ok(Some(child)) => Some(child),
+      Ok(None) => {
+        self.done = true;
+        None
+      }
+      Err(error) => {
+        self.done = true;
+        Some(Err(self.deserializer.locate(error)))
+      }
+    }
+  }
+}
@@ -0,0 +1,347 @@
+//! Deserialization errors.
+//!
+
+use crate::event::TextPosition;
+use std::error::Error as StdError;
+use std::fmt;
+use std::io;
+
+/// The different kinds of failure that can occur while deserializing.
+#[derive(Debug)]
+pub enum ErrorKind {
+  /// The XML parser rejected the document.
+  Syntax(SyntaxError),
+  /// An I/O error occurred while reading the source.
+  Io(io::Error),
+  /// An element was found where another one was expected.
+  UnexpectedElement {
+    expected: Option<String>,
+    found: String,
+  },
+  /// The document element doesn't match the `root` of the deserialized type.
+  UnexpectedRoot {
+    expected: Vec<String>,
+    found: String,
+  },
+  /// An XML event was found that cannot be handled at this position.
+  UnexpectedEvent(String),
+  /// The document ended before the value was complete.
+  UnexpectedEof,
+  /// A required element or attribute is missing.
+  MissingField { field: String, container: String },
+  /// An element, attribute or text is not expected by a container denying unknown fields.
+  UnknownField { field: String, container: String },
+  /// A visitor does not accept this kind of value.
+  InvalidType { found: &'static str, value: String },
+  /// A text or attribute value could not be converted to the expected type.
+  InvalidValue {
+    value: String,
+    expected: String,
+    reason: String,
+  },
+  /// An element is bound to a namespace that is not declared for it.
+  BadNamespace { element: String, namespace: String },
+  /// The document exceeds one of the [`Limits`](super::Limits) of the deserializer.
+  LimitExceeded { limit: Limit, max: usize },
+  /// A value deserialized as `&str` is not in the document as is, e.g. it contains entities, or
+  /// the document is not read from a string.
+  NotBorrowed(String),
+  /// Any other error, usually raised by a hand-written implementation.
+  Custom(String),
+}
+
+impl fmt::Display for ErrorKind {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ErrorKind::Syntax(error) => write!(f, "{}", error),
+      ErrorKind::Io(error) => write!(f, "{}", error),
+      ErrorKind::UnexpectedElement {
+        expected: Some(expected),
+        found,
+      } => write!(f, "unexpected element <{}>, expected <{}>", found, expected),
+      ErrorKind::UnexpectedElement {
+        expected: None,
+        found,
+      } => write!(f, "unexpected element <{}>", found),
+      ErrorKind::UnexpectedRoot { expected, found } => write!(
+        f,
+        "unexpected root element <{}>, expected {}",
+        found,
+        expected
+          .iter()
+          .map(|name| format!("<{}>", name))
+          .collect::<Vec<_>>()
+          .join(" or ")
+      ),
+      ErrorKind::UnexpectedEvent(event) => write!(f, "unknown event {}", event),
+      ErrorKind::UnexpectedEof => write!(f, "End of document, missing some content ?"),
+      ErrorKind::MissingField { field, container } => {
+        write!(f, "missing {} in {}", field, container)
+      }
+      ErrorKind::UnknownField { field, container } => {
+        write!(f, "unknown {} in {}", field, container)
+      }
+      ErrorKind::InvalidType { found, value } => write!(f, "Unexpected {} {:?}", found, value),
+      ErrorKind::InvalidValue {
+        value,
+        expected,
+        reason,
+      } => write!(f, "invalid value {:?} for {}: {}", value, expected, reason),
+      ErrorKind::BadNamespace { element, namespace } => {
+        write!(f, "bad namespace for {}, found {}", element, namespace)
+      }
+      ErrorKind::LimitExceeded { limit, max } => {
+        write!(f, "{} exceeds the limit of {}", limit, max)
+      }
+      ErrorKind::NotBorrowed(value) => write!(
+        f,
+        "cannot borrow {:?} from the document, use String or Cow<str>",
+        value
+      ),
+      ErrorKind::Custom(msg) => write!(f, "{}", msg),
+    }
+  }
+}
+
+/// A document rejected by the XML parser of the [`backend`](crate::backend).
+#[derive(Debug)]
+pub struct SyntaxError {
+  message: String,
+  source: Option<Box<dyn StdError + Send + Sync>>,
+}
+
+impl SyntaxError {
+  pub fn new<T: fmt::Display>(message: T) -> Self {
+    SyntaxError {
+      message: message.to_string(),
+      source: None,
+    }
+  }
+
+  /// Keep the error raised by the parser as the source of this one.
+  pub fn with_source<E: StdError + Send + Sync + 'static>(mut self, source: E) -> Self {
+    self.source = Some(Box::new(source));
+    self
+  }
+
+  pub fn message(&self) -> &str {
+    &self.message
+  }
+}
+
+impl fmt::Display for SyntaxError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.message)
+  }
+}
+
+impl StdError for SyntaxError {
+  fn source(&self) -> Option<&(dyn StdError + 'static)> {
+    self
+      .source
+      .as_deref()
+      .map(|source| source as &(dyn StdError + 'static))
+  }
+}
+
+/// The bounds of [`Limits`](super::Limits).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Limit {
+  /// Nesting of elements.
+  Depth,
+  /// Length of a text node or of an attribute value.
+  TextLength,
+  /// Number of attributes of an element.
+  Attributes,
+  /// Number of elements of the document.
+  Elements,
+}
+
+impl fmt::Display for Limit {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Limit::Depth => write!(f, "element depth"),
+      Limit::TextLength => write!(f, "text length"),
+      Limit::Attributes => write!(f, "number of attributes"),
+      Limit::Elements => write!(f, "number of elements"),
+    }
+  }
+}
+
+/// An error raised while deserializing a data structure from XML.
+///
+/// Errors returned by [`from_str`](super::from_str) and [`from_reader`](super::from_reader)
+/// carry the position in the source and the path of the element being read.
+#[derive(Debug)]
+pub struct Error(Box<ErrorImpl>);
+
+#[derive(Debug)]
+struct ErrorImpl {
+  kind: ErrorKind,
+  position: Option<TextPosition>,
+  path: Option<String>,
+  snippet: Option<String>,
+}
+
+impl Error {
+  pub fn new(kind: ErrorKind) -> Self {
+    Error(Box::new(ErrorImpl {
+      kind,
+      position: None,
+      path: None,
+      snippet: None,
+    }))
+  }
+
+  /// Build an error from a free-form message.
+  pub fn custom<T: fmt::Display>(msg: T) -> Self {
+    ErrorKind::Custom(msg.to_string()).into()
+  }
+
+  /// Build an error for a value that failed to convert into `expected`.
+  pub fn invalid_value<T: fmt::Display>(value: &str, expected: &str, reason: T) -> Self {
+    ErrorKind::InvalidValue {
+      value: value.to_string(),
+      expected: expected.to_string(),
+      reason: reason.to_string(),
+    }
+    .into()
+  }
+
+  /// Build an error for a document exceeding one of the limits of the deserializer.
+  pub fn limit_exceeded(limit: Limit, max: usize) -> Self {
+    ErrorKind::LimitExceeded { limit, max }.into()
+  }
+
+  pub fn kind(&self) -> &ErrorKind {
+    &self.0.kind
+  }
+
+  pub fn into_kind(self) -> ErrorKind {
+    self.0.kind
+  }
+
+  /// Position of the failure in the source, lines and columns counting from 0.
+  pub fn position(&self) -> Option<TextPosition> {
+    self.0.position
+  }
+
+  /// Line of the failure in the source, counting from 1.
+  pub fn line(&self) -> Option<u64> {
+    self.0.position.map(|position| position.row + 1)
+  }
+
+  /// Column of the failure in the source, counting from 1.
+  pub fn column(&self) -> Option<u64> {
+    self.0.position.map(|position| position.column + 1)
+  }
+
+  /// Path of the element being read from the document root, e.g. `/device/peripherals/peripheral[3]`.
+  pub fn path(&self) -> Option<&str> {
+    self.0.path.as_deref()
+  }
+
+  pub(crate) fn locate(mut self, position: TextPosition, path: String) -> Self {
+    self.0.position = Some(position);
+    self.0.path = Some(path);
+    self.0.snippet = None;
+    self
+  }
+
+  /// Move the position, e.g. from a document built around a part of the source.
+  pub(crate) fn relocate<F: FnOnce(TextPosition) -> TextPosition>(mut self, f: F) -> Self {
+    self.0.position = self.0.position.map(f);
+    self
+  }
+
+  pub(crate) fn with_source(mut self, source: &str) -> Self {
+    if let Some(position) = self.0.position {
+      self.0.snippet = build_snippet(source, position);
+    }
+    self
+  }
+}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.0.kind)?;
+    if let Some(position) = self.0.position {
+      write!(
+        f,
+        " at line {}, column {}",
+        position.row + 1,
+        position.column + 1
+      )?;
+    }
+    if let Some(path) = &self.0.path {
+      write!(f, " in {}", path)?;
+    }
+    if let Some(snippet) = &self.0.snippet {
+      write!(f, "\n{}", snippet)?;
+    }
+    Ok(())
+  }
+}
+
+impl StdError for Error {
+  fn source(&self) -> Option<&(dyn StdError + 'static)> {
+    match &self.0.kind {
+      ErrorKind::Syntax(error) => Some(error),
+      ErrorKind::Io(error) => Some(error),
+      _ => None,
+    }
+  }
+}
+
+impl From<ErrorKind> for Error {
+  fn from(kind: ErrorKind) -> Self {
+    Error::new(kind)
+  }
+}
+
+impl From<SyntaxError> for Error {
+  fn from(error: SyntaxError) -> Self {
+    ErrorKind::Syntax(error).into()
+  }
+}
+
+impl From<io::Error> for Error {
+  fn from(error: io::Error) -> Self {
+    ErrorKind::Io(error).into()
+  }
+}
+
+const SNIPPET_WIDTH: usize = 60;
+
+/// Render the line of `position` and the one before it, with a caret under the column.
+fn build_snippet(source: &str, position: TextPosition) -> Option<String> {
+  let row = position.row as usize;
+  let lines: Vec<&str> = source.lines().collect();
+  let line = lines.get(row)?;
+
+  let column = position.column as usize;
+  let start = column.saturating_sub(SNIPPET_WIDTH);
+  let number_width = (row + 1).to_string().len();
+
+  let excerpt =
+    |line: &str| -> String { line.chars().skip(start).take(2 * SNIPPET_WIDTH).collect() };
+
+  let mut snippet = String::new();
+  if row > 0 {
+    snippet += &format!(
+      "{:>w$} | {}\n",
+      row,
+      excerpt(lines[row - 1]),
+      w = number_width
+    );
+  }
+  snippet += &format!("{:>w$} | {}\n", row + 1, excerpt(line), w = number_width);
+  snippet += &format!(
+    "{:>w$} | {}^",
+    "",
+    " ".repeat(column - start),
+    w = number_width
+  );
+
+  Some(snippet)
+}
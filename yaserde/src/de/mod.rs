@@ -1,87 +1,439 @@
 //! Generic data structure deserialization framework.
 //!
 
-use crate::YaDeserialize;
+use crate::backend::{self, XmlReader};
+use crate::event::{QualifiedName, TextPosition, XmlEvent};
+use crate::{YaDeserialize, YaDeserializeOwned};
+use raw::{RawScanner, RawValues};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::Display;
 use std::io::Read;
-use xml::name::OwnedName;
-use xml::reader::{EventReader, ParserConfig, XmlEvent};
+use std::ops::Range;
+use std::str::FromStr;
 
-pub fn from_str<T: YaDeserialize>(s: &str) -> Result<T, String> {
-  from_reader(s.as_bytes())
+mod children;
+mod error;
+mod push;
+mod raw;
+mod visit;
+
+pub use self::children::{iter_children, iter_children_with_config, Children};
+pub use self::error::{Error, ErrorKind, Limit, SyntaxError};
+pub use self::push::PushDeserializer;
+pub use self::visit::{
+  deserialize_enum, deserialize_struct, EnumInfo, Flattened, FlattenedFn, StructInfo, Visit,
+};
+
+/// Deserialize a document from a string, which values like `&str` can borrow from.
+pub fn from_str<'de, T: YaDeserialize<'de>>(s: &'de str) -> Result<T, Error> {
+  from_str_with_config(s, &Config::default())
+}
+
+pub fn from_str_with_config<'de, T: YaDeserialize<'de>>(
+  s: &'de str,
+  config: &Config,
+) -> Result<T, Error> {
+  let mut deserializer = Deserializer::new_from_str_with_config(s, config);
+  <T as YaDeserialize>::deserialize(&mut deserializer)
+    .map_err(|error| deserializer.locate(error).with_source(s))
+}
+
+pub fn from_reader<R: Read, T: YaDeserializeOwned>(reader: R) -> Result<T, Error> {
+  from_reader_with_config(reader, &Config::default())
+}
+
+pub fn from_reader_with_config<R: Read, T: YaDeserializeOwned>(
+  reader: R,
+  config: &Config,
+) -> Result<T, Error> {
+  let mut deserializer = Deserializer::new_from_reader_with_config(reader, config);
+  <T as YaDeserialize>::deserialize(&mut deserializer).map_err(|error| deserializer.locate(error))
+}
+
+/// Deserialize a document from an asynchronous reader. The document is parsed on a blocking
+/// thread of the tokio runtime as its chunks are read, without reading it whole first.
+#[cfg(feature = "tokio")]
+pub async fn from_async_reader<R, T>(reader: R) -> Result<T, Error>
+where
+  R: tokio::io::AsyncRead + Unpin,
+  T: YaDeserializeOwned + Send + 'static,
+{
+  from_async_reader_with_config(reader, &Config::default()).await
+}
+
+#[cfg(feature = "tokio")]
+pub async fn from_async_reader_with_config<R, T>(mut reader: R, config: &Config) -> Result<T, Error>
+where
+  R: tokio::io::AsyncRead + Unpin,
+  T: YaDeserializeOwned + Send + 'static,
+{
+  use tokio::io::AsyncReadExt;
+
+  let (sender, receiver) = tokio::sync::mpsc::channel(ASYNC_CHUNKS);
+  let config = config.clone();
+  let parser = tokio::task::spawn_blocking(move || {
+    let reader = ChannelReader {
+      receiver,
+      chunk: vec![],
+      offset: 0,
+    };
+    from_reader_with_config(reader, &config)
+  });
+
+  let mut buffer = vec![0; ASYNC_CHUNK_SIZE];
+  loop {
+    let chunk = match reader.read(&mut buffer).await {
+      Ok(0) => break,
+      Ok(length) => Ok(buffer[..length].to_vec()),
+      Err(error) => Err(error),
+    };
+    let failed = chunk.is_err();
+    // The parser stops receiving once it has failed
+    if sender.send(chunk).await.is_err() || failed {
+      break;
+    }
+  }
+  drop(sender);
+
+  match parser.await {
+    Ok(result) => result,
+    Err(error) if error.is_panic() => std::panic::resume_unwind(error.into_panic()),
+    Err(error) => Err(Error::custom(error)),
+  }
+}
+
+#[cfg(feature = "tokio")]
+pub(crate) const ASYNC_CHUNKS: usize = 4;
+#[cfg(feature = "tokio")]
+pub(crate) const ASYNC_CHUNK_SIZE: usize = 8 * 1024;
+
+/// Blocking side of [`from_async_reader`], reading the chunks sent by the asynchronous side.
+#[cfg(feature = "tokio")]
+struct ChannelReader {
+  receiver: tokio::sync::mpsc::Receiver<std::io::Result<Vec<u8>>>,
+  chunk: Vec<u8>,
+  offset: usize,
+}
+
+#[cfg(feature = "tokio")]
+impl Read for ChannelReader {
+  fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+    if self.offset == self.chunk.len() {
+      match self.receiver.blocking_recv() {
+        Some(chunk) => {
+          self.chunk = chunk?;
+          self.offset = 0;
+        }
+        None => return Ok(0),
+      }
+    }
+
+    let length = buf.len().min(self.chunk.len() - self.offset);
+    buf[..length].copy_from_slice(&self.chunk[self.offset..self.offset + length]);
+    self.offset += length;
+    Ok(length)
+  }
+}
+
+/// Deserialize the text content of an element named `name`, e.g. the value of an attribute.
+pub fn from_str_content<T: YaDeserializeOwned>(name: &str, content: &str) -> Result<T, Error> {
+  let content = backend::escape_text(content);
+  from_str_inner(&format!("<{0}>{1}</{0}>", name, content))
 }
 
-pub fn from_reader<R: Read, T: YaDeserialize>(reader: R) -> Result<T, String> {
-  <T as YaDeserialize>::deserialize(&mut Deserializer::new_from_reader(reader))
+/// Deserialize a fragment of a document, e.g. flattened contents: its first element is not
+/// checked against the `root` of `T`.
+pub fn from_str_inner<'de, T: YaDeserialize<'de>>(s: &'de str) -> Result<T, Error> {
+  let mut deserializer = Deserializer::new_from_str(s);
+  deserializer.check_root = false;
+  <T as YaDeserialize>::deserialize(&mut deserializer)
+    .map_err(|error| deserializer.locate(error).with_source(s))
 }
 
-pub struct Deserializer<R: Read> {
+/// Check the document element against the `root` name of a type, its `root_alias` names and the
+/// namespace of its prefix. Elements below the document element are not checked.
+pub fn check_root<R: Read>(
+  reader: &mut Deserializer<'_, R>,
+  names: &[&str],
+  namespace: Option<&str>,
+) -> Result<(), Error> {
+  if !reader.check_root || reader.depth() != 0 {
+    return Ok(());
+  }
+
+  if let XmlEvent::StartElement { name, .. } = reader.peek()? {
+    let found_namespace = name.namespace.as_deref();
+    let matching = names.contains(&name.local_name.as_str())
+      && (namespace.is_none() || found_namespace == namespace);
+
+    if !matching {
+      let found = qualified_name(&name.local_name, found_namespace);
+      return Err(
+        ErrorKind::UnexpectedRoot {
+          expected: names
+            .iter()
+            .map(|expected| qualified_name(expected, namespace))
+            .collect(),
+          found,
+        }
+        .into(),
+      );
+    }
+  }
+
+  Ok(())
+}
+
+fn qualified_name(name: &str, namespace: Option<&str>) -> String {
+  match namespace {
+    Some(namespace) => format!("{} xmlns=\"{}\"", name, namespace),
+    None => name.to_string(),
+  }
+}
+
+/// Parse a text or attribute value with `FromStr`, used by `display_fromstr`.
+pub fn from_str_value<T: FromStr>(value: &str) -> Result<T, Error>
+where
+  T::Err: Display,
+{
+  value
+    .parse()
+    .map_err(|error| Error::invalid_value(value, std::any::type_name::<T>(), error))
+}
+
+/// Read the text content of the element at the reader position with `FromStr`, used by
+/// `display_fromstr`. The end element is left to the caller.
+pub fn deserialize_from_str<R: Read, T: FromStr>(
+  reader: &mut Deserializer<'_, R>,
+) -> Result<T, Error>
+where
+  T::Err: Display,
+{
+  crate::deserialize_text(reader, |text, _| from_str_value(text))
+}
+
+/// Read the text content of the element at the reader position, borrowed from the document
+/// when possible, for the `Cow<'a, str>` fields marked with `borrow`. The end element is left to
+/// the caller.
+pub fn deserialize_borrowed_cow<'de: 'a, 'a, R: Read>(
+  reader: &mut Deserializer<'de, R>,
+) -> Result<Cow<'a, str>, Error> {
+  crate::deserialize_text(reader, borrow_cow_value)
+}
+
+/// Borrow `value`, an attribute value or a text content just read by `reader`, from the document
+/// when possible, for the `Cow<'a, str>` fields marked with `borrow`.
+pub fn borrow_cow_value<'de: 'a, 'a, R: Read>(
+  value: &str,
+  reader: &Deserializer<'de, R>,
+) -> Result<Cow<'a, str>, Error> {
+  Ok(match reader.borrow_str(value) {
+    Some(value) => Cow::Borrowed(value),
+    None => Cow::Owned(value.to_string()),
+  })
+}
+
+/// Reader of the events of a document for the deserializers. `'de` is the lifetime of the
+/// document when it is read from a string, which values can borrow from.
+pub struct Deserializer<'de, R: Read> {
   depth: usize,
-  reader: EventReader<R>,
-  peeked: Option<XmlEvent>,
+  source: Source<R>,
+  /// Document read from a string, borrowed values point into it.
+  input: Option<&'de str>,
+  /// Follows the events read from `input` in it.
+  scanner: Option<RawScanner<'de>>,
+  peeked: Option<(XmlEvent, TextPosition, Option<RawValues>)>,
+  position: TextPosition,
+  /// Spans in `input` of the values of the last event consumed.
+  raw_values: Option<RawValues>,
+  /// Spans in `input` of the attribute values of the open elements.
+  attribute_spans: Vec<Vec<Range<usize>>>,
+  /// Span in `input` of the value being read, the last text or the attribute being visited.
+  value_span: Option<Range<usize>>,
+  path: Vec<(String, usize)>,
+  siblings: Vec<HashMap<String, usize>>,
+  deny_unknown_fields: bool,
+  check_root: bool,
+  limits: Limits,
+  elements: usize,
 }
 
-impl<'de, R: Read> Deserializer<R> {
-  pub fn new(reader: EventReader<R>) -> Self {
+/// Where the events of a deserializer come from.
+enum Source<R: Read> {
+  Reader(Box<backend::Reader<R>>),
+  /// Events read earlier from the same document, see [`Deserializer::replay`].
+  Replay(std::vec::IntoIter<BufferedEvent>),
+}
+
+#[derive(Clone, Debug)]
+struct BufferedEvent {
+  event: XmlEvent,
+  position: TextPosition,
+  raw_values: Option<RawValues>,
+}
+
+/// Events consumed by a container without being read, with their location in the document, to
+/// deserialize its flattened fields from with [`Deserializer::replay`].
+#[derive(Clone, Debug, Default)]
+pub struct EventBuffer(Vec<BufferedEvent>);
+
+impl EventBuffer {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Add `event`, the last one consumed from `reader`.
+  pub(crate) fn push<R: Read>(&mut self, reader: &Deserializer<'_, R>, event: XmlEvent) {
+    self.0.push(BufferedEvent {
+      event,
+      position: reader.position,
+      raw_values: reader.raw_values.clone(),
+    });
+  }
+
+  /// Add the event peeked by `reader`, which is left to it.
+  pub(crate) fn push_peeked<R: Read>(&mut self, reader: &Deserializer<'_, R>) {
+    if let Some((ref event, position, ref raw_values)) = reader.peeked {
+      self.0.push(BufferedEvent {
+        event: event.clone(),
+        position,
+        raw_values: raw_values.clone(),
+      });
+    }
+  }
+}
+
+impl<'de> Deserializer<'de, &'de [u8]> {
+  /// Build a deserializer reading `s`, which values can borrow from.
+  pub fn new_from_str(s: &'de str) -> Self {
+    Self::new_from_str_with_config(s, &Config::default())
+  }
+
+  pub fn new_from_str_with_config(s: &'de str, config: &Config) -> Self {
+    let mut deserializer = Self::new_from_reader_with_config(s.as_bytes(), config);
+    deserializer.input = Some(s);
+    deserializer.scanner = Some(RawScanner::new(s));
+    deserializer
+  }
+}
+
+impl<'de, R: Read> Deserializer<'de, R> {
+  pub fn new(reader: backend::Reader<R>) -> Self {
     Deserializer {
       depth: 0,
-      reader,
+      source: Source::Reader(Box::new(reader)),
+      input: None,
+      scanner: None,
       peeked: None,
+      position: TextPosition::new(),
+      raw_values: None,
+      attribute_spans: vec![],
+      value_span: None,
+      path: vec![],
+      siblings: vec![HashMap::new()],
+      deny_unknown_fields: false,
+      check_root: true,
+      limits: Limits::default(),
+      elements: 0,
     }
   }
 
   pub fn new_from_reader(reader: R) -> Self {
-    let config = ParserConfig::new()
-      .trim_whitespace(true)
-      .whitespace_to_characters(true)
-      .cdata_to_characters(true)
-      .ignore_comments(true)
-      .coalesce_characters(true);
+    Self::new_from_reader_with_config(reader, &Config::default())
+  }
+
+  pub fn new_from_reader_with_config(reader: R, config: &Config) -> Self {
+    let mut deserializer = Self::new(backend::Reader::new(reader, config));
+    deserializer.deny_unknown_fields = config.deny_unknown_fields;
+    deserializer.limits = config.limits.clone();
+    deserializer
+  }
 
-    Self::new(EventReader::new_with_config(reader, config))
+  /// Build a deserializer for a fragment of a document, which doesn't check its first element
+  /// against the `root` of the deserialized type.
+  pub fn new_for_inner(reader: R) -> Self {
+    let mut deserializer = Self::new_from_reader(reader);
+    deserializer.check_root = false;
+    deserializer
   }
 
-  pub fn peek(&mut self) -> Result<&XmlEvent, String> {
+  pub fn peek(&mut self) -> Result<&XmlEvent, Error> {
     if self.peeked.is_none() {
-      self.peeked = Some(self.inner_next()?);
+      // Keep reporting the position of the last consumed event until the peeked one is taken
+      let position = self.position;
+      let raw_values = self.raw_values.take();
+      let next = self.inner_next()?;
+      self.peeked = Some((next, self.position, self.raw_values.take()));
+      self.position = position;
+      self.raw_values = raw_values;
     }
 
-    if let Some(ref next) = self.peeked {
+    if let Some((ref next, _, _)) = self.peeked {
       Ok(next)
     } else {
-      Err("unable to peek next item".into())
+      Err(ErrorKind::UnexpectedEof.into())
     }
   }
 
-  pub fn inner_next(&mut self) -> Result<XmlEvent, String> {
+  pub fn inner_next(&mut self) -> Result<XmlEvent, Error> {
     loop {
-      match self.reader.next() {
-        Ok(next) => {
-          match next {
-            XmlEvent::StartDocument { .. }
-            | XmlEvent::ProcessingInstruction { .. }
-            | XmlEvent::Comment(_) => { /* skip */ }
-            other => return Ok(other),
+      let next = match self.source {
+        Source::Reader(ref mut reader) => {
+          let next = reader.next_event();
+          self.position = reader.position();
+          if let (Ok(event), Some(scanner)) = (&next, &mut self.scanner) {
+            self.raw_values = scanner.next(event);
           }
+          next
         }
-        Err(msg) => {
-          return Err(msg.msg().to_string());
-        }
+        Source::Replay(ref mut events) => match events.next() {
+          Some(buffered) => {
+            self.position = buffered.position;
+            self.raw_values = buffered.raw_values;
+            Ok(buffered.event)
+          }
+          None => Ok(XmlEvent::EndDocument),
+        },
+      };
+      match next? {
+        XmlEvent::Comment(_) | XmlEvent::Whitespace(_) => { /* skip */ }
+        other => return Ok(other),
       }
     }
   }
 
-  pub fn next_event(&mut self) -> Result<XmlEvent, String> {
-    let next_event = if let Some(peeked) = self.peeked.take() {
+  pub fn next_event(&mut self) -> Result<XmlEvent, Error> {
+    let next_event = if let Some((peeked, position, raw_values)) = self.peeked.take() {
+      self.position = position;
+      self.raw_values = raw_values;
       peeked
     } else {
       self.inner_next()?
     };
+    self.check_limits(&next_event)?;
     match next_event {
-      XmlEvent::StartElement { .. } => {
+      XmlEvent::StartElement { ref name, .. } => {
         self.depth += 1;
+        self.enter_element(name);
+        self.attribute_spans.push(match self.raw_values {
+          Some(RawValues::Attributes(ref spans)) => spans.clone(),
+          _ => vec![],
+        });
+        self.value_span = None;
       }
       XmlEvent::EndElement { .. } => {
         self.depth -= 1;
+        self.path.pop();
+        self.siblings.pop();
+        self.attribute_spans.pop();
+        self.value_span = None;
+      }
+      XmlEvent::Characters(_) | XmlEvent::CData(_) => {
+        self.value_span = match self.raw_values {
+          Some(RawValues::Text(ref span)) => Some(span.clone()),
+          _ => None,
+        };
       }
       _ => {}
     }
@@ -89,7 +441,50 @@ impl<'de, R: Read> Deserializer<R> {
     Ok(next_event)
   }
 
-  pub fn skip_element(&mut self, mut cb: impl FnMut(&XmlEvent)) -> Result<(), String> {
+  fn check_limits(&mut self, event: &XmlEvent) -> Result<(), Error> {
+    let limits = &self.limits;
+
+    match event {
+      XmlEvent::StartElement { attributes, .. } => {
+        self.elements += 1;
+
+        if self.depth >= limits.max_depth {
+          return Err(Error::limit_exceeded(Limit::Depth, limits.max_depth));
+        }
+        if self.elements > limits.max_elements {
+          return Err(Error::limit_exceeded(Limit::Elements, limits.max_elements));
+        }
+        if attributes.len() > limits.max_attributes {
+          return Err(Error::limit_exceeded(
+            Limit::Attributes,
+            limits.max_attributes,
+          ));
+        }
+        if attributes
+          .iter()
+          .any(|attribute| attribute.value.len() > limits.max_text_length)
+        {
+          return Err(Error::limit_exceeded(
+            Limit::TextLength,
+            limits.max_text_length,
+          ));
+        }
+      }
+      XmlEvent::Characters(text) | XmlEvent::CData(text) | XmlEvent::Whitespace(text)
+        if text.len() > limits.max_text_length =>
+      {
+        return Err(Error::limit_exceeded(
+          Limit::TextLength,
+          limits.max_text_length,
+        ));
+      }
+      _ => {}
+    }
+
+    Ok(())
+  }
+
+  pub fn skip_element(&mut self, mut cb: impl FnMut(&XmlEvent)) -> Result<(), Error> {
     let depth = self.depth;
 
     while self.depth >= depth {
@@ -99,35 +494,342 @@ impl<'de, R: Read> Deserializer<R> {
     Ok(())
   }
 
+  /// Whether every container rejects unknown elements, attributes and text, as if marked with
+  /// `deny_unknown_fields`.
+  pub fn deny_unknown_fields(&self) -> bool {
+    self.deny_unknown_fields
+  }
+
   pub fn depth(&self) -> usize {
     self.depth
   }
 
-  pub fn read_inner_value<T, F: FnOnce(&mut Self) -> Result<T, String>>(
+  /// Position of the last event consumed from the source.
+  pub fn position(&self) -> TextPosition {
+    self.position
+  }
+
+  /// Borrow `value`, the last text read or the attribute being visited, from the document when
+  /// it is a string holding it as is: without entities, normalized line breaks nor merged CDATA
+  /// sections.
+  pub fn borrow_str(&self, value: &str) -> Option<&'de str> {
+    let raw = self.input?.get(self.value_span.clone()?)?;
+    // Trimmed by the parser, or a single CDATA section
+    let trimmed = raw.trim_matches(|c| matches!(c, ' ' | '\t' | '\r' | '\n'));
+    let cdata = trimmed
+      .strip_prefix("<![CDATA[")
+      .and_then(|cdata| cdata.strip_suffix("]]>"));
+    if raw == value {
+      Some(raw)
+    } else if trimmed == value {
+      Some(trimmed)
+    } else {
+      cdata.filter(|cdata| *cdata == value)
+    }
+  }
+
+  /// Select the value of the attribute at `index` in the last start element, to borrow it.
+  pub(crate) fn visit_attribute(&mut self, index: usize) {
+    self.value_span = self
+      .attribute_spans
+      .last()
+      .and_then(|spans| spans.get(index))
+      .cloned();
+  }
+
+  /// Deserialize a `T` from `events` read earlier from the same document, e.g. a flattened field
+  /// from the events its container has not read. Values borrow from the document as well, and
+  /// errors are located where the replayed events were read.
+  pub fn replay<T: YaDeserialize<'de>>(&mut self, events: EventBuffer) -> Result<T, Error> {
+    self.replay_with(events, T::deserialize)
+  }
+
+  /// Deserialize the child elements of `events` which none of the `flattened` types reads, each
+  /// as a `T` merged into the previous ones, e.g. for the field collecting the unknown elements
+  /// of a container with flattened fields. `None` when there are none.
+  pub fn replay_unclaimed<T: YaDeserialize<'de>>(
+    &mut self,
+    events: EventBuffer,
+    flattened: &[Option<Flattened>],
+  ) -> Result<Option<T>, Error> {
+    self.replay_with(events, |replay| {
+      let mut value: Option<T> = None;
+      if let XmlEvent::StartElement { .. } = replay.peek()? {
+        replay.next_event()?;
+      }
+
+      loop {
+        let claimed = match replay.peek()? {
+          XmlEvent::StartElement { name, .. } => flattened
+            .iter()
+            .flatten()
+            .any(|flattened| flattened.claims(&name.local_name)),
+          XmlEvent::EndDocument => break,
+          _ => {
+            replay.next_event()?;
+            continue;
+          }
+        };
+
+        if claimed {
+          replay.next_event()?;
+          replay.skip_element(|_| {})?;
+          continue;
+        }
+
+        let item = T::deserialize(replay)?;
+        replay.next_event()?;
+        match value {
+          Some(ref mut value) => value.merge(item),
+          None => value = Some(item),
+        }
+      }
+
+      Ok(value)
+    })
+  }
+
+  fn replay_with<T>(
+    &mut self,
+    events: EventBuffer,
+    read: impl FnOnce(&mut Deserializer<'de, std::io::Empty>) -> Result<T, Error>,
+  ) -> Result<T, Error> {
+    // The events start with the element being read, entered again by the replay
+    let mut path = self.path.clone();
+    let mut siblings = self.siblings.clone();
+    if let Some((name, _)) = path.pop() {
+      siblings.pop();
+      if let Some(count) = siblings
+        .last_mut()
+        .and_then(|siblings| siblings.get_mut(&name))
+      {
+        *count -= 1;
+      }
+    }
+
+    let mut replay = Deserializer::<'de, std::io::Empty> {
+      depth: 0,
+      source: Source::Replay(events.0.into_iter()),
+      input: self.input,
+      scanner: None,
+      peeked: None,
+      position: self.position,
+      raw_values: None,
+      attribute_spans: vec![],
+      value_span: None,
+      path,
+      siblings,
+      // The other fields of the container have read some of the content
+      deny_unknown_fields: false,
+      check_root: false,
+      limits: self.limits.clone(),
+      elements: 0,
+    };
+
+    read(&mut replay).inspect_err(|_| {
+      self.position = replay.position;
+      self.path = std::mem::take(&mut replay.path);
+    })
+  }
+
+  /// Deserialize a flattened `Option<T>` from `events`, `None` when they hold nothing `T` is read
+  /// from, as told by [`YaDeserialize::flattened`].
+  pub fn replay_option<T: YaDeserialize<'de>>(
+    &mut self,
+    events: EventBuffer,
+  ) -> Result<Option<T>, Error> {
+    match T::flattened() {
+      Some(flattened) if !flattened.is_in(&events) => Ok(None),
+      _ => self.replay(events).map(Some),
+    }
+  }
+
+  /// Deserialize a flattened `Vec<T>` from `events`, with an item for each part of them split by
+  /// [`YaDeserialize::flattened`], or a single one reading them all.
+  pub fn replay_vec<T: YaDeserialize<'de>>(
+    &mut self,
+    events: EventBuffer,
+  ) -> Result<Vec<T>, Error> {
+    match T::flattened() {
+      Some(flattened) => flattened
+        .split(events)
+        .into_iter()
+        .map(|events| self.replay(events))
+        .collect(),
+      None => self.replay(events).map(|item| vec![item]),
+    }
+  }
+
+  /// Path of the current element from the document root, e.g. `/device/peripheral[2]/name`.
+  pub fn path(&self) -> String {
+    if self.path.is_empty() {
+      return "/".to_string();
+    }
+
+    self
+      .path
+      .iter()
+      .map(|(name, index)| {
+        if *index > 1 {
+          format!("/{}[{}]", name, index)
+        } else {
+          format!("/{}", name)
+        }
+      })
+      .collect()
+  }
+
+  /// Attach the current position and element path to `error`.
+  pub fn locate(&self, error: Error) -> Error {
+    error.locate(self.position, self.path())
+  }
+
+  fn enter_element(&mut self, name: &QualifiedName) {
+    let name = name.to_repr();
+
+    let index = self
+      .siblings
+      .last_mut()
+      .map(|siblings| {
+        let count = siblings.entry(name.clone()).or_insert(0);
+        *count += 1;
+        *count
+      })
+      .unwrap_or(1);
+
+    self.path.push((name, index));
+    self.siblings.push(HashMap::new());
+  }
+
+  pub fn read_inner_value<T, F: FnOnce(&mut Self) -> Result<T, Error>>(
     &mut self,
     f: F,
-  ) -> Result<T, String> {
-    if let Ok(XmlEvent::StartElement { name, .. }) = self.next_event() {
-      let result = f(self)?;
-      self.expect_end_element(&name)?;
-      Ok(result)
-    } else {
-      Err("Internal error: Bad Event".to_string())
+  ) -> Result<T, Error> {
+    match self.next_event()? {
+      XmlEvent::StartElement { name, .. } => {
+        let result = f(self)?;
+        self.expect_end_element(&name)?;
+        Ok(result)
+      }
+      event => Err(ErrorKind::UnexpectedEvent(format!("{:?}", event)).into()),
     }
   }
 
-  pub fn expect_end_element(&mut self, start_name: &OwnedName) -> Result<(), String> {
-    if let XmlEvent::EndElement { name, .. } = self.next_event()? {
-      if name == *start_name {
-        Ok(())
-      } else {
-        Err(format!(
-          "End tag </{}> didn't match the start tag <{}>",
-          name.local_name, start_name.local_name
+  pub fn expect_end_element(&mut self, start_name: &QualifiedName) -> Result<(), Error> {
+    match self.next_event()? {
+      XmlEvent::EndElement { name, .. } if name == *start_name => Ok(()),
+      XmlEvent::EndElement { name, .. } => Err(
+        ErrorKind::UnexpectedElement {
+          expected: Some(start_name.local_name.clone()),
+          found: name.local_name,
+        }
+        .into(),
+      ),
+      XmlEvent::StartElement { name, .. } => Err(
+        ErrorKind::UnexpectedElement {
+          expected: Some(format!("/{}", start_name.local_name)),
+          found: name.local_name,
+        }
+        .into(),
+      ),
+      event => Err(
+        ErrorKind::UnexpectedEvent(format!(
+          "{:?}, expected </{}>",
+          event, start_name.local_name
         ))
-      }
-    } else {
-      Err(format!("Unexpected token </{}>", start_name.local_name))
+        .into(),
+      ),
+    }
+  }
+}
+
+/// Options of the deserializer, the defaults match [`from_str`] and [`from_reader`].
+#[derive(Clone, Debug)]
+pub struct Config {
+  /// Reject unknown elements, attributes and text in every container.
+  pub deny_unknown_fields: bool,
+  /// Remove the leading and trailing whitespace of text nodes and drop the whitespace-only ones.
+  /// Disable it to keep significant whitespace in text values.
+  pub trim_whitespace: bool,
+  /// Read whitespace-only text nodes as text. Without trimming, the indentation between
+  /// elements becomes text as well, so disable it to keep whitespace-only nodes out of values.
+  pub whitespace_to_characters: bool,
+  /// Read CDATA sections as text.
+  pub cdata_to_characters: bool,
+  /// Drop comments before they split the surrounding text.
+  pub ignore_comments: bool,
+  /// Merge adjacent text, CDATA and whitespace nodes into a single text.
+  pub coalesce_characters: bool,
+  /// Entities replaced in addition to the predefined XML ones, by name, e.g. `nbsp`.
+  pub extra_entities: HashMap<String, String>,
+  /// Bounds of the document, checked as it is read.
+  pub limits: Limits,
+  /// Replace character references to invalid code points, like `&#xD800;`, with U+FFFD instead
+  /// of failing.
+  pub replace_unknown_entity_references: bool,
+}
+
+impl Default for Config {
+  fn default() -> Self {
+    Config {
+      deny_unknown_fields: false,
+      trim_whitespace: true,
+      whitespace_to_characters: true,
+      cdata_to_characters: true,
+      ignore_comments: true,
+      coalesce_characters: true,
+      extra_entities: HashMap::new(),
+      replace_unknown_entity_references: false,
+      limits: Limits::default(),
+    }
+  }
+}
+
+/// Bounds of a document to deserialize untrusted XML, an error of kind
+/// [`ErrorKind::LimitExceeded`] is returned as soon as one is exceeded.
+///
+/// The lengths and the number of attributes are checked on the bytes of the document as the
+/// parser reads them, so an oversized text or start tag is never held in memory. Texts are
+/// measured as written there, with their entity references, comments and CDATA sections, and
+/// again once the entities are replaced.
+///
+/// There is no limit on the expansion of entities, as neither backend expands the entities
+/// declared in a DTD: a reference to one is an unknown entity, which fails the document, so an
+/// exponential expansion like the "billion laughs" can't happen. The `extra_entities` of the
+/// config are replaced by their value as is, without expanding the references it contains, so a
+/// text grows at most by the length of the longest value for each reference in it, and
+/// `max_text_length` bounds the text once replaced.
+#[derive(Clone, Debug)]
+pub struct Limits {
+  /// Maximum nesting of elements, which bounds the recursion of the deserializers.
+  pub max_depth: usize,
+  /// Maximum length in bytes of a text node, of an attribute value or of a name.
+  pub max_text_length: usize,
+  /// Maximum number of attributes of an element.
+  pub max_attributes: usize,
+  /// Maximum number of elements of the document.
+  pub max_elements: usize,
+}
+
+impl Limits {
+  /// No bound at all, for trusted documents.
+  pub fn unlimited() -> Self {
+    Limits {
+      max_depth: usize::MAX,
+      max_text_length: usize::MAX,
+      max_attributes: usize::MAX,
+      max_elements: usize::MAX,
+    }
+  }
+}
+
+impl Default for Limits {
+  fn default() -> Self {
+    Limits {
+      max_depth: 128,
+      max_text_length: 16 * 1024 * 1024,
+      max_attributes: 1024,
+      max_elements: 10_000_000,
     }
   }
 }
@@ -0,0 +1,363 @@
+//! Push-based deserialization of the children of a never-ending root element.
+//!
+
+use super::{Config, Deserializer, Error, ErrorKind, Limit};
+use crate::backend::RawLimits;
+use crate::event::{TextPosition, XmlEvent};
+use crate::YaDeserializeOwned;
+use std::marker::PhantomData;
+
+/// Deserializer fed with chunks of a stream, e.g. read from a socket, which returns each child
+/// of the root element as soon as it is complete. The root element, like the `<stream>` of XMPP,
+/// doesn't have to be closed.
+///
+/// Incomplete input is kept until the next call to [`feed`](PushDeserializer::feed), so chunks
+/// can be split anywhere. Each child is read by its `YaDeserialize` implementation within the
+/// namespaces declared by the root element.
+///
+/// The [`Limits`](super::Limits) of the config bound the input kept: the lengths are checked on
+/// each chunk, and the depth and the number of elements on each child, counting the root element.
+/// Once one is exceeded, its error ends the stream.
+pub struct PushDeserializer<T> {
+  config: Config,
+  buffer: Vec<u8>,
+  /// Offset of the first byte of `buffer` not scanned yet.
+  position: usize,
+  /// Progress in the incomplete markup at `position`, resumed by the next chunk.
+  markup: MarkupScan,
+  /// Offset of the first byte of `buffer` not checked against the limits yet.
+  checked: usize,
+  limits: RawLimits,
+  /// Position in the stream of the first byte of `buffer`.
+  origin: TextPosition,
+  /// Element depth at `position`, the root element being at depth 1.
+  depth: usize,
+  /// Offset of the child being received.
+  child_start: Option<usize>,
+  /// Elements of the child being received, with the root element.
+  elements: usize,
+  /// Start tag and name of the root element, once received.
+  root: Option<(Vec<u8>, String)>,
+  closed: bool,
+  child: PhantomData<fn() -> T>,
+}
+
+impl<T: YaDeserializeOwned> Default for PushDeserializer<T> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<T: YaDeserializeOwned> PushDeserializer<T> {
+  pub fn new() -> Self {
+    Self::new_with_config(&Config::default())
+  }
+
+  pub fn new_with_config(config: &Config) -> Self {
+    PushDeserializer {
+      config: config.clone(),
+      buffer: vec![],
+      position: 0,
+      markup: MarkupScan::default(),
+      checked: 0,
+      limits: RawLimits::new(&config.limits),
+      origin: TextPosition::new(),
+      depth: 0,
+      child_start: None,
+      elements: 0,
+      root: None,
+      closed: false,
+      child: PhantomData,
+    }
+  }
+
+  /// Whether the root element has been closed, or a limit exceeded, after which the input is
+  /// ignored.
+  pub fn is_closed(&self) -> bool {
+    self.closed
+  }
+
+  /// Add a chunk of the stream and deserialize the children completed by it, in order.
+  pub fn feed(&mut self, chunk: &[u8]) -> Vec<Result<T, Error>> {
+    if self.closed {
+      return vec![];
+    }
+
+    self.buffer.extend_from_slice(chunk);
+    let (children, error) = self.scan();
+
+    let mut values = Vec::with_capacity(children.len() + 1);
+    let mut scanned = 0;
+    let mut position = self.origin;
+    for (start, end) in children {
+      position = advance(position, &self.buffer[scanned..start]);
+      scanned = start;
+      values.push(self.deserialize_child(&self.buffer[start..end], position));
+    }
+
+    if let Some(error) = error {
+      // The markup can't be followed past the input dropped
+      values.push(Err(error));
+      self.closed = true;
+      self.buffer = vec![];
+      return values;
+    }
+
+    // Only the child being received has to be kept
+    let consumed = self.child_start.unwrap_or(self.position);
+    self.origin = advance(self.origin, &self.buffer[..consumed]);
+    self.buffer.drain(..consumed);
+    self.position -= consumed;
+    self.checked -= consumed;
+    self.child_start = self.child_start.map(|start| start - consumed);
+
+    values
+  }
+
+  /// Follow the markup of the new input, returning the ranges of the completed children, then
+  /// the error of the limit exceeded if any.
+  fn scan(&mut self) -> (Vec<(usize, usize)>, Option<Error>) {
+    let mut children = vec![];
+
+    while !self.closed {
+      let start = match self.buffer[self.position..]
+        .iter()
+        .position(|byte| *byte == b'<')
+      {
+        Some(offset) => self.position + offset,
+        None => {
+          self.position = self.buffer.len();
+          break;
+        }
+      };
+      self.position = start;
+
+      let end = match self.markup.length(&self.buffer[start..]) {
+        Some(length) => start + length,
+        None => break,
+      };
+      self.markup = MarkupScan::default();
+      if let Err(error) = self.check(end) {
+        return (children, Some(error));
+      }
+      self.position = end;
+
+      let markup = &self.buffer[start..end];
+      if markup.starts_with(b"<?") || markup.starts_with(b"<!") {
+        continue;
+      }
+      if !markup.starts_with(b"</") {
+        if let Err(error) = self.enter_element() {
+          return (children, Some(error));
+        }
+      }
+
+      let markup = &self.buffer[start..end];
+
+      if markup.starts_with(b"</") {
+        self.depth = self.depth.saturating_sub(1);
+        match self.depth {
+          0 => self.closed = true,
+          1 => {
+            if let Some(child_start) = self.child_start.take() {
+              children.push((child_start, end));
+            }
+          }
+          _ => {}
+        }
+      } else if markup.ends_with(b"/>") {
+        match self.depth {
+          0 => self.closed = true,
+          1 => children.push((start, end)),
+          _ => {}
+        }
+      } else {
+        match self.depth {
+          0 => self.root = Some((markup.to_vec(), element_name(markup))),
+          1 => self.child_start = Some(start),
+          _ => {}
+        }
+        self.depth += 1;
+      }
+    }
+
+    // The incomplete markup or text is kept, so it is bounded as well
+    if !self.closed {
+      if let Err(error) = self.check(self.buffer.len()) {
+        return (children, Some(error));
+      }
+    }
+
+    (children, None)
+  }
+
+  /// Check the input up to `end` against the limits of the texts and attributes.
+  fn check(&mut self, end: usize) -> Result<(), Error> {
+    if end <= self.checked {
+      return Ok(());
+    }
+    let result = self.limits.read(&self.buffer[self.checked..end]);
+    self.checked = end;
+    result.map_err(|(limit, max)| Error::limit_exceeded(limit, max))
+  }
+
+  /// Count an element starting at the current depth against the limits.
+  fn enter_element(&mut self) -> Result<(), Error> {
+    let limits = &self.config.limits;
+    if self.depth >= limits.max_depth {
+      return Err(Error::limit_exceeded(Limit::Depth, limits.max_depth));
+    }
+    self.elements = match self.depth {
+      0 | 1 => self.depth + 1,
+      _ => self.elements + 1,
+    };
+    if self.elements > limits.max_elements {
+      return Err(Error::limit_exceeded(Limit::Elements, limits.max_elements));
+    }
+    Ok(())
+  }
+
+  /// Read a child within the start tag of the root element, for its namespace declarations.
+  /// `position` is where the child starts in the stream, for the errors.
+  fn deserialize_child(&self, child: &[u8], position: TextPosition) -> Result<T, Error> {
+    let (ref root_start, ref root_name) = self.root.as_ref().expect("children follow the root");
+    let child_offset = advance(TextPosition::new(), root_start);
+
+    let mut document = root_start.clone();
+    document.extend_from_slice(child);
+    document.extend_from_slice(format!("</{}>", root_name).as_bytes());
+
+    let mut deserializer =
+      Deserializer::new_from_reader_with_config(document.as_slice(), &self.config);
+    read_child(&mut deserializer).map_err(|error| {
+      deserializer
+        .locate(error)
+        .relocate(|at| shift(at, child_offset, position))
+    })
+  }
+}
+
+fn read_child<T: YaDeserializeOwned>(
+  deserializer: &mut Deserializer<'_, &[u8]>,
+) -> Result<T, Error> {
+  deserializer.next_event()?;
+
+  loop {
+    match deserializer.peek()? {
+      XmlEvent::StartElement { name, .. } => {
+        let name = name.clone();
+        let child = T::deserialize(deserializer)?;
+        deserializer.expect_end_element(&name)?;
+        return Ok(child);
+      }
+      XmlEvent::EndElement { .. } | XmlEvent::EndDocument => {
+        return Err(ErrorKind::UnexpectedEof.into())
+      }
+      _ => {
+        deserializer.next_event()?;
+      }
+    }
+  }
+}
+
+/// Progress in a markup whose end has not been received yet, so that each chunk is only
+/// scanned once.
+#[derive(Clone, Copy, Debug, Default)]
+struct MarkupScan {
+  /// Bytes of the markup already scanned.
+  scanned: usize,
+  /// Quote open in a tag.
+  quote: Option<u8>,
+  /// Depth of the internal subsets open in a declaration.
+  brackets: isize,
+}
+
+impl MarkupScan {
+  /// Length of the markup at the start of `bytes`, or `None` if it is incomplete.
+  fn length(&mut self, bytes: &[u8]) -> Option<usize> {
+    if bytes.starts_with(b"<?") {
+      self.find_end(bytes, 2, b"?>")
+    } else if bytes.starts_with(b"<!--") {
+      self.find_end(bytes, 4, b"-->")
+    } else if bytes.starts_with(b"<![CDATA[") {
+      self.find_end(bytes, 9, b"]]>")
+    } else if bytes.len() < 9 && (b"<!--".starts_with(bytes) || b"<![CDATA[".starts_with(bytes)) {
+      None
+    } else {
+      // Tags and declarations end with the first `>` outside of quotes and internal subsets
+      let start = self.scanned.max(1);
+      for (offset, byte) in bytes.iter().enumerate().skip(start) {
+        match (self.quote, byte) {
+          (Some(open), _) if open == *byte => self.quote = None,
+          (Some(_), _) => {}
+          (None, b'"') | (None, b'\'') => self.quote = Some(*byte),
+          (None, b'[') => self.brackets += 1,
+          (None, b']') => self.brackets -= 1,
+          (None, b'>') if self.brackets <= 0 => return Some(offset + 1),
+          _ => {}
+        }
+      }
+      self.scanned = bytes.len();
+      None
+    }
+  }
+
+  /// Length of the markup ended by `terminator`, searched after its first `skip` bytes. The
+  /// search resumes where the previous one stopped, short of a terminator split between chunks.
+  fn find_end(&mut self, bytes: &[u8], skip: usize, terminator: &[u8]) -> Option<usize> {
+    let start = skip.max(self.scanned.saturating_sub(terminator.len() - 1));
+    match find(&bytes[start..], terminator) {
+      Some(offset) => Some(start + offset + terminator.len()),
+      None => {
+        self.scanned = bytes.len();
+        None
+      }
+    }
+  }
+}
+
+/// Position after `bytes` from `position`, counting columns in characters.
+fn advance(mut position: TextPosition, bytes: &[u8]) -> TextPosition {
+  for byte in bytes {
+    if *byte == b'\n' {
+      position.row += 1;
+      position.column = 0;
+    } else if byte & 0xC0 != 0x80 {
+      position.column += 1;
+    }
+  }
+  position
+}
+
+/// Position in the stream of `at`, a position in a document where the child starts at `from`,
+/// while it starts at `to` in the stream.
+fn shift(at: TextPosition, from: TextPosition, to: TextPosition) -> TextPosition {
+  if (at.row, at.column) < (from.row, from.column) {
+    to
+  } else if at.row == from.row {
+    TextPosition {
+      row: to.row,
+      column: to.column + at.column - from.column,
+    }
+  } else {
+    TextPosition {
+      row: to.row + at.row - from.row,
+      column: at.column,
+    }
+  }
+}
+
+fn find(bytes: &[u8], pattern: &[u8]) -> Option<usize> {
+  bytes
+    .windows(pattern.len())
+    .position(|window| window == pattern)
+}
+
+fn element_name(start_tag: &[u8]) -> String {
+  let name: Vec<u8> = start_tag[1..]
+    .iter()
+    .take_while(|byte| !byte.is_ascii_whitespace() && **byte != b'>' && **byte != b'/')
+    .cloned()
+    .collect();
+  String::from_utf8_lossy(&name).into_owned()
+}
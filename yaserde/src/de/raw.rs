@@ -0,0 +1,149 @@
+//! Location of the values of the events in a document read from a string, to borrow them.
+
+use crate::event::XmlEvent;
+use std::ops::Range;
+
+/// Spans in the document of the values of an event.
+#[derive(Clone, Debug)]
+pub(crate) enum RawValues {
+  /// Values of the attributes of a start element, in order, without the namespace declarations.
+  Attributes(Vec<Range<usize>>),
+  /// Text of a characters or CDATA event, with the markup merged into it by the parser.
+  Text(Range<usize>),
+}
+
+/// Follows the events of a document in its bytes, to find the exact span of their values.
+pub(crate) struct RawScanner<'de> {
+  input: &'de [u8],
+  /// Offset of the end of the last markup or text scanned.
+  offset: usize,
+  /// Whether the last start element is empty, its end element being the same tag.
+  empty: bool,
+}
+
+impl<'de> RawScanner<'de> {
+  pub(crate) fn new(input: &'de str) -> Self {
+    RawScanner {
+      input: input.as_bytes(),
+      offset: 0,
+      empty: false,
+    }
+  }
+
+  /// Spans of the values of `event`, the next event of the document. `None` for the events
+  /// without values, or if the event can't be found, in which case the values are not borrowed.
+  pub(crate) fn next(&mut self, event: &XmlEvent) -> Option<RawValues> {
+    match event {
+      XmlEvent::StartElement { attributes, .. } => {
+        let start = self.next_tag()?;
+        let (values, end, empty) = self.start_tag(start)?;
+        self.offset = end;
+        self.empty = empty;
+        // The parser reports them in order, but check it doesn't have more or less
+        if values.len() != attributes.len() {
+          return None;
+        }
+        Some(RawValues::Attributes(values))
+      }
+      XmlEvent::EndElement { .. } => {
+        if std::mem::take(&mut self.empty) {
+          return None;
+        }
+        let start = self.next_tag()?;
+        self.offset = find(self.input, start, b">")? + 1;
+        None
+      }
+      XmlEvent::Characters(_) | XmlEvent::CData(_) => {
+        let start = self.offset;
+        let end = self.next_tag().unwrap_or(self.input.len());
+        self.offset = end;
+        Some(RawValues::Text(start..end))
+      }
+      _ => None,
+    }
+  }
+
+  /// Offset of the next start or end tag, after the comments, CDATA sections, processing
+  /// instructions and document type declaration in the way.
+  fn next_tag(&self) -> Option<usize> {
+    let mut offset = self.offset;
+    loop {
+      let start = find(self.input, offset, b"<")?;
+      let markup = &self.input[start..];
+      offset = if markup.starts_with(b"<!--") {
+        find(self.input, start + 4, b"-->")? + 3
+      } else if markup.starts_with(b"<![CDATA[") {
+        find(self.input, start + 9, b"]]>")? + 3
+      } else if markup.starts_with(b"<?") {
+        find(self.input, start + 2, b"?>")? + 2
+      } else if markup.starts_with(b"<!") {
+        declaration_end(self.input, start)?
+      } else {
+        return Some(start);
+      };
+    }
+  }
+
+  /// Spans of the attribute values of the start tag at `start`, with its end and whether it is
+  /// an empty element.
+  fn start_tag(&self, start: usize) -> Option<(Vec<Range<usize>>, usize, bool)> {
+    let input = self.input;
+    let mut values = vec![];
+    let mut offset = start + 1;
+    let mut name_start = None;
+    let mut after_space = false;
+
+    while offset < input.len() {
+      match input[offset] {
+        b'>' => return Some((values, offset + 1, input[offset - 1] == b'/')),
+        quote @ (b'"' | b'\'') => {
+          let value_start = offset + 1;
+          let value_end = find(input, value_start, &[quote])?;
+          let name = input[name_start.take()?..offset]
+            .split(|byte| *byte == b'=' || byte.is_ascii_whitespace())
+            .next()?;
+          if name != b"xmlns" && !name.starts_with(b"xmlns:") {
+            values.push(value_start..value_end);
+          }
+          offset = value_end;
+        }
+        b'=' => {}
+        byte if byte.is_ascii_whitespace() => after_space = true,
+        _ => {
+          if after_space && name_start.is_none() {
+            name_start = Some(offset);
+          }
+          after_space = false;
+        }
+      }
+      offset += 1;
+    }
+    None
+  }
+}
+
+/// Offset following the document type declaration at `start`, skipping its internal subset.
+fn declaration_end(input: &[u8], start: usize) -> Option<usize> {
+  let mut brackets = 0usize;
+  let mut quote = None;
+  for (offset, byte) in input.iter().enumerate().skip(start + 2) {
+    match (quote, *byte) {
+      (Some(open), byte) if open == byte => quote = None,
+      (Some(_), _) => {}
+      (None, b'"') | (None, b'\'') => quote = Some(*byte),
+      (None, b'[') => brackets += 1,
+      (None, b']') => brackets = brackets.saturating_sub(1),
+      (None, b'>') if brackets == 0 => return Some(offset + 1),
+      _ => {}
+    }
+  }
+  None
+}
+
+fn find(input: &[u8], from: usize, pattern: &[u8]) -> Option<usize> {
+  input
+    .get(from..)?
+    .windows(pattern.len())
+    .position(|window| window == pattern)
+    .map(|position| from + position)
+}
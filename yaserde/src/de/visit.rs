@@ -0,0 +1,704 @@
+//! Reading loop of the structs and enums implemented by the derive macro, which only handle the
+//! fields and variants they are given.
+
+use super::{BufferedEvent, Deserializer, Error, ErrorKind, EventBuffer};
+use crate::event::{Attribute, QualifiedName, XmlEvent};
+use std::io::Read;
+
+/// Something for a derived implementation to read, given with the reader to
+/// [`deserialize_struct`] and [`deserialize_enum`] callbacks.
+#[derive(Debug)]
+pub enum Visit<'a> {
+  /// Value of the attribute at this index in [`StructInfo::attributes`].
+  Attribute(usize, &'a str),
+  /// Child element at this index in [`StructInfo::children`] or [`EnumInfo::variants`], the
+  /// reader is on its start element. The end element is left to the caller.
+  Child(usize),
+  /// Text content of the element.
+  Text(&'a str),
+  /// Text content naming the unit variant at this index in [`EnumInfo::values`].
+  Value(usize),
+  /// Attribute no other field reads, for the field collecting them when
+  /// [`StructInfo::any_attribute`] is set.
+  OtherAttribute(&'a Attribute),
+  /// Child element no other field reads, for the field collecting them when
+  /// [`StructInfo::any`] is set. The reader is on its start element, the end element is left to
+  /// the caller.
+  Any,
+}
+
+/// Description of a derived struct.
+///
+/// Names are looked up with a binary search: they are sorted, each with the index given back in
+/// [`Visit`], the lowest one first when names are repeated.
+#[derive(Debug)]
+pub struct StructInfo {
+  /// Name of the type, for the logs.
+  pub name: &'static str,
+  /// Name of its element when it is the root of the document.
+  pub root: &'static str,
+  /// Local names of the child element fields.
+  pub children: &'static [(&'static str, usize)],
+  /// Local names of the attribute fields.
+  pub attributes: &'static [(&'static str, usize)],
+  /// Namespace of each attribute field, by index, when it is declared with a prefix. A field reads
+  /// the attributes without namespace, or in its own.
+  pub attribute_namespaces: &'static [Option<&'static str>],
+  /// Whether a field reads the text content.
+  pub text: bool,
+  /// Whether a field collects the child elements no other field reads.
+  pub any: bool,
+  /// Whether a field collects the attributes no other field reads.
+  pub any_attribute: bool,
+  /// Types of the flattened fields, which read attributes of the struct as well.
+  pub flattened: &'static [FlattenedFn],
+  /// Whether the struct is read from the events of another one, until the end of the document.
+  pub flatten: bool,
+  pub deny_unknown_fields: bool,
+}
+
+/// Description of a derived enum, see [`StructInfo`].
+#[derive(Debug)]
+pub struct EnumInfo {
+  /// Name of the type, for the logs.
+  pub name: &'static str,
+  /// Name of its element when it is the root of the document.
+  pub root: &'static str,
+  /// Element names of the unit and newtype variants.
+  pub variants: &'static [(&'static str, usize)],
+  /// Names of the unit variants, read from the text content.
+  pub values: &'static [(&'static str, usize)],
+  /// Types of the flattened newtype variants, by index in [`EnumInfo::variants`]. They are read
+  /// from the element of the type as well.
+  pub flattened: &'static [(usize, FlattenedFn)],
+  /// Whether the enum is read from the events of another container.
+  pub flatten: bool,
+  pub deny_unknown_fields: bool,
+}
+
+/// [`YaDeserialize::flattened`](crate::YaDeserialize::flattened) of a type.
+pub type FlattenedFn = fn() -> Option<Flattened>;
+
+/// Names a derived type is read from when it is flattened into a container, see
+/// [`YaDeserialize::flattened`](crate::YaDeserialize::flattened).
+#[derive(Clone, Copy, Debug)]
+pub enum Flattened {
+  Struct {
+    info: &'static StructInfo,
+    /// Whether each child field, by index, reads a sequence of elements.
+    repeated: &'static [bool],
+  },
+  Enum(&'static EnumInfo),
+}
+
+impl Flattened {
+  /// Name of the element of the type.
+  pub(crate) fn root(&self) -> &'static str {
+    match *self {
+      Flattened::Struct { info, .. } => info.root,
+      Flattened::Enum(info) => info.root,
+    }
+  }
+
+  /// Whether the type, or one of its flattened fields, reads the child elements named `name`.
+  pub(crate) fn claims(&self, name: &str) -> bool {
+    match *self {
+      Flattened::Struct { info, .. } => info.claims(name),
+      Flattened::Enum(info) => find(info.variants, name).is_some(),
+    }
+  }
+
+  /// Whether the type, or one of its flattened fields, reads the attributes named `name`.
+  fn claims_attribute(&self, name: &QualifiedName) -> bool {
+    match *self {
+      Flattened::Struct { info, .. } => info.claims_attribute(name),
+      Flattened::Enum(_) => false,
+    }
+  }
+
+  /// Whether the type, or one of its flattened fields, reads the text content `text`.
+  fn claims_text(&self, text: &str) -> bool {
+    match *self {
+      Flattened::Struct { info, .. } => info.claims_text(text),
+      Flattened::Enum(info) => find(info.values, text).is_some(),
+    }
+  }
+
+  /// Whether the events of a container hold an attribute, child element or text of the type.
+  pub(crate) fn is_in(&self, events: &EventBuffer) -> bool {
+    let nodes = Nodes::new(&events.0);
+    match *self {
+      Flattened::Struct { info, .. } => {
+        nodes
+          .attributes()
+          .any(|name| info.find_attribute(name).is_some())
+          || nodes.content.iter().any(|node| match node.first() {
+            Some(event) => match event.event {
+              XmlEvent::StartElement { ref name, .. } => {
+                find(info.children, &name.local_name).is_some()
+              }
+              XmlEvent::Characters(_) => info.text,
+              _ => false,
+            },
+            None => false,
+          })
+      }
+      Flattened::Enum(info) => nodes
+        .content
+        .iter()
+        .any(|node| variant(info, node).is_some()),
+    }
+  }
+
+  /// Split the events of a container into those of each item of a `Vec` of the type, which all
+  /// start with the element of the container.
+  ///
+  /// Every element or text naming a variant of an enum is an item. The item of a struct ends
+  /// before a child element of a field it has already read, unless the field is a sequence. A
+  /// struct without child fields is a single item, when it is there.
+  pub(crate) fn split(&self, events: EventBuffer) -> Vec<EventBuffer> {
+    let nodes = Nodes::new(&events.0);
+    let item = |content: &[&[BufferedEvent]]| {
+      let mut item = nodes.start.to_vec();
+      item.extend(content.iter().flat_map(|node| node.iter().cloned()));
+      item.extend(nodes.end.iter().cloned());
+      EventBuffer(item)
+    };
+
+    match *self {
+      Flattened::Struct { info, .. } if info.children.is_empty() => {
+        if self.is_in(&events) {
+          vec![events]
+        } else {
+          vec![]
+        }
+      }
+      Flattened::Struct { info, repeated } => {
+        let mut items = vec![];
+        let mut content = vec![];
+        let mut read = vec![false; repeated.len()];
+        for node in &nodes.content {
+          let index = match node.first().map(|event| &event.event) {
+            Some(XmlEvent::StartElement { name, .. }) => find(info.children, &name.local_name),
+            _ => None,
+          };
+          if let Some(index) = index {
+            if read[index] && !repeated[index] {
+              items.push(item(&content));
+              content.clear();
+              read.iter_mut().for_each(|read| *read = false);
+            }
+            read[index] = true;
+          }
+          content.push(*node);
+        }
+        if read.contains(&true) {
+          items.push(item(&content));
+        }
+        items
+      }
+      Flattened::Enum(info) => nodes
+        .content
+        .iter()
+        .filter(|node| variant(info, node).is_some())
+        .map(|node| item(&[node]))
+        .collect(),
+    }
+  }
+}
+
+/// Index of the variant named by the element or text starting `node`.
+fn variant(info: &EnumInfo, node: &[BufferedEvent]) -> Option<usize> {
+  match node.first().map(|event| &event.event) {
+    Some(XmlEvent::StartElement { name, .. }) => find(info.variants, &name.local_name),
+    Some(XmlEvent::Characters(text)) => find(info.values, text),
+    _ => None,
+  }
+}
+
+/// Split `events` into nodes: each element with its content, or another event.
+fn split_nodes(events: &[BufferedEvent]) -> Vec<&[BufferedEvent]> {
+  let mut nodes = vec![];
+  let mut depth = 0usize;
+  let mut node_start = 0;
+  for (index, event) in events.iter().enumerate() {
+    match event.event {
+      XmlEvent::StartElement { .. } => depth += 1,
+      XmlEvent::EndElement { .. } => depth = depth.saturating_sub(1),
+      _ => {}
+    }
+    if depth == 0 {
+      nodes.push(&events[node_start..=index]);
+      node_start = index + 1;
+    }
+  }
+  nodes
+}
+
+/// Buffered events of a container, split into its start and end elements and the nodes of its
+/// content: each child element with its own content, or another event.
+struct Nodes<'a> {
+  start: &'a [BufferedEvent],
+  content: Vec<&'a [BufferedEvent]>,
+  end: &'a [BufferedEvent],
+}
+
+impl<'a> Nodes<'a> {
+  fn new(events: &'a [BufferedEvent]) -> Self {
+    // The events start with the element of the container, holding the others
+    match split_nodes(events).as_slice() {
+      [element]
+        if element.len() > 1 && matches!(element[0].event, XmlEvent::StartElement { .. }) =>
+      {
+        let end = element.len() - 1;
+        Nodes {
+          start: &element[..1],
+          content: split_nodes(&element[1..end]),
+          end: &element[end..],
+        }
+      }
+      content => Nodes {
+        start: &[],
+        content: content.to_vec(),
+        end: &[],
+      },
+    }
+  }
+
+  fn attributes(&self) -> impl Iterator<Item = &QualifiedName> {
+    self
+      .start
+      .iter()
+      .filter_map(|event| match event.event {
+        XmlEvent::StartElement { ref attributes, .. } => Some(attributes),
+        _ => None,
+      })
+      .flatten()
+      .map(|attribute| &attribute.name)
+  }
+}
+
+impl StructInfo {
+  fn claims(&self, name: &str) -> bool {
+    find(self.children, name).is_some() || self.flattened_claims(|flattened| flattened.claims(name))
+  }
+
+  fn claims_attribute(&self, name: &QualifiedName) -> bool {
+    self.find_attribute(name).is_some()
+      || self.flattened_claims(|flattened| flattened.claims_attribute(name))
+  }
+
+  fn claims_text(&self, text: &str) -> bool {
+    self.text || self.flattened_claims(|flattened| flattened.claims_text(text))
+  }
+
+  /// Index of the attribute field reading the attribute `name`: the one in its namespace, or else
+  /// the first one with its local name when it has no namespace.
+  fn find_attribute(&self, name: &QualifiedName) -> Option<usize> {
+    let start = self
+      .attributes
+      .partition_point(|(candidate, _)| *candidate < name.local_name.as_str());
+    let mut candidates = self.attributes[start..]
+      .iter()
+      .take_while(|(candidate, _)| *candidate == name.local_name)
+      .map(|(_, index)| *index);
+    let namespace = |index: usize| self.attribute_namespaces.get(index).copied().flatten();
+
+    match name.namespace {
+      Some(ref uri) => candidates.find(|index| namespace(*index) == Some(uri.as_str())),
+      None => {
+        let candidates: Vec<usize> = candidates.collect();
+        candidates
+          .iter()
+          .find(|index| namespace(**index).is_none())
+          .or_else(|| candidates.first())
+          .copied()
+      }
+    }
+  }
+
+  /// Whether one of the flattened fields `claims` something. The types which don't tell their
+  /// names read nothing then.
+  fn flattened_claims(&self, claims: impl Fn(&Flattened) -> bool) -> bool {
+    self
+      .flattened
+      .iter()
+      .filter_map(|flattened| flattened())
+      .any(|flattened| claims(&flattened))
+  }
+}
+
+impl EnumInfo {
+  /// Index of the flattened variant whose type is read from the element `name`.
+  fn flattened_variant(&self, name: &str) -> Option<usize> {
+    self
+      .flattened
+      .iter()
+      .find(|(_, flattened)| flattened().is_some_and(|flattened| flattened.root() == name))
+      .map(|(index, _)| *index)
+  }
+}
+
+fn find(names: &[(&str, usize)], name: &str) -> Option<usize> {
+  let index = names.partition_point(|(candidate, _)| *candidate < name);
+  names
+    .get(index)
+    .filter(|(candidate, _)| *candidate == name)
+    .map(|(_, index)| *index)
+}
+
+/// Kind of the peeked event, computed while it is borrowed.
+enum Peeked {
+  Start {
+    own_root: bool,
+    index: Option<usize>,
+  },
+  End {
+    own: bool,
+  },
+  Characters,
+  EndDocument,
+  Other,
+}
+
+/// Read the struct at the reader position, handing its attributes, child fields and text content
+/// to `visit`. The end element is left to the caller.
+///
+/// The child elements no other field reads are handed to the field collecting them, if any,
+/// unless `unused` is given. The events consumed without being read are added to `unused`, for
+/// the flattened fields, and only those none of them reads are unknown fields then. Returns the
+/// name of the element, for the errors of the missing fields.
+pub fn deserialize_struct<'de, R: Read>(
+  reader: &mut Deserializer<'de, R>,
+  info: &StructInfo,
+  mut unused: Option<&mut EventBuffer>,
+  mut visit: impl FnMut(&mut Deserializer<'de, R>, Visit<'_>) -> Result<(), Error>,
+) -> Result<String, Error> {
+  let named_element = match reader.peek()? {
+    XmlEvent::StartElement { name, .. } => name.local_name.clone(),
+    _ => info.root.to_string(),
+  };
+  let start_depth = reader.depth();
+  log::debug!(target: "yaserde_derive", "Struct {} @ {}: start to parse {:?}", info.name, start_depth, named_element);
+
+  // Flattened contents are read from the unknown nodes of the parent, which checks them
+  let deny_unknown_fields =
+    !info.flatten && (info.deny_unknown_fields || reader.deny_unknown_fields());
+  let mut started = false;
+
+  loop {
+    let depth = reader.depth();
+    let event = reader.peek()?;
+    log::trace!(target: "yaserde_derive", "Struct {} @ {}: matching {:?}", info.name, start_depth, event);
+    let peeked = match event {
+      XmlEvent::StartElement { name, .. } => {
+        // The element of the struct comes first, even when a child shares its name
+        let own_root = !started && name.local_name == info.root;
+        Peeked::Start {
+          own_root,
+          index: find(info.children, &name.local_name).filter(|_| !own_root),
+        }
+      }
+      XmlEvent::EndElement { name } => Peeked::End {
+        own: name.local_name == named_element && depth == start_depth + 1,
+      },
+      XmlEvent::Characters(_) => Peeked::Characters,
+      XmlEvent::EndDocument => Peeked::EndDocument,
+      _ => Peeked::Other,
+    };
+
+    match peeked {
+      Peeked::Start { own_root: true, .. } => {
+        let element = reader.next_event()?;
+        visit_attributes(
+          reader,
+          info,
+          &named_element,
+          &element,
+          deny_unknown_fields,
+          &mut visit,
+        )?;
+        if let Some(unused) = unused.as_deref_mut() {
+          unused.push(reader, element);
+        }
+        started = true;
+      }
+      Peeked::Start {
+        index: Some(index), ..
+      } => {
+        if !started {
+          // The element of the struct is named like a child field
+          let element = if info.flatten {
+            reader.peek()?.clone()
+          } else {
+            reader.next_event()?
+          };
+          visit_attributes(
+            reader,
+            info,
+            &named_element,
+            &element,
+            deny_unknown_fields,
+            &mut visit,
+          )?;
+          if let Some(unused) = unused.as_deref_mut().filter(|_| !info.flatten) {
+            unused.push(reader, element);
+          }
+        }
+
+        if let XmlEvent::StartElement { .. } = reader.peek()? {
+          visit(reader, Visit::Child(index))?;
+          reader.next_event()?;
+        }
+        started = true;
+      }
+      Peeked::Start { index: None, .. } if started && info.any && unused.is_none() => {
+        visit(reader, Visit::Any)?;
+        reader.next_event()?;
+      }
+      Peeked::Start { index: None, .. } => {
+        let element = reader.next_event()?;
+
+        if started {
+          if deny_unknown_fields && !info.any {
+            match element {
+              XmlEvent::StartElement { ref name, .. } if !info.claims(&name.local_name) => {
+                return Err(
+                  ErrorKind::UnknownField {
+                    field: format!("element <{}>", name.local_name),
+                    container: named_element,
+                  }
+                  .into(),
+                );
+              }
+              _ => {}
+            }
+          }
+
+          match unused.as_deref_mut() {
+            Some(unused) => {
+              unused.push(reader, element);
+              let depth = reader.depth();
+              while reader.depth() >= depth {
+                let event = reader.next_event()?;
+                unused.push(reader, event);
+              }
+            }
+            None => reader.skip_element(|_| {})?,
+          }
+        } else {
+          visit_attributes(
+            reader,
+            info,
+            &named_element,
+            &element,
+            deny_unknown_fields,
+            &mut visit,
+          )?;
+          if let Some(unused) = unused.as_deref_mut() {
+            unused.push(reader, element);
+          }
+          started = true;
+        }
+      }
+      Peeked::End { own } => {
+        if own {
+          if let Some(unused) = unused.as_deref_mut() {
+            unused.push_peeked(reader);
+          }
+          break;
+        }
+
+        let event = reader.next_event()?;
+        if let Some(unused) = unused.as_deref_mut() {
+          unused.push(reader, event);
+        }
+      }
+      Peeked::Characters => {
+        let event = reader.next_event()?;
+        if let XmlEvent::Characters(ref text) = event {
+          if deny_unknown_fields && !info.claims_text(text) {
+            return Err(
+              ErrorKind::UnknownField {
+                field: format!("text {:?}", text),
+                container: named_element,
+              }
+              .into(),
+            );
+          }
+          if info.text {
+            visit(reader, Visit::Text(text))?;
+          }
+        }
+        if let Some(unused) = unused.as_deref_mut() {
+          unused.push(reader, event);
+        }
+      }
+      Peeked::EndDocument => {
+        if info.flatten {
+          break;
+        }
+        return Err(ErrorKind::UnexpectedEof.into());
+      }
+      Peeked::Other => {
+        return Err(ErrorKind::UnexpectedEvent(format!("{:?}", reader.peek()?)).into());
+      }
+    }
+  }
+
+  log::debug!(target: "yaserde_derive", "Struct {} @ {}: success", info.name, start_depth);
+  Ok(named_element)
+}
+
+fn visit_attributes<'de, R: Read>(
+  reader: &mut Deserializer<'de, R>,
+  info: &StructInfo,
+  named_element: &str,
+  element: &XmlEvent,
+  deny_unknown_fields: bool,
+  visit: &mut impl FnMut(&mut Deserializer<'de, R>, Visit<'_>) -> Result<(), Error>,
+) -> Result<(), Error> {
+  let attributes = match element {
+    XmlEvent::StartElement { attributes, .. } => attributes,
+    _ => return Ok(()),
+  };
+
+  if deny_unknown_fields && !info.any_attribute {
+    if let Some(attribute) = attributes
+      .iter()
+      .find(|attribute| !info.claims_attribute(&attribute.name))
+    {
+      return Err(
+        ErrorKind::UnknownField {
+          field: format!("attribute {}", attribute.name.local_name),
+          container: named_element.to_string(),
+        }
+        .into(),
+      );
+    }
+  }
+
+  for (position, attribute) in attributes.iter().enumerate() {
+    match info.find_attribute(&attribute.name) {
+      Some(index) => {
+        reader.visit_attribute(position);
+        visit(reader, Visit::Attribute(index, &attribute.value))?
+      }
+      None if info.any_attribute && !info.claims_attribute(&attribute.name) => {
+        visit(reader, Visit::OtherAttribute(attribute))?
+      }
+      None => {}
+    }
+  }
+
+  Ok(())
+}
+
+/// Read the enum at the reader position, handing the elements and the text content naming its
+/// variants to `visit`. The end element is left to the caller. Returns the name of the element,
+/// for the error of a missing variant.
+pub fn deserialize_enum<'de, R: Read>(
+  reader: &mut Deserializer<'de, R>,
+  info: &EnumInfo,
+  mut visit: impl FnMut(&mut Deserializer<'de, R>, Visit<'_>) -> Result<(), Error>,
+) -> Result<String, Error> {
+  let named_element = match reader.peek()? {
+    XmlEvent::StartElement { name, .. } => name.local_name.clone(),
+    _ => info.root.to_string(),
+  };
+  let start_depth = reader.depth();
+  log::debug!(target: "yaserde_derive", "Enum {} @ {}: start to parse {:?}", info.name, start_depth, named_element);
+
+  let deny_unknown_fields =
+    !info.flatten && (info.deny_unknown_fields || reader.deny_unknown_fields());
+
+  loop {
+    let depth = reader.depth();
+    let event = reader.peek()?;
+    log::trace!(target: "yaserde_derive", "Enum {} @ {}: matching {:?}", info.name, start_depth, event);
+    let peeked = match event {
+      // The element of the enum comes first, unless it is flattened into its variant
+      XmlEvent::StartElement { name, .. } => Peeked::Start {
+        own_root: false,
+        index: find(info.variants, &name.local_name)
+          .or_else(|| info.flattened_variant(&name.local_name))
+          .filter(|_| info.flatten || depth > start_depth),
+      },
+      XmlEvent::EndElement { name } => Peeked::End {
+        own: name.local_name == named_element && depth == start_depth + 1,
+      },
+      XmlEvent::Characters(_) => Peeked::Characters,
+      XmlEvent::EndDocument => Peeked::EndDocument,
+      _ => Peeked::Other,
+    };
+
+    match peeked {
+      Peeked::Start { index, .. } => {
+        match index {
+          Some(index) => visit(reader, Visit::Child(index))?,
+          None => {
+            let own_element = reader.depth() == start_depth;
+            if let XmlEvent::StartElement {
+              name, attributes, ..
+            } = reader.next_event()?
+            {
+              if deny_unknown_fields && !own_element {
+                return Err(
+                  ErrorKind::UnknownField {
+                    field: format!("element <{}>", name.local_name),
+                    container: named_element,
+                  }
+                  .into(),
+                );
+              }
+              if let Some(attribute) = attributes.first().filter(|_| deny_unknown_fields) {
+                return Err(
+                  ErrorKind::UnknownField {
+                    field: format!("attribute {}", attribute.name.local_name),
+                    container: named_element,
+                  }
+                  .into(),
+                );
+              }
+            }
+          }
+        }
+
+        let value = match reader.peek()? {
+          XmlEvent::Characters(text) => match find(info.values, text) {
+            None if deny_unknown_fields => {
+              return Err(
+                ErrorKind::UnknownField {
+                  field: format!("text {:?}", text),
+                  container: named_element,
+                }
+                .into(),
+              );
+            }
+            value => value,
+          },
+          _ => None,
+        };
+        if let Some(index) = value {
+          visit(reader, Visit::Value(index))?;
+        }
+      }
+      Peeked::End { own: true } => break,
+      Peeked::End { own: false } | Peeked::Characters => {
+        reader.next_event()?;
+      }
+      Peeked::EndDocument => {
+        if info.flatten {
+          break;
+        }
+        return Err(ErrorKind::UnexpectedEof.into());
+      }
+      Peeked::Other => {
+        return Err(ErrorKind::UnexpectedEvent(format!("{:?}", reader.peek()?)).into());
+      }
+    }
+  }
+
+  log::debug!(target: "yaserde_derive", "Enum {} @ {}: success", info.name, start_depth);
+  Ok(named_element)
+}
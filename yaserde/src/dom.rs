@@ -0,0 +1,139 @@
+//! Elements kept as they were read, e.g. the unknown children of a container collected by a
+//! `#[yaserde(any)]` field, to be written back unchanged.
+
+use crate::event::{Attribute, Namespace, QualifiedName, XmlEvent, NS_NO_PREFIX, NS_XML_PREFIX};
+use crate::{de, ser, YaDeserialize, YaSerialize};
+use std::io::{Read, Write};
+
+/// An element with its attributes and content.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Element {
+  pub name: QualifiedName,
+  pub attributes: Vec<Attribute>,
+  /// Namespaces of the name and the attributes, declared when the element is written unless they
+  /// are already.
+  pub namespace: Namespace,
+  pub children: Vec<Node>,
+}
+
+/// Content of an [`Element`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Node {
+  Element(Element),
+  Text(String),
+}
+
+impl Element {
+  /// Element named `name`, e.g. `ext:note`, without attributes nor content.
+  pub fn new(name: &str) -> Self {
+    Element {
+      name: QualifiedName::from(name),
+      ..Element::default()
+    }
+  }
+
+  /// Value of the attribute named `local_name`, whatever its namespace.
+  pub fn attribute(&self, local_name: &str) -> Option<&str> {
+    self
+      .attributes
+      .iter()
+      .find(|attribute| attribute.name.local_name == local_name)
+      .map(|attribute| attribute.value.as_str())
+  }
+
+  /// Text content, concatenated when it is split by child elements.
+  pub fn text(&self) -> String {
+    self
+      .children
+      .iter()
+      .filter_map(|child| match child {
+        Node::Text(text) => Some(text.as_str()),
+        Node::Element(_) => None,
+      })
+      .collect()
+  }
+}
+
+/// Reads the element at the reader position, whatever its name. The end element is left to the
+/// caller.
+impl<'de> YaDeserialize<'de> for Element {
+  fn deserialize<R: Read>(reader: &mut de::Deserializer<'de, R>) -> Result<Self, de::Error> {
+    let mut element = match reader.next_event()? {
+      XmlEvent::StartElement {
+        name,
+        attributes,
+        namespace,
+      } => Element {
+        namespace: used_namespace(&name, &attributes, &namespace),
+        name,
+        attributes,
+        children: vec![],
+      },
+      event => return Err(de::ErrorKind::UnexpectedEvent(format!("{:?}", event)).into()),
+    };
+
+    loop {
+      match reader.peek()? {
+        XmlEvent::StartElement { .. } => {
+          let child = Element::deserialize(reader)?;
+          reader.next_event()?;
+          element.children.push(Node::Element(child));
+        }
+        XmlEvent::EndElement { .. } => break,
+        XmlEvent::EndDocument => return Err(de::ErrorKind::UnexpectedEof.into()),
+        _ => {
+          if let XmlEvent::Characters(text) | XmlEvent::CData(text) = reader.next_event()? {
+            element.children.push(Node::Text(text));
+          }
+        }
+      }
+    }
+
+    Ok(element)
+  }
+}
+
+/// Namespaces in `scope` bound to the prefixes of `name` and `attributes`, e.g. without the ones
+/// declared for the siblings of a collected element.
+fn used_namespace(name: &QualifiedName, attributes: &[Attribute], scope: &Namespace) -> Namespace {
+  let mut namespace = Namespace::empty();
+  let prefixes = std::iter::once(name)
+    .chain(attributes.iter().map(|attribute| &attribute.name))
+    .filter_map(|name| name.prefix.as_deref())
+    .filter(|prefix| *prefix != NS_XML_PREFIX);
+  for prefix in prefixes {
+    if let Some(uri) = scope.get(prefix) {
+      namespace.put(prefix, uri);
+    }
+  }
+  if let (None, Some(uri)) = (&name.prefix, &name.namespace) {
+    namespace.put(NS_NO_PREFIX, uri.as_str());
+  }
+  namespace
+}
+
+/// Written with its own name, whatever the name requested by the caller.
+impl YaSerialize for Element {
+  fn serialize<W: Write>(&self, writer: &mut ser::Serializer<W>) -> Result<(), ser::Error> {
+    writer.write(XmlEvent::StartElement {
+      name: self.name.clone(),
+      attributes: self.attributes.clone(),
+      namespace: self.namespace.clone(),
+    })?;
+    for child in &self.children {
+      match child {
+        Node::Element(element) => element.serialize(writer)?,
+        Node::Text(text) => writer.write(XmlEvent::characters(text))?,
+      }
+    }
+    writer.write(XmlEvent::end_element())
+  }
+
+  fn serialize_attributes(
+    &self,
+    attributes: Vec<Attribute>,
+    namespace: Namespace,
+  ) -> Result<(Vec<Attribute>, Namespace), ser::Error> {
+    Ok((attributes, namespace))
+  }
+}
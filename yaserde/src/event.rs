@@ -0,0 +1,288 @@
+//! XML events read by the deserializers and written by the serializers.
+//!
+//! These types don't depend on the backend parsing and emitting the documents, see
+//! [`backend`](crate::backend).
+
+use std::cmp::Ordering;
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/// Name of an element or of an attribute.
+///
+/// Names are compared by local name and namespace: the prefix is only how a document writes the
+/// namespace, so `a:item` and `b:item` are the same name when both prefixes are bound to the same
+/// namespace. The prefix is compared only for names without namespace, e.g. parsed from a string.
+#[derive(Clone, Debug, Default)]
+pub struct QualifiedName {
+  /// Name without its prefix, e.g. `element` for `xs:element`.
+  pub local_name: String,
+  /// URI of the namespace the name is bound to, if any.
+  pub namespace: Option<String>,
+  /// Prefix the name is written with, if any.
+  pub prefix: Option<String>,
+}
+
+impl QualifiedName {
+  /// Name without prefix nor namespace.
+  pub fn local<S: Into<String>>(local_name: S) -> Self {
+    QualifiedName {
+      local_name: local_name.into(),
+      namespace: None,
+      prefix: None,
+    }
+  }
+
+  /// Name as written in a document, e.g. `xs:element`.
+  pub fn to_repr(&self) -> String {
+    match self.prefix {
+      Some(ref prefix) => format!("{}:{}", prefix, self.local_name),
+      None => self.local_name.clone(),
+    }
+  }
+
+  /// Fields identifying the name: the prefix only stands for the namespace when there is one.
+  fn key(&self) -> (&str, &Option<String>, Option<&String>) {
+    let prefix = match self.namespace {
+      Some(_) => None,
+      None => self.prefix.as_ref(),
+    };
+    (&self.local_name, &self.namespace, prefix)
+  }
+}
+
+impl PartialEq for QualifiedName {
+  fn eq(&self, other: &Self) -> bool {
+    self.key() == other.key()
+  }
+}
+
+impl Eq for QualifiedName {}
+
+impl PartialOrd for QualifiedName {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl Ord for QualifiedName {
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.key().cmp(&other.key())
+  }
+}
+
+impl Hash for QualifiedName {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    self.key().hash(state);
+  }
+}
+
+/// Parse a name as written in a document, e.g. `xs:element`. Its namespace is left unknown.
+impl From<&str> for QualifiedName {
+  fn from(name: &str) -> Self {
+    match name.split_once(':') {
+      Some((prefix, local_name)) => QualifiedName {
+        local_name: local_name.to_string(),
+        namespace: None,
+        prefix: Some(prefix.to_string()),
+      },
+      None => QualifiedName::local(name),
+    }
+  }
+}
+
+/// Display the namespace between braces before the name, e.g. `{http://example.com}ex:item`.
+impl fmt::Display for QualifiedName {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if let Some(ref namespace) = self.namespace {
+      write!(f, "{{{}}}", namespace)?;
+    }
+    if let Some(ref prefix) = self.prefix {
+      write!(f, "{}:", prefix)?;
+    }
+    write!(f, "{}", self.local_name)
+  }
+}
+
+/// An attribute of an element, with its unescaped value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Attribute {
+  pub name: QualifiedName,
+  pub value: String,
+}
+
+impl Attribute {
+  pub fn new<N: Into<QualifiedName>, V: Into<String>>(name: N, value: V) -> Self {
+    Attribute {
+      name: name.into(),
+      value: value.into(),
+    }
+  }
+}
+
+/// Prefix of the default namespace.
+pub const NS_NO_PREFIX: &str = "";
+/// Prefix bound to the XML namespace, never declared.
+pub const NS_XML_PREFIX: &str = "xml";
+/// URI of the XML namespace.
+pub const NS_XML_URI: &str = "http://www.w3.org/XML/1998/namespace";
+/// Prefix of the namespace declarations, never declared.
+pub const NS_XMLNS_PREFIX: &str = "xmlns";
+/// URI of the namespace of the namespace declarations.
+pub const NS_XMLNS_URI: &str = "http://www.w3.org/2000/xmlns/";
+
+/// Namespaces by prefix, the default namespace having an empty prefix.
+///
+/// A read element carries every namespace in scope, a written one the namespaces it declares.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Namespace(pub BTreeMap<String, String>);
+
+impl Namespace {
+  pub fn empty() -> Self {
+    Namespace(BTreeMap::new())
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.0.is_empty()
+  }
+
+  /// Bind `prefix` to `uri` unless it is already bound. Returns whether it was bound.
+  pub fn put<P: Into<String>, U: Into<String>>(&mut self, prefix: P, uri: U) -> bool {
+    match self.0.entry(prefix.into()) {
+      Entry::Occupied(_) => false,
+      Entry::Vacant(entry) => {
+        entry.insert(uri.into());
+        true
+      }
+    }
+  }
+
+  /// URI bound to `prefix`.
+  pub fn get(&self, prefix: &str) -> Option<&str> {
+    self.0.get(prefix).map(String::as_str)
+  }
+
+  /// Bind the prefixes of `other` which are not bound yet.
+  pub fn extend(&mut self, other: &Namespace) {
+    for (prefix, uri) in other.iter() {
+      self.put(prefix, uri);
+    }
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+    self
+      .0
+      .iter()
+      .map(|(prefix, uri)| (prefix.as_str(), uri.as_str()))
+  }
+}
+
+/// Position in a document, lines and columns counting from 0.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TextPosition {
+  pub row: u64,
+  pub column: u64,
+}
+
+impl TextPosition {
+  /// Start of a document.
+  pub fn new() -> Self {
+    TextPosition::default()
+  }
+}
+
+/// Display the line and the column counting from 1, e.g. `3:14`.
+impl fmt::Display for TextPosition {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}:{}", self.row + 1, self.column + 1)
+  }
+}
+
+/// An event of a document.
+///
+/// The document declaration, processing instructions and doctypes are not reported.
+#[derive(Clone, Debug, PartialEq)]
+pub enum XmlEvent {
+  StartElement {
+    name: QualifiedName,
+    attributes: Vec<Attribute>,
+    namespace: Namespace,
+  },
+  /// The name of a written end element is taken from the element it closes.
+  EndElement {
+    name: QualifiedName,
+  },
+  Characters(String),
+  CData(String),
+  Comment(String),
+  Whitespace(String),
+  EndDocument,
+}
+
+impl XmlEvent {
+  /// Build the start of an element named `name`, e.g. `xs:element`.
+  pub fn start_element(name: &str) -> StartElementBuilder {
+    StartElementBuilder {
+      name: QualifiedName::from(name),
+      attributes: vec![],
+      namespace: Namespace::empty(),
+    }
+  }
+
+  /// End of the last element written.
+  pub fn end_element() -> Self {
+    XmlEvent::EndElement {
+      name: QualifiedName::default(),
+    }
+  }
+
+  pub fn characters(content: &str) -> Self {
+    XmlEvent::Characters(content.to_string())
+  }
+
+  pub fn cdata(content: &str) -> Self {
+    XmlEvent::CData(content.to_string())
+  }
+
+  pub fn comment(content: &str) -> Self {
+    XmlEvent::Comment(content.to_string())
+  }
+}
+
+/// Builder of a [`XmlEvent::StartElement`] to write.
+#[derive(Clone, Debug)]
+pub struct StartElementBuilder {
+  name: QualifiedName,
+  attributes: Vec<Attribute>,
+  namespace: Namespace,
+}
+
+impl StartElementBuilder {
+  /// Add an attribute named `name`, e.g. `xsi:type`.
+  pub fn attr(mut self, name: &str, value: &str) -> Self {
+    self.attributes.push(Attribute::new(name, value));
+    self
+  }
+
+  /// Declare the namespace `uri` with `prefix`.
+  pub fn ns<P: Into<String>, U: Into<String>>(mut self, prefix: P, uri: U) -> Self {
+    self.namespace.put(prefix, uri);
+    self
+  }
+
+  /// Declare `uri` as the default namespace.
+  pub fn default_ns<U: Into<String>>(self, uri: U) -> Self {
+    self.ns(NS_NO_PREFIX, uri)
+  }
+}
+
+impl From<StartElementBuilder> for XmlEvent {
+  fn from(builder: StartElementBuilder) -> Self {
+    XmlEvent::StartElement {
+      name: builder.name,
+      attributes: builder.attributes,
+      namespace: builder.namespace,
+    }
+  }
+}
@@ -25,13 +25,11 @@
 //!   xmlns: String,
 //!   #[yaserde(attribute)]
 //!   xsnonamespaceschemalocation: String,
-//!   #[yaserde(child)]
 //!   attributes: DeviceAttributes
 //! }
 //!
 //! #[derive(Default, PartialEq, Debug, YaSerialize)]
 //! struct DeviceAttributes {
-//!   #[yaserde(child)]
 //!   vendor: String,
 //! }
 //!```
@@ -86,32 +84,122 @@
 #[macro_use]
 extern crate yaserde_derive;
 
+use crate::event::XmlEvent;
+use std::borrow::Cow;
 use std::io::{Read, Write};
-use xml::writer::XmlEvent;
-
+use std::num::{
+  NonZeroI128, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroIsize, NonZeroU128,
+  NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU8, NonZeroUsize,
+};
+use std::rc::Rc;
+use std::sync::Arc;
+
+pub mod backend;
 pub mod de;
+pub mod dom;
+pub mod event;
 pub mod ser;
 
 /// A **data structure** that can be deserialized from any data format supported by YaSerDe.
-pub trait YaDeserialize: Sized {
-  fn deserialize<R: Read>(reader: &mut de::Deserializer<R>) -> Result<Self, String>;
+///
+/// `deserialize` starts on the start element of the value and returns with its end element
+/// as the next event, which is consumed by the caller.
+///
+/// `'de` is the lifetime of the document when it is deserialized from a string, which values
+/// like `&'de str` borrow from.
+pub trait YaDeserialize<'de>: Sized {
+  /// Whether a field of this type reads every occurrence of its element, like sequences, rather
+  /// than starting a new item of a flattened `Vec` of its container.
+  const REPEATED: bool = false;
+
+  fn deserialize<R: Read>(reader: &mut de::Deserializer<'de, R>) -> Result<Self, de::Error>;
+
+  /// Combine the value read so far for a field with the one read from a later occurrence of
+  /// its element.
+  ///
+  /// The later value replaces the current one by default, sequences append it.
+  fn merge(&mut self, later: Self) {
+    *self = later;
+  }
+
+  /// Value of a field whose element is missing from the document.
+  ///
+  /// `None` by default, the field is required. Optional values and sequences are empty.
+  fn missing() -> Option<Self> {
+    None
+  }
+
+  /// Names `Self` is read from when it is flattened into a container, telling whether it is
+  /// there and where each item of a flattened `Vec` starts.
+  ///
+  /// `None` by default, the content of the container is read as a single value.
+  fn flattened() -> Option<de::Flattened> {
+    None
+  }
+
+  /// Read `Self` flattened into a container, from `events`, the ones of the container which its
+  /// other fields have not read.
+  ///
+  /// A single value by default, optional values and sequences read their items according to
+  /// [`flattened`](YaDeserialize::flattened).
+  fn deserialize_flattened<R: Read>(
+    reader: &mut de::Deserializer<'de, R>,
+    events: de::EventBuffer,
+  ) -> Result<Self, de::Error> {
+    reader.replay(events)
+  }
 }
 
+/// A **data structure** that doesn't borrow from the document, so it can be deserialized from
+/// any reader.
+pub trait YaDeserializeOwned: for<'de> YaDeserialize<'de> {}
+
+impl<T> YaDeserializeOwned for T where T: for<'de> YaDeserialize<'de> {}
+
 /// A **data structure** that can be serialized into any data format supported by YaSerDe.
 pub trait YaSerialize: Sized {
-  fn serialize<W: Write>(&self, writer: &mut ser::Serializer<W>) -> Result<(), String>;
+  fn serialize<W: Write>(&self, writer: &mut ser::Serializer<W>) -> Result<(), ser::Error>;
 
   fn serialize_attributes(
     &self,
-    attributes: Vec<xml::attribute::OwnedAttribute>,
-    namespace: xml::namespace::Namespace,
-  ) -> Result<
-    (
-      Vec<xml::attribute::OwnedAttribute>,
-      xml::namespace::Namespace,
-    ),
-    String,
-  >;
+    attributes: Vec<event::Attribute>,
+    namespace: event::Namespace,
+  ) -> Result<(Vec<event::Attribute>, event::Namespace), ser::Error>;
+
+  /// Start element of `Self` as the root of a document, with the namespaces it declares but
+  /// without its attributes, which depend on the value.
+  ///
+  /// `None` by default, for the types which are not written as an element of their own.
+  fn root_element() -> Option<XmlEvent> {
+    None
+  }
+}
+
+/// A **value** that can be deserialized from an attribute value or a text content.
+pub trait YaDeserializeValue<'de>: Sized {
+  fn deserialize_value(value: &str) -> Result<Self, de::Error>;
+
+  /// Deserialize `value`, an attribute value or a text content just read by `reader`, borrowing
+  /// it from the document when possible. Same as `deserialize_value` by default.
+  fn deserialize_borrowed_value<R: Read>(
+    value: &str,
+    _reader: &de::Deserializer<'de, R>,
+  ) -> Result<Self, de::Error> {
+    Self::deserialize_value(value)
+  }
+
+  /// Value of a field whose attribute or text content is missing from the document.
+  ///
+  /// `None` by default, the field is required. Optional values are empty.
+  fn missing_value() -> Option<Self> {
+    None
+  }
+}
+
+/// A **value** that can be serialized into an attribute value or a text content.
+pub trait YaSerializeValue {
+  /// Returns `None` when nothing has to be written, e.g. for an absent optional value.
+  fn serialize_value(&self) -> Result<Option<String>, ser::Error>;
 }
 
 /// A **visitor** that can be implemented to retrieve information from source file.
@@ -119,103 +207,544 @@ pub trait Visitor<'de>: Sized {
   /// The value produced by this visitor.
   type Value;
 
-  fn visit_bool(self, v: &str) -> Result<Self::Value, String> {
-    Err(format!("Unexpected bool {:?}", v))
+  fn visit_bool(self, v: &str) -> Result<Self::Value, de::Error> {
+    Err(de::Error::new(de::ErrorKind::InvalidType {
+      found: "bool",
+      value: v.to_string(),
+    }))
+  }
+
+  fn visit_i8(self, v: &str) -> Result<Self::Value, de::Error> {
+    Err(de::Error::new(de::ErrorKind::InvalidType {
+      found: "i8",
+      value: v.to_string(),
+    }))
+  }
+
+  fn visit_u8(self, v: &str) -> Result<Self::Value, de::Error> {
+    Err(de::Error::new(de::ErrorKind::InvalidType {
+      found: "u8",
+      value: v.to_string(),
+    }))
   }
 
-  fn visit_i8(self, v: &str) -> Result<Self::Value, String> {
-    Err(format!("Unexpected i8 {:?}", v))
+  fn visit_i16(self, v: &str) -> Result<Self::Value, de::Error> {
+    Err(de::Error::new(de::ErrorKind::InvalidType {
+      found: "i16",
+      value: v.to_string(),
+    }))
   }
 
-  fn visit_u8(self, v: &str) -> Result<Self::Value, String> {
-    Err(format!("Unexpected u8 {:?}", v))
+  fn visit_u16(self, v: &str) -> Result<Self::Value, de::Error> {
+    Err(de::Error::new(de::ErrorKind::InvalidType {
+      found: "u16",
+      value: v.to_string(),
+    }))
   }
 
-  fn visit_i16(self, v: &str) -> Result<Self::Value, String> {
-    Err(format!("Unexpected i16 {:?}", v))
+  fn visit_i32(self, v: &str) -> Result<Self::Value, de::Error> {
+    Err(de::Error::new(de::ErrorKind::InvalidType {
+      found: "i32",
+      value: v.to_string(),
+    }))
   }
 
-  fn visit_u16(self, v: &str) -> Result<Self::Value, String> {
-    Err(format!("Unexpected u16 {:?}", v))
+  fn visit_u32(self, v: &str) -> Result<Self::Value, de::Error> {
+    Err(de::Error::new(de::ErrorKind::InvalidType {
+      found: "u32",
+      value: v.to_string(),
+    }))
   }
 
-  fn visit_i32(self, v: &str) -> Result<Self::Value, String> {
-    Err(format!("Unexpected i32 {:?}", v))
+  fn visit_i64(self, v: &str) -> Result<Self::Value, de::Error> {
+    Err(de::Error::new(de::ErrorKind::InvalidType {
+      found: "i64",
+      value: v.to_string(),
+    }))
   }
 
-  fn visit_u32(self, v: &str) -> Result<Self::Value, String> {
-    Err(format!("Unexpected u32 {:?}", v))
+  fn visit_u64(self, v: &str) -> Result<Self::Value, de::Error> {
+    Err(de::Error::new(de::ErrorKind::InvalidType {
+      found: "u64",
+      value: v.to_string(),
+    }))
   }
 
-  fn visit_i64(self, v: &str) -> Result<Self::Value, String> {
-    Err(format!("Unexpected i64 {:?}", v))
+  fn visit_f32(self, v: &str) -> Result<Self::Value, de::Error> {
+    Err(de::Error::new(de::ErrorKind::InvalidType {
+      found: "f32",
+      value: v.to_string(),
+    }))
   }
 
-  fn visit_u64(self, v: &str) -> Result<Self::Value, String> {
-    Err(format!("Unexpected u64 {:?}", v))
+  fn visit_f64(self, v: &str) -> Result<Self::Value, de::Error> {
+    Err(de::Error::new(de::ErrorKind::InvalidType {
+      found: "f64",
+      value: v.to_string(),
+    }))
   }
 
-  fn visit_f32(self, v: &str) -> Result<Self::Value, String> {
-    Err(format!("Unexpected f32 {:?}", v))
+  fn visit_str(self, v: &str) -> Result<Self::Value, de::Error> {
+    Err(de::Error::new(de::ErrorKind::InvalidType {
+      found: "str",
+      value: v.to_string(),
+    }))
   }
 
-  fn visit_f64(self, v: &str) -> Result<Self::Value, String> {
-    Err(format!("Unexpected f64 {:?}", v))
+  /// Visit a string borrowed from the document, forwarded to `visit_str` by default.
+  fn visit_borrowed_str(self, v: &'de str) -> Result<Self::Value, de::Error> {
+    self.visit_str(v)
   }
+}
+
+/// Write `content` as the text of the element requested by the caller, or as bare
+/// characters when no element is expected.
+pub(crate) fn serialize_text<W: Write>(
+  writer: &mut ser::Serializer<W>,
+  content: &str,
+) -> Result<(), ser::Error> {
+  match writer.get_start_event_name() {
+    Some(name) if !writer.skip_start_end() => {
+      writer.set_start_event_name(None);
+      writer.write(XmlEvent::start_element(name.as_str()))?;
+      writer.write(XmlEvent::characters(content))?;
+      writer.write(XmlEvent::end_element())
+    }
+    _ => writer.write(XmlEvent::characters(content)),
+  }
+}
 
-  fn visit_str(self, v: &str) -> Result<Self::Value, String> {
-    Err(format!("Unexpected str {:?}", v))
+/// Read the text content of the element at the reader position and convert it with `parse`,
+/// which can borrow it from the document through the reader.
+///
+/// Child elements are skipped. Like derived implementations, the end element is left to the
+/// caller.
+pub(crate) fn deserialize_text<'de, R: Read, T>(
+  reader: &mut de::Deserializer<'de, R>,
+  parse: impl FnOnce(&str, &de::Deserializer<'de, R>) -> Result<T, de::Error>,
+) -> Result<T, de::Error> {
+  match reader.next_event()? {
+    XmlEvent::StartElement { .. } => {}
+    event => return Err(de::ErrorKind::UnexpectedEvent(format!("{:?}", event)).into()),
   }
+
+  let mut text = String::new();
+  loop {
+    match reader.peek()? {
+      XmlEvent::Characters(_) => {
+        if let XmlEvent::Characters(characters) = reader.next_event()? {
+          text.push_str(&characters);
+        }
+      }
+      XmlEvent::StartElement { .. } => {
+        reader.next_event()?;
+        reader.skip_element(|_event| {})?;
+      }
+      XmlEvent::EndElement { .. } => break,
+      event => return Err(de::ErrorKind::UnexpectedEvent(format!("{:?}", event)).into()),
+    }
+  }
+
+  parse(&text, reader)
 }
 
-macro_rules! serialize_type {
+macro_rules! primitive_type {
   ($type:ty) => {
+    primitive_type!($type, |value| value);
+  };
+  ($type:ty, $normalize:expr) => {
     impl YaSerialize for $type {
-      fn serialize<W: Write>(&self, writer: &mut ser::Serializer<W>) -> Result<(), String> {
-        let content = format!("{}", self);
-        let event = XmlEvent::characters(&content);
-        let _ret = writer.write(event);
-        Ok(())
+      fn serialize<W: Write>(&self, writer: &mut ser::Serializer<W>) -> Result<(), ser::Error> {
+        serialize_text(writer, &self.to_string())
+      }
+
+      fn serialize_attributes(
+        &self,
+        attributes: Vec<event::Attribute>,
+        namespace: event::Namespace,
+      ) -> Result<(Vec<event::Attribute>, event::Namespace), ser::Error> {
+        Ok((attributes, namespace))
+      }
+    }
+
+    impl<'de> YaDeserialize<'de> for $type {
+      fn deserialize<R: Read>(reader: &mut de::Deserializer<'de, R>) -> Result<Self, de::Error> {
+        deserialize_text(reader, |text, _| {
+          <$type as YaDeserializeValue>::deserialize_value(text)
+        })
+      }
+    }
+
+    impl YaSerializeValue for $type {
+      fn serialize_value(&self) -> Result<Option<String>, ser::Error> {
+        Ok(Some(self.to_string()))
+      }
+    }
+
+    impl YaDeserializeValue<'_> for $type {
+      fn deserialize_value(value: &str) -> Result<Self, de::Error> {
+        let normalize: fn(&str) -> &str = $normalize;
+        normalize(value)
+          .parse::<$type>()
+          .map_err(|error| de::Error::invalid_value(value, stringify!($type), error))
+      }
+    }
+  };
+}
+
+primitive_type!(bool, |value| match value {
+  "1" => "true",
+  "0" => "false",
+  value => value,
+});
+primitive_type!(char);
+
+primitive_type!(usize);
+primitive_type!(u8);
+primitive_type!(u16);
+primitive_type!(u32);
+primitive_type!(u64);
+primitive_type!(u128);
+
+primitive_type!(isize);
+primitive_type!(i8);
+primitive_type!(i16);
+primitive_type!(i32);
+primitive_type!(i64);
+primitive_type!(i128);
+
+primitive_type!(f32);
+primitive_type!(f64);
+
+primitive_type!(NonZeroUsize);
+primitive_type!(NonZeroU8);
+primitive_type!(NonZeroU16);
+primitive_type!(NonZeroU32);
+primitive_type!(NonZeroU64);
+primitive_type!(NonZeroU128);
+
+primitive_type!(NonZeroIsize);
+primitive_type!(NonZeroI8);
+primitive_type!(NonZeroI16);
+primitive_type!(NonZeroI32);
+primitive_type!(NonZeroI64);
+primitive_type!(NonZeroI128);
+
+macro_rules! string_type {
+  ($type:ty) => {
+    impl YaSerialize for $type {
+      fn serialize<W: Write>(&self, writer: &mut ser::Serializer<W>) -> Result<(), ser::Error> {
+        serialize_text(writer, self)
+      }
+
+      fn serialize_attributes(
+        &self,
+        attributes: Vec<event::Attribute>,
+        namespace: event::Namespace,
+      ) -> Result<(Vec<event::Attribute>, event::Namespace), ser::Error> {
+        Ok((attributes, namespace))
+      }
+    }
+
+    impl YaSerializeValue for $type {
+      fn serialize_value(&self) -> Result<Option<String>, ser::Error> {
+        Ok(Some(self.to_string()))
+      }
+    }
+  };
+}
+
+string_type!(String);
+string_type!(&str);
+string_type!(Cow<'_, str>);
+
+impl<'de> YaDeserialize<'de> for String {
+  fn deserialize<R: Read>(reader: &mut de::Deserializer<'de, R>) -> Result<Self, de::Error> {
+    deserialize_text(reader, |text, _| Ok(text.to_string()))
+  }
+}
+
+impl YaDeserializeValue<'_> for String {
+  fn deserialize_value(value: &str) -> Result<Self, de::Error> {
+    Ok(value.to_string())
+  }
+}
+
+/// Borrowed from the document, which has to be a string holding the value as is, without
+/// entities to replace.
+impl<'de: 'a, 'a> YaDeserialize<'de> for &'a str {
+  fn deserialize<R: Read>(reader: &mut de::Deserializer<'de, R>) -> Result<Self, de::Error> {
+    deserialize_text(
+      reader,
+      <&str as YaDeserializeValue>::deserialize_borrowed_value,
+    )
+  }
+}
+
+impl<'de: 'a, 'a> YaDeserializeValue<'de> for &'a str {
+  fn deserialize_value(value: &str) -> Result<Self, de::Error> {
+    Err(de::ErrorKind::NotBorrowed(value.to_string()).into())
+  }
+
+  fn deserialize_borrowed_value<R: Read>(
+    value: &str,
+    reader: &de::Deserializer<'de, R>,
+  ) -> Result<Self, de::Error> {
+    reader
+      .borrow_str(value)
+      .ok_or_else(|| de::ErrorKind::NotBorrowed(value.to_string()).into())
+  }
+}
+
+/// Always owned, as the lifetime of the `Cow` can outlive the document, e.g. `'static`. Derived
+/// implementations borrow the fields marked with `#[yaserde(borrow)]`.
+impl YaDeserialize<'_> for Cow<'_, str> {
+  fn deserialize<R: Read>(reader: &mut de::Deserializer<R>) -> Result<Self, de::Error> {
+    deserialize_text(reader, |text, _| Ok(Cow::Owned(text.to_string())))
+  }
+}
+
+impl YaDeserializeValue<'_> for Cow<'_, str> {
+  fn deserialize_value(value: &str) -> Result<Self, de::Error> {
+    Ok(Cow::Owned(value.to_string()))
+  }
+}
+
+impl<T: YaSerialize> YaSerialize for Option<T> {
+  fn serialize<W: Write>(&self, writer: &mut ser::Serializer<W>) -> Result<(), ser::Error> {
+    match self {
+      Some(value) => value.serialize(writer),
+      None => Ok(()),
+    }
+  }
+
+  fn serialize_attributes(
+    &self,
+    attributes: Vec<event::Attribute>,
+    namespace: event::Namespace,
+  ) -> Result<(Vec<event::Attribute>, event::Namespace), ser::Error> {
+    match self {
+      Some(value) => value.serialize_attributes(attributes, namespace),
+      None => Ok((attributes, namespace)),
+    }
+  }
+}
+
+/// An element that is present always holds a value, an absent one is handled by the caller.
+impl<'de, T: YaDeserialize<'de>> YaDeserialize<'de> for Option<T> {
+  fn deserialize<R: Read>(reader: &mut de::Deserializer<'de, R>) -> Result<Self, de::Error> {
+    T::deserialize(reader).map(Some)
+  }
+
+  fn merge(&mut self, later: Self) {
+    match (self, later) {
+      (Some(current), Some(later)) => current.merge(later),
+      (current, later) => *current = later,
+    }
+  }
+
+  fn missing() -> Option<Self> {
+    Some(None)
+  }
+
+  fn flattened() -> Option<de::Flattened> {
+    T::flattened()
+  }
+
+  fn deserialize_flattened<R: Read>(
+    reader: &mut de::Deserializer<'de, R>,
+    events: de::EventBuffer,
+  ) -> Result<Self, de::Error> {
+    reader.replay_option(events)
+  }
+}
+
+impl<T: YaSerializeValue> YaSerializeValue for Option<T> {
+  fn serialize_value(&self) -> Result<Option<String>, ser::Error> {
+    match self {
+      Some(value) => value.serialize_value(),
+      None => Ok(None),
+    }
+  }
+}
+
+impl<'de, T: YaDeserializeValue<'de>> YaDeserializeValue<'de> for Option<T> {
+  fn deserialize_value(value: &str) -> Result<Self, de::Error> {
+    T::deserialize_value(value).map(Some)
+  }
+
+  fn deserialize_borrowed_value<R: Read>(
+    value: &str,
+    reader: &de::Deserializer<'de, R>,
+  ) -> Result<Self, de::Error> {
+    T::deserialize_borrowed_value(value, reader).map(Some)
+  }
+
+  fn missing_value() -> Option<Self> {
+    Some(None)
+  }
+}
+
+/// Every item is written as its own element, using the name requested by the caller.
+impl<T: YaSerialize> YaSerialize for Vec<T> {
+  fn serialize<W: Write>(&self, writer: &mut ser::Serializer<W>) -> Result<(), ser::Error> {
+    let start_event_name = writer.get_start_event_name();
+    let skip_start_end = writer.skip_start_end();
+
+    for item in self {
+      writer.set_start_event_name(start_event_name.clone());
+      writer.set_skip_start_end(skip_start_end);
+      item.serialize(writer)?;
+    }
+    Ok(())
+  }
+
+  fn serialize_attributes(
+    &self,
+    attributes: Vec<event::Attribute>,
+    namespace: event::Namespace,
+  ) -> Result<(Vec<event::Attribute>, event::Namespace), ser::Error> {
+    self
+      .iter()
+      .try_fold((attributes, namespace), |(attributes, namespace), item| {
+        item.serialize_attributes(attributes, namespace)
+      })
+  }
+}
+
+/// Reads a single element into a one item vector, repeated elements are
+/// accumulated with `merge`.
+impl<'de, T: YaDeserialize<'de>> YaDeserialize<'de> for Vec<T> {
+  const REPEATED: bool = true;
+
+  fn deserialize<R: Read>(reader: &mut de::Deserializer<'de, R>) -> Result<Self, de::Error> {
+    T::deserialize(reader).map(|item| vec![item])
+  }
+
+  fn merge(&mut self, later: Self) {
+    self.extend(later);
+  }
+
+  fn missing() -> Option<Self> {
+    Some(vec![])
+  }
+
+  fn flattened() -> Option<de::Flattened> {
+    T::flattened()
+  }
+
+  fn deserialize_flattened<R: Read>(
+    reader: &mut de::Deserializer<'de, R>,
+    events: de::EventBuffer,
+  ) -> Result<Self, de::Error> {
+    reader.replay_vec(events)
+  }
+}
+
+macro_rules! pointer_type {
+  ($pointer:ident $(, $merge:item)?) => {
+    impl<T: YaSerialize> YaSerialize for $pointer<T> {
+      fn serialize<W: Write>(&self, writer: &mut ser::Serializer<W>) -> Result<(), ser::Error> {
+        (**self).serialize(writer)
       }
 
       fn serialize_attributes(
         &self,
-        attributes: Vec<xml::attribute::OwnedAttribute>,
-        namespace: xml::namespace::Namespace,
+        attributes: Vec<event::Attribute>,
+        namespace: event::Namespace,
       ) -> Result<
         (
-          Vec<xml::attribute::OwnedAttribute>,
-          xml::namespace::Namespace,
+          Vec<event::Attribute>,
+          event::Namespace,
         ),
-        String,
+        ser::Error,
       > {
-        Ok((attributes, namespace))
+        (**self).serialize_attributes(attributes, namespace)
+      }
+
+      fn root_element() -> Option<XmlEvent> {
+        T::root_element()
       }
     }
-  };
-}
 
-serialize_type!(bool);
-serialize_type!(char);
+    impl<'de, T: YaDeserialize<'de>> YaDeserialize<'de> for $pointer<T> {
+      const REPEATED: bool = T::REPEATED;
 
-serialize_type!(usize);
-serialize_type!(u8);
-serialize_type!(u16);
-serialize_type!(u32);
-serialize_type!(u64);
+      fn deserialize<R: Read>(reader: &mut de::Deserializer<'de, R>) -> Result<Self, de::Error> {
+        T::deserialize(reader).map($pointer::new)
+      }
 
-serialize_type!(isize);
-serialize_type!(i8);
-serialize_type!(i16);
-serialize_type!(i32);
-serialize_type!(i64);
+      $($merge)?
 
-serialize_type!(f32);
-serialize_type!(f64);
+      fn missing() -> Option<Self> {
+        T::missing().map($pointer::new)
+      }
 
-/// Re-export for use in yaserde_derive
-#[doc(hidden)]
-pub use xml as __xml;
+      fn flattened() -> Option<de::Flattened> {
+        T::flattened()
+      }
+
+      fn deserialize_flattened<R: Read>(
+        reader: &mut de::Deserializer<'de, R>,
+        events: de::EventBuffer,
+      ) -> Result<Self, de::Error> {
+        T::deserialize_flattened(reader, events).map($pointer::new)
+      }
+    }
+
+    impl<T: YaSerializeValue> YaSerializeValue for $pointer<T> {
+      fn serialize_value(&self) -> Result<Option<String>, ser::Error> {
+        (**self).serialize_value()
+      }
+    }
+
+    impl<'de, T: YaDeserializeValue<'de>> YaDeserializeValue<'de> for $pointer<T> {
+      fn deserialize_value(value: &str) -> Result<Self, de::Error> {
+        T::deserialize_value(value).map($pointer::new)
+      }
+
+      fn deserialize_borrowed_value<R: Read>(
+        value: &str,
+        reader: &de::Deserializer<'de, R>,
+      ) -> Result<Self, de::Error> {
+        T::deserialize_borrowed_value(value, reader).map($pointer::new)
+      }
+
+      fn missing_value() -> Option<Self> {
+        T::missing_value().map($pointer::new)
+      }
+    }
+  };
+}
+
+pointer_type!(
+  Box,
+  fn merge(&mut self, later: Self) {
+    (**self).merge(*later)
+  }
+);
+
+/// Shared pointers merge in place the values read while deserializing, which no other pointer
+/// holds yet. A shared value is replaced instead.
+macro_rules! shared_pointer_type {
+  ($pointer:ident) => {
+    pointer_type!(
+      $pointer,
+      fn merge(&mut self, later: Self) {
+        match $pointer::try_unwrap(later) {
+          Ok(later) => match $pointer::get_mut(self) {
+            Some(current) => current.merge(later),
+            None => *self = $pointer::new(later),
+          },
+          Err(later) => *self = later,
+        }
+      }
+    );
+  };
+}
+
+shared_pointer_type!(Rc);
+shared_pointer_type!(Arc);
 
 /// Re-export for use in yaserde_derive
 #[doc(hidden)]
@@ -245,7 +774,10 @@ fn default_visitor() {
   macro_rules! test_type {
     ($visitor:tt, $message:expr) => {{
       let t = Test {};
-      assert_eq!(t.$visitor(""), Err($message.to_string()));
+      assert_eq!(
+        t.$visitor("").map_err(|e| e.to_string()),
+        Err($message.to_string())
+      );
     }};
   }
 
@@ -311,10 +843,10 @@ macro_rules! test_for_attribute_type {
 #[doc(hidden)]
 #[macro_export]
 macro_rules! deserialize_and_validate {
-  ($content: expr, $model: expr, $struct: tt) => {
+  ($content: expr, $model: expr, $struct: ty) => {
     log::debug!("deserialize_and_validate @ {}:{}", file!(), line!());
-    let loaded: Result<$struct, String> = yaserde::de::from_str($content);
-    assert_eq!(loaded, Ok($model));
+    let loaded: Result<$struct, yaserde::de::Error> = yaserde::de::from_str($content);
+    assert_eq!(loaded.map_err(|e| e.to_string()), Ok($model));
   };
 }
 
@@ -323,11 +855,11 @@ macro_rules! deserialize_and_validate {
 macro_rules! serialize_and_validate {
   ($model: expr, $content: expr) => {
     log::debug!("serialize_and_validate @ {}:{}", file!(), line!());
-    let data: Result<String, String> = yaserde::ser::to_string(&$model);
+    let data: Result<String, yaserde::ser::Error> = yaserde::ser::to_string(&$model);
 
     let content = &format!(r#"<?xml version="1.0" encoding="utf-8"?>{}"#, $content);
     assert_eq!(
-      data,
+      data.map_err(|e| e.to_string()),
       Ok(content.split("\n").map(|s| s.trim()).collect::<String>())
     );
   };
@@ -0,0 +1,81 @@
+//! Serialization errors.
+//!
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::io;
+use std::string::FromUtf8Error;
+
+/// An error raised while serializing a data structure into XML.
+#[derive(Debug)]
+pub enum Error {
+  /// The XML emitter of the [`backend`](crate::backend) rejected an event, with its error.
+  Xml(Box<dyn StdError + Send + Sync>),
+  /// The events don't form a document, e.g. an end element without a start element, when the
+  /// emitter has no error of its own for it.
+  Emitter(String),
+  /// An I/O error occurred while writing the output.
+  Io(io::Error),
+  /// The produced document is not valid UTF-8.
+  Utf8(FromUtf8Error),
+  /// Any other error, usually raised by a hand-written implementation.
+  Custom(String),
+}
+
+impl Error {
+  /// Build an error from a free-form message.
+  pub fn custom<T: fmt::Display>(msg: T) -> Self {
+    Error::Custom(msg.to_string())
+  }
+
+  /// Keep the error raised by the XML emitter as the source of this one.
+  pub fn xml<E: StdError + Send + Sync + 'static>(error: E) -> Self {
+    Error::Xml(Box::new(error))
+  }
+}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Error::Xml(error) => write!(f, "{}", error),
+      Error::Emitter(msg) => write!(f, "emitter error: {}", msg),
+      Error::Io(error) => write!(f, "{}", error),
+      Error::Utf8(error) => write!(f, "{}", error),
+      Error::Custom(msg) => write!(f, "{}", msg),
+    }
+  }
+}
+
+impl StdError for Error {
+  fn source(&self) -> Option<&(dyn StdError + 'static)> {
+    match self {
+      Error::Xml(error) => Some(error.as_ref()),
+      Error::Emitter(_) => None,
+      Error::Io(error) => Some(error),
+      Error::Utf8(error) => Some(error),
+      Error::Custom(_) => None,
+    }
+  }
+}
+
+#[cfg(feature = "xml-rs")]
+impl From<xml::writer::Error> for Error {
+  fn from(error: xml::writer::Error) -> Self {
+    match error {
+      xml::writer::Error::Io(error) => Error::Io(error),
+      error => Error::xml(error),
+    }
+  }
+}
+
+impl From<io::Error> for Error {
+  fn from(error: io::Error) -> Self {
+    Error::Io(error)
+  }
+}
+
+impl From<FromUtf8Error> for Error {
+  fn from(error: FromUtf8Error) -> Self {
+    Error::Utf8(error)
+  }
+}
@@ -1,33 +1,35 @@
 //! Generic data structure serialization framework.
 //!
 
+use crate::backend::{self, XmlWriter};
+use crate::event::{Attribute, Namespace, QualifiedName, XmlEvent};
 use crate::YaSerialize;
+use std::fmt::Display;
 use std::io::{Cursor, Write};
-use std::str;
-use xml::writer::XmlEvent;
-use xml::{EmitterConfig, EventWriter};
+
+mod error;
+
+pub use self::error::Error;
 
 /// Serialize XML into a plain String with no formatting (EmitterConfig).
-pub fn to_string<T: YaSerialize>(model: &T) -> Result<String, String> {
+pub fn to_string<T: YaSerialize>(model: &T) -> Result<String, Error> {
   let buf = Cursor::new(Vec::new());
   let cursor = serialize_with_writer(model, buf, &Config::default())?;
-  let data = str::from_utf8(cursor.get_ref()).expect("Found invalid UTF-8");
-  Ok(data.into())
+  Ok(String::from_utf8(cursor.into_inner())?)
 }
 
 /// Serialize XML into a plain String with control on formatting (via EmitterConfig parameters)
-pub fn to_string_with_config<T: YaSerialize>(model: &T, config: &Config) -> Result<String, String> {
+pub fn to_string_with_config<T: YaSerialize>(model: &T, config: &Config) -> Result<String, Error> {
   let buf = Cursor::new(Vec::new());
   let cursor = serialize_with_writer(model, buf, config)?;
-  let data = str::from_utf8(cursor.get_ref()).expect("Found invalid UTF-8");
-  Ok(data.into())
+  Ok(String::from_utf8(cursor.into_inner())?)
 }
 
 pub fn serialize_with_writer<W: Write, T: YaSerialize>(
   model: &T,
   writer: W,
   config: &Config,
-) -> Result<W, String> {
+) -> Result<W, Error> {
   let mut serializer = Serializer::new_from_writer(writer, config);
   match YaSerialize::serialize(model, &mut serializer) {
     Ok(()) => Ok(serializer.into_inner()),
@@ -35,17 +37,106 @@ pub fn serialize_with_writer<W: Write, T: YaSerialize>(
   }
 }
 
-pub fn to_string_content<T: YaSerialize>(model: &T) -> Result<String, String> {
+/// Serialize XML into an asynchronous writer, returned once flushed. The document is serialized
+/// on a blocking thread of the tokio runtime and written chunk by chunk as it is produced,
+/// without building it whole first.
+///
+/// The model is moved to that thread: pass an `Arc` to keep it.
+#[cfg(feature = "tokio")]
+pub async fn to_async_writer<W, T>(model: T, writer: W) -> Result<W, Error>
+where
+  W: tokio::io::AsyncWrite + Unpin,
+  T: YaSerialize + Send + 'static,
+{
+  to_async_writer_with_config(model, writer, &Config::default()).await
+}
+
+#[cfg(feature = "tokio")]
+pub async fn to_async_writer_with_config<W, T>(
+  model: T,
+  mut writer: W,
+  config: &Config,
+) -> Result<W, Error>
+where
+  W: tokio::io::AsyncWrite + Unpin,
+  T: YaSerialize + Send + 'static,
+{
+  use crate::de::{ASYNC_CHUNKS, ASYNC_CHUNK_SIZE};
+  use tokio::io::AsyncWriteExt;
+
+  let (sender, mut receiver) = tokio::sync::mpsc::channel(ASYNC_CHUNKS);
+  let config = config.clone();
+  let serializer = tokio::task::spawn_blocking(move || {
+    let writer = ChannelWriter {
+      sender,
+      chunk: Vec::with_capacity(ASYNC_CHUNK_SIZE),
+    };
+    serialize_with_writer(&model, writer, &config)?.flush()?;
+    Ok(())
+  });
+
+  let mut written = Ok(());
+  while let Some(chunk) = receiver.recv().await {
+    written = writer.write_all(&chunk).await;
+    if written.is_err() {
+      break;
+    }
+  }
+  // The serializer stops once the chunks are no longer received
+  drop(receiver);
+
+  let serialized = match serializer.await {
+    Ok(result) => result,
+    Err(error) if error.is_panic() => std::panic::resume_unwind(error.into_panic()),
+    Err(error) => Err(Error::custom(error)),
+  };
+  written?;
+  serialized?;
+  writer.flush().await?;
+  Ok(writer)
+}
+
+/// Blocking side of [`to_async_writer`], sending the chunks written to the asynchronous side.
+#[cfg(feature = "tokio")]
+struct ChannelWriter {
+  sender: tokio::sync::mpsc::Sender<Vec<u8>>,
+  chunk: Vec<u8>,
+}
+
+#[cfg(feature = "tokio")]
+impl Write for ChannelWriter {
+  fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+    self.chunk.extend_from_slice(buf);
+    if self.chunk.len() >= crate::de::ASYNC_CHUNK_SIZE {
+      self.flush()?;
+    }
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> std::io::Result<()> {
+    if self.chunk.is_empty() {
+      return Ok(());
+    }
+    let chunk = std::mem::take(&mut self.chunk);
+    self.sender.blocking_send(chunk).map_err(|_| {
+      std::io::Error::new(
+        std::io::ErrorKind::BrokenPipe,
+        "the asynchronous writer stopped",
+      )
+    })
+  }
+}
+
+pub fn to_string_content<T: YaSerialize>(model: &T) -> Result<String, Error> {
   let buf = Cursor::new(Vec::new());
   let cursor = serialize_with_writer_content(model, buf)?;
-  let data = str::from_utf8(cursor.get_ref()).expect("Found invalid UTF-8");
-  Ok(data.into())
+  Ok(String::from_utf8(cursor.into_inner())?)
 }
 
 pub fn serialize_with_writer_content<W: Write, T: YaSerialize>(
   model: &T,
   writer: W,
-) -> Result<W, String> {
+) -> Result<W, Error> {
   let mut serializer = Serializer::new_for_inner(writer);
   serializer.set_skip_start_end(true);
   match YaSerialize::serialize(model, &mut serializer) {
@@ -54,14 +145,28 @@ pub fn serialize_with_writer_content<W: Write, T: YaSerialize>(
   }
 }
 
+/// Format a text or attribute value with `Display`, used by `display_fromstr`.
+pub fn to_string_value<T: Display>(value: &T) -> Result<Option<String>, Error> {
+  Ok(Some(value.to_string()))
+}
+
+/// Write `value` with `Display` as the text of the element requested by the caller, used by
+/// `display_fromstr`.
+pub fn serialize_display<W: Write, T: Display>(
+  value: &T,
+  writer: &mut Serializer<W>,
+) -> Result<(), Error> {
+  crate::serialize_text(writer, &value.to_string())
+}
+
 pub struct Serializer<W: Write> {
-  writer: EventWriter<W>,
+  writer: backend::Writer<W>,
   skip_start_end: bool,
   start_event_name: Option<String>,
 }
 
-impl<'de, W: Write> Serializer<W> {
-  pub fn new(writer: EventWriter<W>) -> Self {
+impl<W: Write> Serializer<W> {
+  pub fn new(writer: backend::Writer<W>) -> Self {
     Serializer {
       writer,
       skip_start_end: false,
@@ -70,22 +175,16 @@ impl<'de, W: Write> Serializer<W> {
   }
 
   pub fn new_from_writer(writer: W, config: &Config) -> Self {
-    let mut emitter_config = EmitterConfig::new()
-      .cdata_to_characters(true)
-      .perform_indent(config.perform_indent)
-      .write_document_declaration(config.write_document_declaration);
-
-    if let Some(indent_string_value) = &config.indent_string {
-      emitter_config = emitter_config.indent_string(indent_string_value.clone());
-    }
-
-    Self::new(EventWriter::new_with_config(writer, emitter_config))
+    Self::new(backend::Writer::new(writer, config))
   }
 
   pub fn new_for_inner(writer: W) -> Self {
-    let config = EmitterConfig::new().write_document_declaration(false);
+    let config = Config {
+      write_document_declaration: false,
+      ..Config::default()
+    };
 
-    Self::new(EventWriter::new_with_config(writer, config))
+    Self::new(backend::Writer::new(writer, &config))
   }
 
   pub fn into_inner(self) -> W {
@@ -108,14 +207,98 @@ impl<'de, W: Write> Serializer<W> {
     self.start_event_name = name;
   }
 
-  pub fn write<'a, E>(&mut self, event: E) -> xml::writer::Result<()>
-  where
-    E: Into<XmlEvent<'a>>,
-  {
-    self.writer.write(event)
+  pub fn write<E: Into<XmlEvent>>(&mut self, event: E) -> Result<(), Error> {
+    self.writer.write_event(event.into())
+  }
+}
+
+/// Writer of a large document: the root element is opened first, then the children are
+/// written one at a time as they are produced.
+pub struct StreamWriter<W: Write> {
+  serializer: Serializer<W>,
+}
+
+impl<W: Write> StreamWriter<W> {
+  /// Open a document with a root element `name`, without attributes.
+  pub fn new(writer: W, name: &str, config: &Config) -> Result<Self, Error> {
+    Self::open(writer, name, vec![], Namespace::empty(), config)
+  }
+
+  /// Open a document with the root element of `C`, the type of the whole document, named and
+  /// declaring the namespaces after its container attributes.
+  pub fn new_for_container<C: YaSerialize>(writer: W, config: &Config) -> Result<Self, Error> {
+    let (name, namespace) = root_element::<C>()?;
+    Self::open(writer, name, vec![], namespace, config)
+  }
+
+  /// Open a document with the root element of `C`, like
+  /// [`new_for_container`](StreamWriter::new_for_container), carrying the attributes of
+  /// `container`. Its child elements are not written.
+  pub fn new_for_container_with_attributes<C: YaSerialize>(
+    writer: W,
+    container: &C,
+    config: &Config,
+  ) -> Result<Self, Error> {
+    let (name, namespace) = root_element::<C>()?;
+    let (attributes, namespace) = container.serialize_attributes(vec![], namespace)?;
+    Self::open(writer, name, attributes, namespace, config)
+  }
+
+  fn open(
+    writer: W,
+    name: impl Into<QualifiedName>,
+    attributes: Vec<Attribute>,
+    namespace: Namespace,
+    config: &Config,
+  ) -> Result<Self, Error> {
+    let mut serializer = Serializer::new_from_writer(writer, config);
+    serializer.write(XmlEvent::StartElement {
+      name: name.into(),
+      attributes,
+      namespace,
+    })?;
+
+    Ok(StreamWriter { serializer })
+  }
+
+  /// Write `item` as a child of the root element, named after its type. The namespaces already
+  /// declared by the root element are not declared again.
+  pub fn write_item<T: YaSerialize>(&mut self, item: &T) -> Result<(), Error> {
+    self.write(None, item)
+  }
+
+  /// Write `item` as a child element `name` of the root element.
+  pub fn write_named_item<T: YaSerialize>(&mut self, name: &str, item: &T) -> Result<(), Error> {
+    self.write(Some(name.to_string()), item)
+  }
+
+  /// Close the root element and return the underlying writer.
+  pub fn finish(mut self) -> Result<W, Error> {
+    self.serializer.write(XmlEvent::end_element())?;
+    Ok(self.serializer.into_inner())
+  }
+
+  fn write<T: YaSerialize>(&mut self, name: Option<String>, item: &T) -> Result<(), Error> {
+    self.serializer.set_start_event_name(name);
+    self.serializer.set_skip_start_end(false);
+    item.serialize(&mut self.serializer)
+  }
+}
+
+/// Name and namespaces of the root element of `C`.
+fn root_element<C: YaSerialize>() -> Result<(QualifiedName, Namespace), Error> {
+  match C::root_element() {
+    Some(XmlEvent::StartElement {
+      name, namespace, ..
+    }) => Ok((name, namespace)),
+    _ => Err(Error::custom(format!(
+      "{} is not written as an element",
+      std::any::type_name::<C>()
+    ))),
   }
 }
 
+#[derive(Clone, Debug)]
 pub struct Config {
   pub perform_indent: bool,
   pub write_document_declaration: bool,
@@ -0,0 +1,326 @@
+#[macro_use]
+extern crate yaserde;
+#[macro_use]
+extern crate yaserde_derive;
+
+use std::collections::BTreeMap;
+use yaserde::dom::{Element, Node};
+use yaserde::event::QualifiedName;
+
+fn init() {
+  let _ = env_logger::builder().is_test(true).try_init();
+}
+
+#[test]
+fn any_elements() {
+  init();
+
+  #[derive(Default, PartialEq, Debug, YaDeserialize, YaSerialize)]
+  #[yaserde(rename = "config")]
+  struct Config {
+    #[yaserde(attribute)]
+    version: u32,
+    name: String,
+    #[yaserde(any)]
+    extra: Vec<Element>,
+  }
+
+  let content = r#"
+    <config xmlns:ext="urn:example:ext" version="1">
+      <ext:note lang="en">keep<b>me</b></ext:note>
+      <name>server</name>
+      <port>8080</port>
+    </config>"#;
+
+  let mut config: Config = yaserde::de::from_str(content).unwrap();
+  assert_eq!(config.name, "server");
+  assert_eq!(config.extra.len(), 2);
+
+  let note = &config.extra[0];
+  assert_eq!(note.name.to_string(), "{urn:example:ext}ext:note");
+  assert_eq!(note.attribute("lang"), Some("en"));
+  assert_eq!(note.text(), "keep");
+  assert!(matches!(note.children[1], Node::Element(ref b) if b.text() == "me"));
+  assert_eq!(config.extra[1].text(), "8080");
+
+  // Unknown elements survive a read-modify-write cycle, after the known ones
+  config.name = "proxy".to_string();
+  let content = r#"
+    <config version="1">
+      <name>proxy</name>
+      <ext:note xmlns:ext="urn:example:ext" lang="en">keep<b>me</b></ext:note>
+      <port>8080</port>
+    </config>"#;
+  serialize_and_validate!(config, content);
+
+  let model = Config {
+    version: 2,
+    name: "plain".to_string(),
+    extra: vec![],
+  };
+  let content = r#"<config version="2"><name>plain</name></config>"#;
+  serialize_and_validate!(model, content);
+  deserialize_and_validate!(content, model, Config);
+}
+
+#[test]
+fn any_elements_with_flatten() {
+  init();
+
+  #[derive(Default, PartialEq, Debug, YaDeserialize, YaSerialize)]
+  struct Shape {
+    #[yaserde(flatten)]
+    size: Size,
+    #[yaserde(flatten)]
+    fill: Option<Fill>,
+    kind: String,
+    #[yaserde(any)]
+    extra: Vec<Element>,
+  }
+
+  #[derive(Default, PartialEq, Debug, YaDeserialize, YaSerialize)]
+  struct Size {
+    width: u32,
+    height: u32,
+  }
+
+  #[derive(PartialEq, Debug, YaDeserialize, YaSerialize)]
+  enum Fill {
+    Color(String),
+    Gradient(String),
+  }
+
+  impl Default for Fill {
+    fn default() -> Self {
+      Self::Color(String::new())
+    }
+  }
+
+  let mut shadow = Element::new("shadow");
+  shadow.children.push(Node::Text("2".to_string()));
+  let mut unit = Element::new("unit");
+  unit.children.push(Node::Text("px".to_string()));
+
+  let model = Shape {
+    size: Size {
+      width: 3,
+      height: 4,
+    },
+    fill: Some(Fill::Color("red".to_string())),
+    kind: "box".to_string(),
+    extra: vec![shadow, unit],
+  };
+
+  let content = r#"
+    <Shape>
+      <width>3</width>
+      <height>4</height>
+      <Color>red</Color>
+      <kind>box</kind>
+      <shadow>2</shadow>
+      <unit>px</unit>
+    </Shape>"#;
+
+  serialize_and_validate!(model, content);
+  deserialize_and_validate!(content, model, Shape);
+}
+
+#[test]
+fn any_elements_with_deny_unknown_fields() {
+  init();
+
+  #[derive(Default, PartialEq, Debug, YaDeserialize)]
+  #[yaserde(rename = "book", deny_unknown_fields)]
+  struct Book {
+    title: String,
+    #[yaserde(any)]
+    extra: Vec<Element>,
+  }
+
+  let book: Book =
+    yaserde::de::from_str("<book><title>Dune</title><isbn>978-0</isbn></book>").unwrap();
+  assert_eq!(book.extra.len(), 1);
+  assert_eq!(book.extra[0].name.local_name, "isbn");
+
+  let result: Result<Book, _> =
+    yaserde::de::from_str(r#"<book lang="en"><title>Dune</title></book>"#);
+  assert_eq!(
+    result.map_err(|error| error.kind().to_string()),
+    Err("unknown attribute lang in book".to_string())
+  );
+}
+
+#[test]
+fn any_attributes() {
+  init();
+
+  #[derive(Default, PartialEq, Debug, YaDeserialize, YaSerialize)]
+  #[yaserde(rename = "item")]
+  struct Item {
+    #[yaserde(attribute)]
+    id: u32,
+    #[yaserde(flatten)]
+    common: CommonAttributes,
+    #[yaserde(any_attribute)]
+    other: BTreeMap<QualifiedName, String>,
+    name: String,
+  }
+
+  #[derive(Default, PartialEq, Debug, YaDeserialize, YaSerialize)]
+  struct CommonAttributes {
+    #[yaserde(attribute)]
+    lang: String,
+    #[yaserde(flatten)]
+    core: CoreAttributes,
+  }
+
+  #[derive(Default, PartialEq, Debug, YaDeserialize, YaSerialize)]
+  struct CoreAttributes {
+    #[yaserde(attribute)]
+    class: Option<String>,
+  }
+
+  let content = r#"
+    <item xmlns:ext="urn:example:ext" id="1" ext:flag="on" lang="en" class="big" hidden="true">
+      <name>lamp</name>
+    </item>"#;
+
+  let item: Item = yaserde::de::from_str(content).unwrap();
+  let flag = QualifiedName {
+    local_name: "flag".to_string(),
+    namespace: Some("urn:example:ext".to_string()),
+    prefix: Some("ext".to_string()),
+  };
+  let mut other = BTreeMap::new();
+  other.insert(QualifiedName::local("hidden"), "true".to_string());
+  other.insert(flag, "on".to_string());
+
+  let model = Item {
+    id: 1,
+    common: CommonAttributes {
+      lang: "en".to_string(),
+      core: CoreAttributes {
+        class: Some("big".to_string()),
+      },
+    },
+    other,
+    name: "lamp".to_string(),
+  };
+  assert_eq!(item, model);
+
+  let content = r#"
+    <item xmlns:ext="urn:example:ext" id="1" lang="en" class="big" ext:flag="on" hidden="true">
+      <name>lamp</name>
+    </item>"#;
+  serialize_and_validate!(model, content);
+  deserialize_and_validate!(content, model, Item);
+
+  let model = Item {
+    id: 2,
+    name: "desk".to_string(),
+    ..Item::default()
+  };
+  let content = r#"<item id="2" lang=""><name>desk</name></item>"#;
+  serialize_and_validate!(model, content);
+  deserialize_and_validate!(content, model, Item);
+}
+
+#[test]
+fn any_attributes_with_namespace() {
+  init();
+
+  #[derive(Default, PartialEq, Debug, YaDeserialize, YaSerialize)]
+  #[yaserde(rename = "e")]
+  struct Element {
+    #[yaserde(attribute)]
+    id: String,
+    #[yaserde(any_attribute)]
+    other: BTreeMap<QualifiedName, String>,
+  }
+
+  let content = r#"<e xmlns:x="urn:x" id="1" x:id="2" />"#;
+  let x_id = QualifiedName {
+    local_name: "id".to_string(),
+    namespace: Some("urn:x".to_string()),
+    prefix: Some("x".to_string()),
+  };
+  let mut other = BTreeMap::new();
+  other.insert(x_id, "2".to_string());
+  let model = Element {
+    id: "1".to_string(),
+    other,
+  };
+
+  serialize_and_validate!(model, content);
+  deserialize_and_validate!(content, model, Element);
+
+  // The keys don't depend on the prefix bound to the namespace in the document
+  let first: Element = yaserde::de::from_str(r#"<e xmlns:x="urn:x" id="1" x:id="2" />"#).unwrap();
+  let second: Element = yaserde::de::from_str(r#"<e xmlns:z="urn:x" id="1" z:id="2" />"#).unwrap();
+  assert_eq!(first, second);
+  assert_eq!(second.other.len(), 1);
+  assert_eq!(
+    second.other.keys().next().unwrap().prefix.as_deref(),
+    Some("z")
+  );
+  let key = QualifiedName {
+    local_name: "id".to_string(),
+    namespace: Some("urn:x".to_string()),
+    prefix: None,
+  };
+  assert_eq!(second.other.get(&key).map(String::as_str), Some("2"));
+
+  // Without namespace, the prefix is part of the name
+  assert_ne!(QualifiedName::from("x:id"), QualifiedName::from("z:id"));
+
+  // A field with a prefix reads the attributes in its namespace, or without one
+  #[derive(Default, PartialEq, Debug, YaDeserialize, YaSerialize)]
+  #[yaserde(rename = "e", namespace = "x: urn:x")]
+  struct Prefixed {
+    #[yaserde(attribute, prefix = "x")]
+    id: String,
+    #[yaserde(any_attribute)]
+    other: BTreeMap<QualifiedName, String>,
+  }
+
+  let content = r#"<e xmlns:x="urn:x" xmlns:y="urn:y" x:id="1" y:id="2" />"#;
+  let y_id = QualifiedName {
+    local_name: "id".to_string(),
+    namespace: Some("urn:y".to_string()),
+    prefix: Some("y".to_string()),
+  };
+  let mut other = BTreeMap::new();
+  other.insert(y_id, "2".to_string());
+  let model = Prefixed {
+    id: "1".to_string(),
+    other,
+  };
+
+  serialize_and_validate!(model, content);
+  deserialize_and_validate!(content, model, Prefixed);
+
+  let prefixed: Prefixed = yaserde::de::from_str(r#"<e id="3" />"#).unwrap();
+  assert_eq!(prefixed.id, "3");
+}
+
+#[test]
+fn any_attributes_with_deny_unknown_fields() {
+  init();
+
+  #[derive(Default, PartialEq, Debug, YaDeserialize)]
+  #[yaserde(rename = "book", deny_unknown_fields)]
+  struct Book {
+    #[yaserde(any_attribute)]
+    other: BTreeMap<QualifiedName, String>,
+    title: String,
+  }
+
+  let book: Book = yaserde::de::from_str(r#"<book lang="en"><title>Dune</title></book>"#).unwrap();
+  assert_eq!(
+    book
+      .other
+      .get(&QualifiedName::local("lang"))
+      .map(String::as_str),
+    Some("en")
+  );
+}
@@ -0,0 +1,162 @@
+#[macro_use]
+extern crate yaserde_derive;
+
+use std::borrow::Cow;
+use yaserde::de::{from_reader, from_str, from_str_with_config, Config, Error, ErrorKind};
+
+fn init() {
+  let _ = env_logger::builder().is_test(true).try_init();
+}
+
+#[derive(Debug, PartialEq, YaDeserialize)]
+#[yaserde(rename = "book")]
+pub struct Book<'a> {
+  #[yaserde(attribute)]
+  isbn: &'a str,
+  #[yaserde(attribute, borrow)]
+  lang: Cow<'a, str>,
+  title: &'a str,
+  #[yaserde(borrow)]
+  author: Cow<'a, str>,
+  tags: Vec<&'a str>,
+  note: Option<&'a str>,
+}
+
+#[derive(Debug, PartialEq, YaDeserialize)]
+#[yaserde(rename = "label")]
+pub struct Label<'a> {
+  #[yaserde(text, borrow)]
+  text: Cow<'a, str>,
+}
+
+fn is_borrowed_from(value: &str, input: &str) -> bool {
+  input.as_bytes().as_ptr_range().contains(&value.as_ptr())
+}
+
+#[test]
+fn borrow_str() {
+  init();
+
+  let content = r#"<book isbn="978-0" lang="en">
+    <title>Dune</title>
+    <author>Frank Herbert</author>
+    <tags>sf</tags>
+    <tags>classic</tags>
+    <note>first</note>
+  </book>"#;
+  let book: Book = from_str(content).unwrap();
+
+  assert_eq!(
+    book,
+    Book {
+      isbn: "978-0",
+      lang: Cow::Borrowed("en"),
+      title: "Dune",
+      author: Cow::Borrowed("Frank Herbert"),
+      tags: vec!["sf", "classic"],
+      note: Some("first"),
+    }
+  );
+  for value in [book.isbn, book.title, book.tags[0], book.tags[1]] {
+    assert!(
+      is_borrowed_from(value, content),
+      "{:?} is not borrowed",
+      value
+    );
+  }
+  assert!(matches!(book.lang, Cow::Borrowed(lang) if is_borrowed_from(lang, content)));
+  assert!(matches!(book.author, Cow::Borrowed(author) if is_borrowed_from(author, content)));
+}
+
+#[test]
+fn borrow_cow_text() {
+  init();
+
+  let content = "<label>\n  ready\n</label>";
+  let label: Label = from_str(content).unwrap();
+  assert!(matches!(label.text, Cow::Borrowed("ready")));
+
+  let config = Config {
+    trim_whitespace: false,
+    ..Config::default()
+  };
+  let label: Label = from_str_with_config(content, &config).unwrap();
+  assert!(matches!(label.text, Cow::Borrowed("\n  ready\n")));
+}
+
+#[test]
+fn borrow_unescaped() {
+  init();
+
+  let label: Label = from_str("<label>Fish &amp; Chips</label>").unwrap();
+  assert!(matches!(label.text, Cow::Owned(ref text) if text == "Fish & Chips"));
+
+  let label: Label = from_str("<label><![CDATA[x]]>y</label>").unwrap();
+  assert!(matches!(label.text, Cow::Owned(ref text) if text == "xy"));
+
+  let content = r#"<book isbn="978-0" lang="en"><title>Fish &amp; Chips</title></book>"#;
+  let error = from_str::<Book>(content).map(|_| ()).unwrap_err();
+  assert!(matches!(
+    error.into_kind(),
+    ErrorKind::NotBorrowed(ref value) if value == "Fish & Chips"
+  ));
+}
+
+#[test]
+fn borrow_repeated_value() {
+  init();
+
+  // Values are found in their own element, not in an earlier one holding the same text
+  let content = r#"<book isbn="en" lang="en"><title>en</title><author>en</author></book>"#;
+  let book: Book = from_str(content).unwrap();
+
+  let title_offset = content.find("<title>").unwrap() + "<title>".len();
+  assert_eq!(book.title.as_ptr(), content[title_offset..].as_ptr());
+
+  let lang_offset = content.find("lang=\"").unwrap() + "lang=\"".len();
+  assert!(
+    matches!(book.lang, Cow::Borrowed(lang) if lang.as_ptr() == content[lang_offset..].as_ptr())
+  );
+  let author_offset = content.find("<author>").unwrap() + "<author>".len();
+  assert!(
+    matches!(book.author, Cow::Borrowed(author) if author.as_ptr() == content[author_offset..].as_ptr())
+  );
+}
+
+#[test]
+fn borrow_aliased_cow() {
+  init();
+
+  type Text<'a> = Cow<'a, str>;
+
+  #[derive(Debug, PartialEq, YaDeserialize)]
+  #[yaserde(rename = "label")]
+  pub struct AliasedLabel<'a> {
+    #[yaserde(attribute, borrow)]
+    lang: Text<'a>,
+    #[yaserde(text, borrow)]
+    text: Text<'a>,
+  }
+
+  let label: AliasedLabel = from_str(r#"<label lang="en">ready</label>"#).unwrap();
+  assert!(matches!(label.lang, Cow::Borrowed("en")));
+  assert!(matches!(label.text, Cow::Borrowed("ready")));
+}
+
+#[test]
+fn owned_from_reader() {
+  init();
+
+  #[derive(Debug, PartialEq, YaDeserialize)]
+  #[yaserde(rename = "label")]
+  pub struct OwnedLabel {
+    #[yaserde(text)]
+    text: Cow<'static, str>,
+  }
+
+  let label: OwnedLabel = from_reader("<label>ready</label>".as_bytes()).unwrap();
+  assert_eq!(label.text, "ready");
+
+  let result: Result<OwnedLabel, Error> = from_str("<label>ready</label>");
+  assert!(matches!(result.unwrap().text, Cow::Owned(_)));
+}
@@ -120,6 +120,35 @@ fn default_attribute_string() {
   deserialize_and_validate!(content, model, XmlStruct);
 }
 
+#[test]
+fn default_trait_field() {
+  init();
+
+  #[derive(Debug, PartialEq, YaDeserialize, YaSerialize)]
+  #[yaserde(rename = "base")]
+  pub struct XmlStruct {
+    #[yaserde(attribute, default)]
+    width: u32,
+    #[yaserde(default)]
+    background: String,
+  }
+
+  let content = "<base />";
+  let model = XmlStruct {
+    width: 0,
+    background: String::new(),
+  };
+  deserialize_and_validate!(content, model, XmlStruct);
+
+  let content = r#"<base width="12"><background>red</background></base>"#;
+  let model = XmlStruct {
+    width: 12,
+    background: "red".to_string(),
+  };
+  serialize_and_validate!(model, content);
+  deserialize_and_validate!(content, model, XmlStruct);
+}
+
 #[test]
 fn module_inclusion() {
   init();
@@ -0,0 +1,235 @@
+#[macro_use]
+extern crate yaserde;
+#[macro_use]
+extern crate yaserde_derive;
+
+use yaserde::de::{from_str, from_str_with_config, Config, Error};
+
+fn init() {
+  let _ = env_logger::builder().is_test(true).try_init();
+}
+
+#[test]
+fn deny_unknown_fields_struct() {
+  init();
+
+  #[derive(Debug, Default, PartialEq, YaDeserialize)]
+  #[yaserde(rename = "book", deny_unknown_fields)]
+  pub struct Book {
+    #[yaserde(attribute)]
+    isbn: String,
+    author: String,
+  }
+
+  #[derive(Debug, Default, PartialEq, YaDeserialize)]
+  #[yaserde(rename = "shelf")]
+  pub struct Shelf {
+    #[yaserde(rename = "book")]
+    books: Vec<Book>,
+  }
+
+  let content = r#"<book isbn="123"><author>Antoine</author></book>"#;
+  let model = Book {
+    isbn: "123".to_string(),
+    author: "Antoine".to_string(),
+  };
+  deserialize_and_validate!(content, model, Book);
+
+  let loaded: Result<Book, Error> =
+    from_str(r#"<book isbn="123"><author>Antoine</author><autor>Antoine</autor></book>"#);
+  let error = loaded.unwrap_err();
+  assert_eq!(error.kind().to_string(), "unknown element <autor> in book");
+  assert_eq!(error.path(), Some("/book/autor"));
+
+  let loaded: Result<Book, Error> =
+    from_str(r#"<book isbn="123" isnb="123"><author>Antoine</author></book>"#);
+  assert_eq!(
+    loaded.map_err(|error| error.kind().to_string()),
+    Err("unknown attribute isnb in book".to_string())
+  );
+
+  let loaded: Result<Book, Error> =
+    from_str(r#"<book isbn="123">Little prince<author>Antoine</author></book>"#);
+  let error = loaded.unwrap_err();
+  assert_eq!(
+    error.kind().to_string(),
+    r#"unknown text "Little prince" in book"#
+  );
+  // Located at the text, not at the start of the container
+  assert_eq!((error.line(), error.column()), (Some(1), Some(18)));
+  assert_eq!(error.path(), Some("/book"));
+
+  let loaded: Result<Shelf, Error> =
+    from_str(r#"<shelf><book isbn="1"><title>Little prince</title></book></shelf>"#);
+  assert_eq!(
+    loaded.map_err(|error| error.kind().to_string()),
+    Err("unknown element <title> in book".to_string())
+  );
+}
+
+#[test]
+fn deny_unknown_fields_enum() {
+  init();
+
+  #[derive(Debug, Default, PartialEq, YaDeserialize)]
+  #[yaserde(deny_unknown_fields)]
+  pub enum Color {
+    #[default]
+    Red,
+    Blue,
+    #[yaserde(rename = "rgb")]
+    Rgb(String),
+  }
+
+  #[derive(Debug, Default, PartialEq, YaDeserialize)]
+  #[yaserde(rename = "paint")]
+  pub struct Paint {
+    color: Color,
+  }
+
+  let content = r#"<paint><color>Blue</color></paint>"#;
+  let model = Paint { color: Color::Blue };
+  deserialize_and_validate!(content, model, Paint);
+
+  let content = r#"<paint><color><rgb>#fff</rgb></color></paint>"#;
+  let model = Paint {
+    color: Color::Rgb("#fff".to_string()),
+  };
+  deserialize_and_validate!(content, model, Paint);
+
+  let loaded: Result<Paint, Error> = from_str(r#"<paint><color>Green</color></paint>"#);
+  assert_eq!(
+    loaded.map_err(|error| error.kind().to_string()),
+    Err(r#"unknown text "Green" in color"#.to_string())
+  );
+
+  let loaded: Result<Paint, Error> = from_str(r#"<paint><color><cmyk>0</cmyk></color></paint>"#);
+  assert_eq!(
+    loaded.map_err(|error| error.kind().to_string()),
+    Err("unknown element <cmyk> in color".to_string())
+  );
+}
+
+#[test]
+fn deny_unknown_fields_config() {
+  init();
+
+  #[derive(Debug, Default, PartialEq, YaDeserialize)]
+  #[yaserde(rename = "book")]
+  pub struct Book {
+    author: String,
+  }
+
+  let content = r#"<book><author>Antoine</author><title>Little prince</title></book>"#;
+  let model = Book {
+    author: "Antoine".to_string(),
+  };
+  deserialize_and_validate!(content, model, Book);
+
+  let config = Config {
+    deny_unknown_fields: true,
+    ..Config::default()
+  };
+  let loaded: Result<Book, Error> = from_str_with_config(content, &config);
+  assert_eq!(
+    loaded.map_err(|error| error.kind().to_string()),
+    Err("unknown element <title> in book".to_string())
+  );
+}
+
+#[test]
+fn deny_unknown_fields_with_flatten() {
+  init();
+
+  #[derive(Debug, Default, PartialEq, YaDeserialize)]
+  pub struct Details {
+    title: String,
+  }
+
+  #[derive(Debug, Default, PartialEq, YaDeserialize)]
+  #[yaserde(rename = "book", deny_unknown_fields)]
+  pub struct Book {
+    author: String,
+    #[yaserde(flatten)]
+    details: Details,
+  }
+
+  let content = r#"<book><author>Antoine</author><title>Little prince</title></book>"#;
+  let model = Book {
+    author: "Antoine".to_string(),
+    details: Details {
+      title: "Little prince".to_string(),
+    },
+  };
+  deserialize_and_validate!(content, model, Book);
+
+  let loaded: Result<Book, Error> = from_str(
+    r#"<book><author>Antoine</author><title>Little prince</title><year>1943</year></book>"#,
+  );
+  assert_eq!(
+    loaded.map_err(|error| error.kind().to_string()),
+    Err("unknown element <year> in book".to_string())
+  );
+
+  let loaded: Result<Book, Error> =
+    from_str(r#"<book isbn="123"><author>Antoine</author><title>Little prince</title></book>"#);
+  assert_eq!(
+    loaded.map_err(|error| error.kind().to_string()),
+    Err("unknown attribute isbn in book".to_string())
+  );
+
+  let loaded: Result<Book, Error> =
+    from_str(r#"<book>Little prince<author>Antoine</author></book>"#);
+  assert_eq!(
+    loaded.map_err(|error| error.kind().to_string()),
+    Err(r#"unknown text "Little prince" in book"#.to_string())
+  );
+}
+
+#[test]
+fn deny_unknown_fields_with_nested_flatten() {
+  init();
+
+  #[derive(Debug, Default, PartialEq, YaDeserialize)]
+  pub struct Publication {
+    #[yaserde(attribute)]
+    isbn: String,
+    year: u16,
+  }
+
+  #[derive(Debug, Default, PartialEq, YaDeserialize)]
+  pub struct Details {
+    title: String,
+    #[yaserde(flatten)]
+    publication: Publication,
+  }
+
+  #[derive(Debug, Default, PartialEq, YaDeserialize)]
+  #[yaserde(rename = "book", deny_unknown_fields)]
+  pub struct Book {
+    author: String,
+    #[yaserde(flatten)]
+    details: Details,
+  }
+
+  let content = r#"<book isbn="123"><author>Antoine</author><title>Little prince</title><year>1943</year></book>"#;
+  let model = Book {
+    author: "Antoine".to_string(),
+    details: Details {
+      title: "Little prince".to_string(),
+      publication: Publication {
+        isbn: "123".to_string(),
+        year: 1943,
+      },
+    },
+  };
+  deserialize_and_validate!(content, model, Book);
+
+  let loaded: Result<Book, Error> = from_str(
+    r#"<book><author>Antoine</author><title>Little prince</title><pages>96</pages></book>"#,
+  );
+  assert_eq!(
+    loaded.map_err(|error| error.kind().to_string()),
+    Err("unknown element <pages> in book".to_string())
+  );
+}
@@ -1,3 +1,5 @@
+#![allow(clippy::derivable_impls)]
+
 #[macro_use]
 extern crate yaserde;
 #[macro_use]
@@ -15,8 +17,8 @@ fn init() {
 macro_rules! convert_and_validate {
   ($content: expr, $struct: tt, $model: expr) => {
     debug!("convert_and_validate @ {}:{}", file!(), line!());
-    let loaded: Result<$struct, String> = from_str($content);
-    assert_eq!(loaded, Ok($model));
+    let loaded: Result<$struct, yaserde::de::Error> = from_str($content);
+    assert_eq!(loaded.map_err(|e| e.to_string()), Ok($model));
   };
 }
 
@@ -244,15 +246,17 @@ fn de_attributes_custom_deserializer() {
   mod other_mod {
     use super::*;
 
-    use xml::reader::XmlEvent;
+    use yaserde::event::XmlEvent;
 
     #[derive(Debug, Default, PartialEq)]
     pub struct Attributes {
       pub items: Vec<String>,
     }
 
-    impl YaDeserialize for Attributes {
-      fn deserialize<R: Read>(reader: &mut yaserde::de::Deserializer<R>) -> Result<Self, String> {
+    impl<'de> YaDeserialize<'de> for Attributes {
+      fn deserialize<R: Read>(
+        reader: &mut yaserde::de::Deserializer<'de, R>,
+      ) -> Result<Self, yaserde::de::Error> {
         loop {
           match reader.next_event()? {
             XmlEvent::StartElement { .. } => {}
@@ -269,7 +273,13 @@ fn de_attributes_custom_deserializer() {
           }
         }
 
-        Err("Unable to parse attribute".to_string())
+        Err(yaserde::de::Error::custom("Unable to parse attribute"))
+      }
+    }
+
+    impl yaserde::YaDeserializeValue<'_> for Attributes {
+      fn deserialize_value(value: &str) -> Result<Self, yaserde::de::Error> {
+        yaserde::de::from_str_content("Attributes", value)
       }
     }
   }
@@ -489,6 +499,7 @@ fn de_enum() {
     }
   }
 
+  #[allow(dead_code)]
   #[derive(YaDeserialize, PartialEq, Debug)]
   pub struct RGBColor {
     red: String,
@@ -524,6 +535,15 @@ fn de_enum() {
       items: vec![Color::Black, Color::White],
     }
   );
+  let content =
+    "<?xml version=\"1.0\" encoding=\"utf-8\"?><base><background><Black /></background></base>";
+  convert_and_validate!(
+    content,
+    XmlStruct,
+    XmlStruct {
+      background: Color::Black,
+    }
+  );
 }
 
 #[test]
@@ -820,29 +840,31 @@ fn de_custom() {
     value: i32,
   }
 
-  impl YaDeserialize for Day {
-    fn deserialize<R: Read>(reader: &mut yaserde::de::Deserializer<R>) -> Result<Self, String> {
+  impl<'de> YaDeserialize<'de> for Day {
+    fn deserialize<R: Read>(
+      reader: &mut yaserde::de::Deserializer<'de, R>,
+    ) -> Result<Self, yaserde::de::Error> {
       use std::str::FromStr;
 
-      if let xml::reader::XmlEvent::StartElement { name, .. } = reader.peek()?.to_owned() {
+      if let yaserde::event::XmlEvent::StartElement { name, .. } = reader.peek()?.to_owned() {
         let expected_name = "Day".to_owned();
         if name.local_name != expected_name {
-          return Err(format!(
+          return Err(yaserde::de::Error::custom(format!(
             "Wrong StartElement name: {}, expected: {}",
             name, expected_name
-          ));
+          )));
         }
         let _next = reader.next_event();
       } else {
-        return Err("StartElement missing".to_string());
+        return Err(yaserde::de::Error::custom("StartElement missing"));
       }
 
-      if let xml::reader::XmlEvent::Characters(text) = reader.peek()?.to_owned() {
+      if let yaserde::event::XmlEvent::Characters(text) = reader.peek()?.to_owned() {
         Ok(Day {
           value: 2 * i32::from_str(&text).unwrap(),
         })
       } else {
-        Err("Characters missing".to_string())
+        Err(yaserde::de::Error::custom("Characters missing"))
       }
     }
   }
@@ -1074,3 +1096,100 @@ fn de_same_field_name_but_some_other_fields_or_something() {
   serialize_and_validate!(model, content);
   deserialize_and_validate!(content, model, FooOuter);
 }
+
+#[test]
+fn de_config_whitespace() {
+  init();
+
+  #[derive(Debug, PartialEq, YaDeserialize)]
+  #[yaserde(rename = "poem")]
+  pub struct Poem {
+    title: String,
+    #[yaserde(rename = "line")]
+    lines: Vec<String>,
+  }
+
+  let content = r#"
+    <poem>
+      <title>  Indented title</title>
+      <line>first line </line>
+      <line>  second line</line>
+    </poem>
+  "#;
+
+  let model = Poem {
+    title: "Indented title".to_string(),
+    lines: vec!["first line".to_string(), "second line".to_string()],
+  };
+  deserialize_and_validate!(content, model, Poem);
+
+  let config = yaserde::de::Config {
+    trim_whitespace: false,
+    whitespace_to_characters: false,
+    ..yaserde::de::Config::default()
+  };
+  let loaded: Result<Poem, yaserde::de::Error> =
+    yaserde::de::from_str_with_config(content, &config);
+  assert_eq!(
+    loaded.map_err(|error| error.to_string()),
+    Ok(Poem {
+      title: "  Indented title".to_string(),
+      lines: vec!["first line ".to_string(), "  second line".to_string()],
+    })
+  );
+}
+
+#[test]
+fn de_config_entities_and_comments() {
+  init();
+
+  #[derive(Debug, PartialEq, YaDeserialize)]
+  #[yaserde(rename = "note")]
+  pub struct Note {
+    body: String,
+  }
+
+  let content = "<note><body>Caf&eacute; <!-- closed on mondays -->au lait</body></note>";
+
+  let loaded: Result<Note, yaserde::de::Error> = from_str(content);
+  assert!(loaded.is_err());
+
+  let mut config = yaserde::de::Config::default();
+  config
+    .extra_entities
+    .insert("eacute".to_string(), "é".to_string());
+  let loaded: Result<Note, yaserde::de::Error> =
+    yaserde::de::from_str_with_config(content, &config);
+  assert_eq!(
+    loaded.map_err(|error| error.to_string()),
+    Ok(Note {
+      body: "Café au lait".to_string(),
+    })
+  );
+
+  let content = "<note><body>Caf&#xD800;</body></note>";
+  let loaded: Result<Note, yaserde::de::Error> = from_str(content);
+  assert!(loaded.is_err());
+
+  let config = yaserde::de::Config {
+    replace_unknown_entity_references: true,
+    ..yaserde::de::Config::default()
+  };
+  let loaded: Result<Note, yaserde::de::Error> =
+    yaserde::de::from_str_with_config(content, &config);
+  assert_eq!(
+    loaded.map_err(|error| error.to_string()),
+    Ok(Note {
+      body: "Caf\u{fffd}".to_string(),
+    })
+  );
+
+  let content = "<note><body><![CDATA[<b>bold</b>]]></body></note>";
+  let loaded: Result<Note, yaserde::de::Error> = from_str(content);
+  assert_eq!(
+    loaded.map_err(|error| error.to_string()),
+    Ok(Note {
+      body: "<b>bold</b>".to_string(),
+    })
+  );
+}
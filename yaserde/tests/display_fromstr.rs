@@ -0,0 +1,191 @@
+#[macro_use]
+extern crate yaserde;
+#[macro_use]
+extern crate yaserde_derive;
+
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+use yaserde::de::{Error, ErrorKind};
+
+fn init() {
+  let _ = env_logger::builder().is_test(true).try_init();
+}
+
+#[test]
+fn display_fromstr_fields() {
+  init();
+
+  fn any_v4() -> Ipv4Addr {
+    Ipv4Addr::UNSPECIFIED
+  }
+
+  fn any_v6() -> Ipv6Addr {
+    Ipv6Addr::UNSPECIFIED
+  }
+
+  fn any_address() -> IpAddr {
+    any_v4().into()
+  }
+
+  #[derive(Debug, PartialEq, YaDeserialize, YaSerialize)]
+  #[yaserde(rename = "host")]
+  pub struct Host {
+    #[yaserde(attribute, display_fromstr, default = "any_address")]
+    address: IpAddr,
+    #[yaserde(display_fromstr, default = "any_v4")]
+    gateway: Ipv4Addr,
+    #[yaserde(text, display_fromstr, default = "any_v6")]
+    link: Ipv6Addr,
+  }
+
+  let model = Host {
+    address: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)),
+    gateway: Ipv4Addr::new(10, 0, 0, 1),
+    link: Ipv6Addr::LOCALHOST,
+  };
+  let content = r#"<host address="10.0.0.2"><gateway>10.0.0.1</gateway>::1</host>"#;
+
+  serialize_and_validate!(model, content);
+  deserialize_and_validate!(content, model, Host);
+}
+
+#[test]
+fn display_fromstr_container() {
+  init();
+
+  #[derive(Debug, Default, PartialEq, YaDeserialize, YaSerialize)]
+  #[yaserde(rename = "id", display_fromstr)]
+  pub struct Id {
+    kind: String,
+    number: u32,
+  }
+
+  impl fmt::Display for Id {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+      write!(f, "{}-{}", self.kind, self.number)
+    }
+  }
+
+  impl FromStr for Id {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+      let (kind, number) = s.split_once('-').ok_or("missing separator")?;
+      let number = number.parse().map_err(|_| "invalid number")?;
+
+      Ok(Id {
+        kind: kind.to_string(),
+        number,
+      })
+    }
+  }
+
+  #[derive(Debug, Default, PartialEq, YaDeserialize, YaSerialize)]
+  #[yaserde(rename = "order")]
+  pub struct Order {
+    #[yaserde(attribute)]
+    id: Id,
+    parent: Option<Id>,
+    #[yaserde(rename = "item")]
+    items: Vec<Id>,
+  }
+
+  let model = Order {
+    id: Id {
+      kind: "order".to_string(),
+      number: 12,
+    },
+    parent: Some(Id {
+      kind: "order".to_string(),
+      number: 3,
+    }),
+    items: vec![
+      Id {
+        kind: "item".to_string(),
+        number: 1,
+      },
+      Id {
+        kind: "item".to_string(),
+        number: 2,
+      },
+    ],
+  };
+  let content = r#"<order id="order-12"><parent>order-3</parent><item>item-1</item><item>item-2</item></order>"#;
+
+  serialize_and_validate!(model, content);
+  deserialize_and_validate!(content, model, Order);
+
+  let model = Id {
+    kind: "root".to_string(),
+    number: 1,
+  };
+  let content = r#"<id>root-1</id>"#;
+
+  serialize_and_validate!(model, content);
+  deserialize_and_validate!(content, model, Id);
+}
+
+#[test]
+fn display_fromstr_invalid_value() {
+  init();
+
+  fn any_address() -> IpAddr {
+    Ipv4Addr::UNSPECIFIED.into()
+  }
+
+  #[derive(Debug, PartialEq, YaDeserialize)]
+  #[yaserde(rename = "host")]
+  pub struct Host {
+    #[yaserde(attribute, display_fromstr, default = "any_address")]
+    address: IpAddr,
+  }
+
+  let loaded: Result<Host, Error> = yaserde::de::from_str(r#"<host address="10.0.0" />"#);
+  let error = loaded.unwrap_err();
+
+  match error.kind() {
+    ErrorKind::InvalidValue {
+      value, expected, ..
+    } => {
+      assert_eq!(value, "10.0.0");
+      assert!(expected.ends_with("IpAddr"));
+    }
+    kind => panic!("unexpected error {:?}", kind),
+  }
+}
+
+#[test]
+fn display_fromstr_required() {
+  init();
+
+  #[derive(Debug, PartialEq, YaDeserialize, YaSerialize)]
+  #[yaserde(rename = "host")]
+  pub struct Host {
+    #[yaserde(attribute, display_fromstr)]
+    address: IpAddr,
+    #[yaserde(display_fromstr)]
+    gateway: Ipv4Addr,
+  }
+
+  let content = r#"<host address="10.0.0.2"><gateway>10.0.0.1</gateway></host>"#;
+  let model = Host {
+    address: Ipv4Addr::new(10, 0, 0, 2).into(),
+    gateway: Ipv4Addr::new(10, 0, 0, 1),
+  };
+  serialize_and_validate!(model, content);
+  deserialize_and_validate!(content, model, Host);
+
+  let loaded: Result<Host, Error> = yaserde::de::from_str(r#"<host address="10.0.0.2" />"#);
+  assert_eq!(
+    loaded.map_err(|error| error.kind().to_string()),
+    Err("missing element <gateway> in host".to_string())
+  );
+
+  let loaded: Result<Host, Error> =
+    yaserde::de::from_str(r#"<host><gateway>10.0.0.1</gateway></host>"#);
+  assert_eq!(
+    loaded.map_err(|error| error.kind().to_string()),
+    Err("missing attribute address in host".to_string())
+  );
+}
@@ -1,3 +1,5 @@
+#![allow(clippy::derivable_impls)]
+
 #[macro_use]
 extern crate yaserde;
 #[macro_use]
@@ -114,8 +116,7 @@ fn basic_enum() {
 </base>"#;
 
   serialize_and_validate!(model, content);
-  // TODO
-  // deserialize_and_validate!(content, model, XmlStruct);
+  deserialize_and_validate!(content, model, XmlStruct);
 }
 
 #[test]
@@ -374,3 +375,51 @@ fn unnamed_enum() {
   serialize_and_validate!(model, content);
   deserialize_and_validate!(content, model, XmlStruct);
 }
+
+#[test]
+fn named_fields_enum() {
+  #[derive(Debug, PartialEq, YaDeserialize, YaSerialize)]
+  #[yaserde(rename = "shape")]
+  pub enum Shape {
+    Point,
+    Circle {
+      radius: u32,
+    },
+    Rectangle {
+      width: u32,
+      height: u32,
+      #[yaserde(rename = "label")]
+      labels: Vec<String>,
+    },
+  }
+
+  #[derive(Debug, PartialEq, YaDeserialize, YaSerialize)]
+  #[yaserde(rename = "drawing")]
+  pub struct Drawing {
+    #[yaserde(rename = "shape")]
+    shapes: Vec<Shape>,
+  }
+
+  let model = Drawing {
+    shapes: vec![
+      Shape::Point,
+      Shape::Circle { radius: 2 },
+      Shape::Rectangle {
+        width: 3,
+        height: 4,
+        labels: vec!["door".to_string(), "window".to_string()],
+      },
+    ],
+  };
+
+  let content = "<drawing><shape>Point</shape><shape><radius>2</radius></shape><shape><width>3</width><height>4</height><label>door</label><label>window</label></shape></drawing>";
+  serialize_and_validate!(model, content);
+  deserialize_and_validate!(content, model, Drawing);
+
+  let loaded: Result<Shape, yaserde::de::Error> =
+    yaserde::de::from_str("<shape><width>3</width></shape>");
+  assert_eq!(
+    loaded.map_err(|error| error.kind().to_string()),
+    Err("missing element <height> in shape".to_string())
+  );
+}
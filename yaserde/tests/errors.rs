@@ -1,7 +1,7 @@
 #[macro_use]
 extern crate yaserde_derive;
 
-use yaserde::de::from_str;
+use yaserde::de::{from_str, Error, ErrorKind};
 
 fn init() {
   let _ = env_logger::builder().is_test(true).try_init();
@@ -19,9 +19,9 @@ fn de_no_content() {
   }
 
   let content = "";
-  let loaded: Result<Book, String> = from_str(content);
+  let loaded: Result<Book, Error> = from_str(content);
   assert_eq!(
-    loaded,
+    loaded.map_err(|e| e.kind().to_string()),
     Err("Unexpected end of stream: no root element found".to_owned())
   );
 }
@@ -38,9 +38,254 @@ fn de_wrong_end_balise() {
   }
 
   let content = "<book><author>Antoine de Saint-Exupéry<title>Little prince</title></book>";
-  let loaded: Result<Book, String> = from_str(content);
+  let loaded: Result<Book, Error> = from_str(content);
   assert_eq!(
-    loaded,
+    loaded.map_err(|e| e.kind().to_string()),
     Err("Unexpected closing tag: book, expected author".to_owned())
   );
 }
+
+#[test]
+fn de_error_kinds() {
+  use std::error::Error as _;
+
+  init();
+
+  #[derive(YaDeserialize, PartialEq, Debug)]
+  #[yaserde(rename = "book")]
+  pub struct Book {
+    author: String,
+  }
+
+  let loaded: Result<Book, Error> = from_str("<book><author>Antoine</title></book>");
+  let error = loaded.unwrap_err();
+  assert!(matches!(error.kind(), ErrorKind::Syntax(_)));
+  assert!(error.source().is_some());
+
+  #[derive(YaDeserialize, PartialEq, Debug)]
+  #[yaserde(rename = "price")]
+  pub struct Price {
+    #[yaserde(text)]
+    value: u32,
+  }
+
+  let loaded: Result<Price, Error> = from_str("<price>twelve</price>");
+  match loaded.as_ref().map_err(|e| e.kind()) {
+    Err(ErrorKind::InvalidValue {
+      value, expected, ..
+    }) => {
+      assert_eq!(value, "twelve");
+      assert_eq!(expected, "u32");
+    }
+    other => panic!("unexpected result {:?}", other),
+  }
+}
+
+#[test]
+fn de_missing_field() {
+  use std::num::NonZeroU32;
+
+  init();
+
+  // Required fields don't need a Default implementation
+  #[derive(YaDeserialize, PartialEq, Debug)]
+  #[yaserde(rename = "book")]
+  pub struct Book {
+    #[yaserde(attribute)]
+    id: NonZeroU32,
+    author: String,
+    translator: Option<String>,
+    #[yaserde(rename = "tag")]
+    tags: Vec<String>,
+  }
+
+  let loaded: Result<Book, Error> = from_str(r#"<book id="1"><author>Antoine</author></book>"#);
+  assert_eq!(
+    loaded.map_err(|e| e.to_string()),
+    Ok(Book {
+      id: NonZeroU32::new(1).unwrap(),
+      author: "Antoine".to_string(),
+      translator: None,
+      tags: vec![],
+    })
+  );
+
+  let loaded: Result<Book, Error> = from_str(r#"<book id="1"><tag>tale</tag></book>"#);
+  let error = loaded.unwrap_err();
+  assert!(matches!(error.kind(), ErrorKind::MissingField { .. }));
+  assert_eq!(error.kind().to_string(), "missing element <author> in book");
+
+  let loaded: Result<Book, Error> = from_str(r#"<book><author>Antoine</author></book>"#);
+  assert_eq!(
+    loaded.map_err(|e| e.kind().to_string()),
+    Err("missing attribute id in book".to_string())
+  );
+}
+
+#[test]
+fn de_missing_variant() {
+  init();
+
+  // Enums don't need a Default implementation either
+  #[derive(YaDeserialize, PartialEq, Debug)]
+  pub enum Binding {
+    Hardcover,
+    Paperback,
+    #[yaserde(rename = "other")]
+    Other(String),
+  }
+
+  #[derive(YaDeserialize, PartialEq, Debug)]
+  #[yaserde(rename = "book")]
+  pub struct Book {
+    binding: Binding,
+  }
+
+  let loaded: Result<Book, Error> = from_str(r#"<book><binding>Paperback</binding></book>"#);
+  assert_eq!(
+    loaded.map_err(|e| e.to_string()),
+    Ok(Book {
+      binding: Binding::Paperback,
+    })
+  );
+
+  let loaded: Result<Book, Error> = from_str(r#"<book><binding>Spiral</binding></book>"#);
+  let error = loaded.unwrap_err();
+  assert!(matches!(error.kind(), ErrorKind::MissingField { .. }));
+  assert_eq!(
+    error.kind().to_string(),
+    "missing variant of Binding in binding"
+  );
+
+  let loaded: Result<Book, Error> = from_str(r#"<book><binding><spiral /></binding></book>"#);
+  assert_eq!(
+    loaded.map_err(|e| e.kind().to_string()),
+    Err("missing variant of Binding in binding".to_string())
+  );
+
+  let loaded: Result<Book, Error> = from_str(r#"<book></book>"#);
+  assert_eq!(
+    loaded.map_err(|e| e.kind().to_string()),
+    Err("missing element <binding> in book".to_string())
+  );
+}
+
+#[test]
+fn de_bad_namespace_kind() {
+  init();
+
+  #[derive(YaDeserialize, PartialEq, Debug)]
+  #[yaserde(
+    rename = "book",
+    prefix = "ns",
+    namespace = "ns: http://www.sample.com/ns/domain"
+  )]
+  pub struct Book {
+    #[yaserde(prefix = "ns")]
+    author: String,
+  }
+
+  let content =
+    r#"<ns:book xmlns:ns="http://www.sample.com/ns/other"><ns:author>A</ns:author></ns:book>"#;
+  let loaded: Result<Book, Error> = from_str(content);
+  match loaded.as_ref().map_err(|e| e.kind()) {
+    Err(ErrorKind::BadNamespace { element, namespace }) => {
+      assert_eq!(element, "book");
+      assert_eq!(namespace, "http://www.sample.com/ns/other");
+    }
+    other => panic!("unexpected result {:?}", other),
+  }
+}
+
+#[test]
+fn de_error_location() {
+  init();
+
+  #[derive(YaDeserialize, PartialEq, Debug)]
+  #[yaserde(rename = "device")]
+  pub struct Device {
+    peripherals: Peripherals,
+  }
+
+  #[derive(YaDeserialize, Default, PartialEq, Debug)]
+  pub struct Peripherals {
+    peripheral: Vec<Peripheral>,
+  }
+
+  #[derive(YaDeserialize, Default, PartialEq, Debug)]
+  pub struct Peripheral {
+    name: String,
+    #[yaserde(rename = "baseAddress")]
+    base_address: u32,
+  }
+
+  let content = r#"<device>
+  <peripherals>
+    <peripheral>
+      <name>GPIOA</name>
+      <baseAddress>1073872896</baseAddress>
+    </peripheral>
+    <peripheral>
+      <name>GPIOB</name>
+      <baseAddress>0x40020400</baseAddress>
+    </peripheral>
+  </peripherals>
+</device>"#;
+
+  let error = from_str::<Device>(content).unwrap_err();
+  assert!(matches!(error.kind(), ErrorKind::InvalidValue { .. }));
+  assert_eq!(error.line(), Some(9));
+  assert_eq!(error.column(), Some(20));
+  assert_eq!(
+    error.path(),
+    Some("/device/peripherals/peripheral[2]/baseAddress")
+  );
+  assert_eq!(
+    error.to_string(),
+    r#"invalid value "0x40020400" for u32: invalid digit found in string at line 9, column 20 in /device/peripherals/peripheral[2]/baseAddress
+8 |       <name>GPIOB</name>
+9 |       <baseAddress>0x40020400</baseAddress>
+  |                    ^"#
+  );
+}
+
+#[test]
+fn de_syntax_error_location() {
+  init();
+
+  #[derive(YaDeserialize, PartialEq, Debug)]
+  #[yaserde(rename = "book")]
+  pub struct Book {
+    author: String,
+    title: String,
+  }
+
+  let content = "<book>\n  <author>Antoine</author>\n  <title>Little prince</titl>\n</book>";
+  let error = from_str::<Book>(content).unwrap_err();
+  assert!(matches!(error.kind(), ErrorKind::Syntax(_)));
+  assert_eq!(error.line(), Some(3));
+  assert_eq!(error.path(), Some("/book/title"));
+}
+
+#[test]
+fn ser_error_source() {
+  init();
+
+  use std::error::Error as _;
+  use yaserde::event::XmlEvent;
+  use yaserde::ser::{Config, Error, Serializer};
+
+  // The end element doesn't have a start element
+  let mut serializer = Serializer::new_from_writer(Vec::new(), &Config::default());
+  let error = serializer.write(XmlEvent::end_element()).unwrap_err();
+
+  if cfg!(feature = "quick-xml") {
+    assert!(matches!(error, Error::Emitter(_)));
+    assert!(error.source().is_none());
+  } else {
+    // The error of xml-rs is kept as the source
+    assert!(matches!(error, Error::Xml(_)));
+    let source = error.source().unwrap();
+    assert_eq!(source.to_string(), error.to_string());
+  }
+}
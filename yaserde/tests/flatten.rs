@@ -1,3 +1,5 @@
+#![allow(clippy::derivable_impls)]
+
 #[macro_use]
 extern crate yaserde;
 #[macro_use]
@@ -201,7 +203,6 @@ fn flatten_attribute_and_child() {
   struct Node {
     #[yaserde(flatten)]
     base: Base,
-    #[yaserde(child)]
     value: StringValue,
   }
 
@@ -240,7 +241,6 @@ fn flatten_name_in_unknown_child() {
   pub struct Node {
     #[yaserde(flatten)]
     base: Base,
-    #[yaserde(child)]
     value: Value,
   }
 
@@ -274,6 +274,373 @@ fn flatten_name_in_unknown_child() {
   let content = r#"<Node id="Foo"><value><Foo /></value></Node>"#;
   serialize_and_validate!(model, content);
 
-  let content = r#"<Node id="Foo"><value><SomethingThatDoesntExist><value></value></SomethingThatDoesntExist></value></Node>"#;
+  let content = r#"<Node id="Foo"><value><SomethingThatDoesntExist><value></value></SomethingThatDoesntExist><Foo /></value></Node>"#;
   deserialize_and_validate!(content, model, Node);
+
+  let content = r#"<Node id="Foo"><value><SomethingThatDoesntExist><value></value></SomethingThatDoesntExist></value></Node>"#;
+  let loaded: Result<Node, yaserde::de::Error> = yaserde::de::from_str(content);
+  assert_eq!(
+    loaded.map_err(|error| error.kind().to_string()),
+    Err("missing variant of Value in value".to_string())
+  );
+}
+
+#[test]
+fn flatten_replayed_events() {
+  init();
+
+  #[derive(Default, PartialEq, Debug, YaDeserialize)]
+  #[yaserde(namespace = "ns: http://www.sample.com/ns/domain")]
+  pub struct Item<'a> {
+    name: &'a str,
+    #[yaserde(flatten)]
+    size: Size,
+    #[yaserde(flatten)]
+    reference: Reference<'a>,
+  }
+
+  #[derive(Default, PartialEq, Debug, YaDeserialize)]
+  pub struct Size {
+    width: u32,
+    height: u32,
+  }
+
+  #[derive(Default, PartialEq, Debug, YaDeserialize)]
+  #[yaserde(namespace = "ns: http://www.sample.com/ns/domain")]
+  pub struct Reference<'a> {
+    #[yaserde(prefix = "ns")]
+    id: &'a str,
+  }
+
+  let content = r#"<Item xmlns:ns="http://www.sample.com/ns/domain">
+  <name>box</name>
+  <width>4</width>
+  <height>2</height>
+  <ns:id>b-12</ns:id>
+</Item>"#;
+  let item: Item = yaserde::de::from_str(content).unwrap();
+  assert_eq!(
+    item,
+    Item {
+      name: "box",
+      size: Size {
+        width: 4,
+        height: 2
+      },
+      reference: Reference { id: "b-12" },
+    }
+  );
+  // Flattened fields borrow from the document as well
+  let id_offset = content.find("b-12").unwrap();
+  assert_eq!(item.reference.id.as_ptr(), content[id_offset..].as_ptr());
+
+  // Errors are located in the document
+  let content = content.replace("<height>2</height>", "<height>two</height>");
+  let error = yaserde::de::from_str::<Item>(&content).unwrap_err();
+  assert_eq!(error.line(), Some(4));
+  assert_eq!(error.path(), Some("/Item/height"));
+
+  #[derive(Default, PartialEq, Debug, YaDeserialize)]
+  #[yaserde(rename = "List")]
+  pub struct List {
+    #[yaserde(rename = "Sized")]
+    sized: Vec<Sized>,
+  }
+
+  #[derive(Default, PartialEq, Debug, YaDeserialize)]
+  pub struct Sized {
+    #[yaserde(flatten)]
+    size: Size,
+  }
+
+  let content =
+    "<List><Sized><width>1</width><height>1</height></Sized><Sized><width>x</width></Sized></List>";
+  let error = yaserde::de::from_str::<List>(content).unwrap_err();
+  assert_eq!(error.path(), Some("/List/Sized[2]/width"));
+}
+
+#[test]
+fn flatten_sequence() {
+  init();
+
+  #[derive(Default, PartialEq, Debug, YaDeserialize, YaSerialize)]
+  struct Path {
+    name: String,
+    #[yaserde(flatten)]
+    points: Vec<Point>,
+    closed: bool,
+  }
+
+  #[derive(Default, PartialEq, Debug, YaDeserialize, YaSerialize)]
+  struct Point {
+    x: i32,
+    y: i32,
+    label: Vec<String>,
+  }
+
+  let model = Path {
+    name: "triangle".to_string(),
+    points: vec![
+      Point {
+        x: 0,
+        y: 0,
+        label: vec!["origin".to_string(), "start".to_string()],
+      },
+      Point {
+        x: 4,
+        y: 0,
+        label: vec![],
+      },
+      Point {
+        x: 0,
+        y: 3,
+        label: vec!["end".to_string()],
+      },
+    ],
+    closed: true,
+  };
+
+  let content = r#"
+    <Path>
+      <name>triangle</name>
+      <x>0</x><y>0</y><label>origin</label><label>start</label>
+      <x>4</x><y>0</y>
+      <x>0</x><y>3</y><label>end</label>
+      <closed>true</closed>
+    </Path>"#;
+
+  serialize_and_validate!(model, content);
+  deserialize_and_validate!(content, model, Path);
+
+  let model = Path {
+    name: "empty".to_string(),
+    points: vec![],
+    closed: false,
+  };
+
+  let content = "<Path><name>empty</name><closed>false</closed></Path>";
+
+  serialize_and_validate!(model, content);
+  deserialize_and_validate!(content, model, Path);
+}
+
+#[test]
+fn flatten_through_aliases() {
+  init();
+
+  // Sequences and optional values are told by their traits, not by the names of their types
+  type Points = Vec<Point>;
+  type Labels = Vec<String>;
+  type Maybe<T> = Option<T>;
+
+  #[derive(Default, PartialEq, Debug, YaDeserialize, YaSerialize)]
+  struct Path {
+    #[yaserde(flatten)]
+    points: Points,
+    #[yaserde(flatten)]
+    style: Maybe<Style>,
+  }
+
+  #[derive(Default, PartialEq, Debug, YaDeserialize, YaSerialize)]
+  struct Point {
+    x: i32,
+    label: Labels,
+  }
+
+  #[derive(Default, PartialEq, Debug, YaDeserialize, YaSerialize)]
+  struct Style {
+    color: String,
+  }
+
+  let model = Path {
+    points: vec![
+      Point {
+        x: 0,
+        label: vec!["origin".to_string(), "start".to_string()],
+      },
+      Point {
+        x: 4,
+        label: vec![],
+      },
+    ],
+    style: None,
+  };
+
+  let content = "<Path><x>0</x><label>origin</label><label>start</label><x>4</x></Path>";
+
+  serialize_and_validate!(model, content);
+  deserialize_and_validate!(content, model, Path);
+
+  let model = Path {
+    points: vec![],
+    style: Some(Style {
+      color: "red".to_string(),
+    }),
+  };
+
+  let content = "<Path><color>red</color></Path>";
+
+  serialize_and_validate!(model, content);
+  deserialize_and_validate!(content, model, Path);
+}
+
+#[test]
+fn flatten_choice() {
+  init();
+
+  #[derive(Default, PartialEq, Debug, YaDeserialize, YaSerialize)]
+  struct Drawing {
+    #[yaserde(flatten)]
+    frame: Frame,
+    #[yaserde(flatten)]
+    background: Option<Background>,
+    #[yaserde(flatten)]
+    shapes: Vec<Shape>,
+    title: String,
+  }
+
+  #[derive(PartialEq, Debug, YaDeserialize, YaSerialize)]
+  enum Frame {
+    Border(u32),
+    Shadow(u32),
+  }
+
+  impl Default for Frame {
+    fn default() -> Self {
+      Self::Border(0)
+    }
+  }
+
+  #[derive(PartialEq, Debug, YaDeserialize, YaSerialize)]
+  enum Background {
+    Color(String),
+    Pattern(String),
+  }
+
+  impl Default for Background {
+    fn default() -> Self {
+      Self::Color(String::new())
+    }
+  }
+
+  #[derive(PartialEq, Debug, YaDeserialize, YaSerialize)]
+  enum Shape {
+    Circle(Circle),
+    Square(Square),
+  }
+
+  impl Default for Shape {
+    fn default() -> Self {
+      Self::Circle(Circle::default())
+    }
+  }
+
+  #[derive(Default, PartialEq, Debug, YaDeserialize, YaSerialize)]
+  struct Circle {
+    radius: u32,
+  }
+
+  #[derive(Default, PartialEq, Debug, YaDeserialize, YaSerialize)]
+  struct Square {
+    side: u32,
+  }
+
+  let model = Drawing {
+    frame: Frame::Shadow(2),
+    background: Some(Background::Pattern("dots".to_string())),
+    shapes: vec![
+      Shape::Square(Square { side: 4 }),
+      Shape::Circle(Circle { radius: 1 }),
+      Shape::Square(Square { side: 2 }),
+    ],
+    title: "sketch".to_string(),
+  };
+
+  let content = r#"
+    <Drawing>
+      <Shadow>2</Shadow>
+      <Pattern>dots</Pattern>
+      <Square><side>4</side></Square>
+      <Circle><radius>1</radius></Circle>
+      <Square><side>2</side></Square>
+      <title>sketch</title>
+    </Drawing>"#;
+
+  serialize_and_validate!(model, content);
+  deserialize_and_validate!(content, model, Drawing);
+
+  let model = Drawing {
+    frame: Frame::Border(1),
+    background: None,
+    shapes: vec![],
+    title: "blank".to_string(),
+  };
+
+  let content = "<Drawing><Border>1</Border><title>blank</title></Drawing>";
+
+  serialize_and_validate!(model, content);
+  deserialize_and_validate!(content, model, Drawing);
+}
+
+#[test]
+fn flatten_attribute_group() {
+  init();
+
+  #[derive(Default, PartialEq, Debug, YaDeserialize, YaSerialize)]
+  #[yaserde(rename = "cell")]
+  struct Cell {
+    #[yaserde(flatten)]
+    core: CoreAttributes,
+    #[yaserde(flatten)]
+    style: Option<StyleAttributes>,
+    #[yaserde(text)]
+    value: String,
+  }
+
+  #[derive(Default, PartialEq, Debug, YaDeserialize, YaSerialize)]
+  struct CoreAttributes {
+    #[yaserde(attribute)]
+    id: String,
+    #[yaserde(attribute)]
+    class: Option<String>,
+  }
+
+  #[derive(Default, PartialEq, Debug, YaDeserialize, YaSerialize)]
+  struct StyleAttributes {
+    #[yaserde(attribute)]
+    color: String,
+    #[yaserde(attribute)]
+    width: Option<u32>,
+  }
+
+  let model = Cell {
+    core: CoreAttributes {
+      id: "a1".to_string(),
+      class: None,
+    },
+    style: Some(StyleAttributes {
+      color: "red".to_string(),
+      width: Some(3),
+    }),
+    value: "42".to_string(),
+  };
+
+  let content = r#"<cell id="a1" color="red" width="3">42</cell>"#;
+
+  serialize_and_validate!(model, content);
+  deserialize_and_validate!(content, model, Cell);
+
+  let model = Cell {
+    core: CoreAttributes {
+      id: "b2".to_string(),
+      class: Some("total".to_string()),
+    },
+    style: None,
+    value: "7".to_string(),
+  };
+
+  let content = r#"<cell id="b2" class="total">7</cell>"#;
+
+  serialize_and_validate!(model, content);
+  deserialize_and_validate!(content, model, Cell);
 }
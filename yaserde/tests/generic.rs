@@ -0,0 +1,149 @@
+#[macro_use]
+extern crate yaserde;
+#[macro_use]
+extern crate yaserde_derive;
+
+use std::borrow::Cow;
+use std::fmt::Debug;
+
+fn init() {
+  let _ = env_logger::builder().is_test(true).try_init();
+}
+
+#[test]
+fn generic_struct() {
+  init();
+
+  #[derive(Debug, Default, PartialEq, YaDeserialize, YaSerialize)]
+  #[yaserde(rename = "item")]
+  pub struct Item {
+    #[yaserde(attribute)]
+    id: u32,
+  }
+
+  #[derive(Debug, Default, PartialEq, YaDeserialize, YaSerialize)]
+  #[yaserde(rename = "page")]
+  pub struct Page<T> {
+    #[yaserde(attribute)]
+    number: u32,
+    #[yaserde(rename = "item")]
+    items: Vec<T>,
+  }
+
+  let model = Page {
+    number: 2,
+    items: vec![Item { id: 1 }, Item { id: 2 }],
+  };
+  let content = r#"<page number="2"><item id="1" /><item id="2" /></page>"#;
+
+  serialize_and_validate!(model, content);
+  deserialize_and_validate!(content, model, Page<Item>);
+
+  let model = Page {
+    number: 1,
+    items: vec!["a".to_string(), "b".to_string()],
+  };
+  let content = r#"<page number="1"><item>a</item><item>b</item></page>"#;
+
+  serialize_and_validate!(model, content);
+  deserialize_and_validate!(content, model, Page<String>);
+}
+
+#[test]
+fn generic_attribute_and_text() {
+  init();
+
+  #[derive(Debug, Default, PartialEq, YaDeserialize, YaSerialize)]
+  #[yaserde(rename = "value")]
+  pub struct Value<K, V> {
+    #[yaserde(attribute)]
+    key: K,
+    #[yaserde(text)]
+    value: V,
+  }
+
+  let model = Value {
+    key: 'k',
+    value: 12_u64,
+  };
+  let content = r#"<value key="k">12</value>"#;
+
+  serialize_and_validate!(model, content);
+  deserialize_and_validate!(content, model, Value<char, u64>);
+}
+
+#[test]
+fn generic_with_lifetime_and_where_clause() {
+  init();
+
+  #[derive(Debug, Default, PartialEq, YaDeserialize, YaSerialize)]
+  #[yaserde(rename = "envelope")]
+  pub struct Envelope<'a, B>
+  where
+    B: Debug,
+  {
+    #[yaserde(attribute)]
+    version: Cow<'a, str>,
+    body: B,
+  }
+
+  let model = Envelope {
+    version: Cow::Borrowed("1.1"),
+    body: 42_i32,
+  };
+  let content = r#"<envelope version="1.1"><body>42</body></envelope>"#;
+
+  serialize_and_validate!(model, content);
+  deserialize_and_validate!(content, model, Envelope<i32>);
+}
+
+#[test]
+fn generic_enum() {
+  init();
+
+  #[derive(Debug, Default, PartialEq, YaDeserialize, YaSerialize)]
+  pub enum Answer<T> {
+    #[yaserde(rename = "value")]
+    Value(T),
+    #[default]
+    #[yaserde(rename = "none")]
+    Nothing,
+  }
+
+  #[derive(Debug, Default, PartialEq, YaDeserialize, YaSerialize)]
+  #[yaserde(rename = "response")]
+  pub struct Response<T> {
+    answer: Answer<T>,
+  }
+
+  let model = Response {
+    answer: Answer::Value(3_u8),
+  };
+  let content = r#"<response><answer><value>3</value></answer></response>"#;
+
+  serialize_and_validate!(model, content);
+  deserialize_and_validate!(content, model, Response<u8>);
+}
+
+#[test]
+fn generic_bound_override() {
+  init();
+
+  pub trait Payload: yaserde::YaSerialize + yaserde::YaDeserializeOwned + Default {}
+
+  impl Payload for String {}
+
+  #[derive(Debug, Default, PartialEq, YaDeserialize, YaSerialize)]
+  #[yaserde(rename = "wrapper", bound = "T: Payload")]
+  pub struct Wrapper<T> {
+    content: T,
+  }
+
+  let model = Wrapper {
+    content: "text".to_string(),
+  };
+  let content = r#"<wrapper><content>text</content></wrapper>"#;
+
+  serialize_and_validate!(model, content);
+  deserialize_and_validate!(content, model, Wrapper<String>);
+}
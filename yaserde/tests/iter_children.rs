@@ -0,0 +1,190 @@
+#[macro_use]
+extern crate yaserde_derive;
+
+use std::io::Read;
+use yaserde::de::{iter_children, iter_children_with_config, Config, Error, ErrorKind, Limits};
+
+fn init() {
+  let _ = env_logger::builder().is_test(true).try_init();
+}
+
+#[derive(Debug, PartialEq, YaDeserialize)]
+#[yaserde(rename = "record")]
+pub struct Record {
+  #[yaserde(attribute)]
+  id: u32,
+  name: String,
+}
+
+/// A `<records>` document generated on the fly, never held in memory.
+struct Records {
+  count: u32,
+  next: u32,
+  pending: Vec<u8>,
+}
+
+impl Records {
+  fn new(count: u32) -> Self {
+    Records {
+      count,
+      next: 0,
+      pending: b"<?xml version=\"1.0\"?><records>".to_vec(),
+    }
+  }
+}
+
+impl Read for Records {
+  fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+    if self.pending.is_empty() && self.next <= self.count {
+      self.pending = if self.next == self.count {
+        b"</records>".to_vec()
+      } else {
+        format!(
+          r#"<record id="{0}"><name>record {0}</name></record>"#,
+          self.next
+        )
+        .into_bytes()
+      };
+      self.next += 1;
+    }
+
+    let length = buf.len().min(self.pending.len());
+    buf[..length].copy_from_slice(&self.pending[..length]);
+    self.pending.drain(..length);
+    Ok(length)
+  }
+}
+
+#[test]
+fn iter_children_records() {
+  init();
+
+  let content = r#"
+    <export>
+      <header>ignored</header>
+      <records>
+        <record id="1"><name>first</name></record>
+        <record id="2"><name>second</name></record>
+      </records>
+    </export>
+  "#;
+
+  let records: Result<Vec<Record>, Error> =
+    iter_children::<_, Record>(content.as_bytes(), "records").collect();
+  assert_eq!(
+    records.map_err(|error| error.to_string()),
+    Ok(vec![
+      Record {
+        id: 1,
+        name: "first".to_string(),
+      },
+      Record {
+        id: 2,
+        name: "second".to_string(),
+      },
+    ])
+  );
+
+  let mut records = iter_children::<_, Record>("<records/>".as_bytes(), "records");
+  assert!(records.next().is_none());
+}
+
+#[test]
+fn iter_children_stream() {
+  init();
+
+  // The number of elements is limited per child, not for the whole stream
+  let config = Config {
+    limits: Limits {
+      max_elements: 2,
+      ..Limits::default()
+    },
+    ..Config::default()
+  };
+
+  let mut count = 0;
+  for (index, record) in
+    iter_children_with_config::<_, Record>(Records::new(10_000), "records", &config).enumerate()
+  {
+    let record = record.unwrap();
+    assert_eq!(record.id as usize, index);
+    count += 1;
+  }
+  assert_eq!(count, 10_000);
+}
+
+#[test]
+fn iter_children_errors() {
+  init();
+
+  let content = r#"<records><record id="1"><name>first</name></record><record id="two"><name>second</name></record><record id="3"><name>third</name></record></records>"#;
+
+  let mut records = iter_children::<_, Record>(content.as_bytes(), "records");
+  assert!(records.next().unwrap().is_ok());
+
+  let error = records.next().unwrap().unwrap_err();
+  assert_eq!(error.path(), Some("/records/record[2]"));
+
+  // The iteration goes on with the next child
+  assert_eq!(records.next().unwrap().unwrap().id, 3);
+  assert!(records.next().is_none());
+
+  // Syntax errors end the iteration
+  let content = r#"<records><record id="1"><name>first</name></record><record id="2"><name>second</nam></record><record id="3"><name>third</name></record></records>"#;
+
+  let mut records = iter_children::<_, Record>(content.as_bytes(), "records");
+  assert!(records.next().unwrap().is_ok());
+  assert!(matches!(
+    records.next().unwrap().unwrap_err().kind(),
+    ErrorKind::Syntax(_)
+  ));
+  assert!(records.next().is_none());
+}
+
+#[test]
+fn iter_children_missing_parent() {
+  init();
+
+  let mut records = iter_children::<_, Record>("<export/>".as_bytes(), "records");
+  let error = records.next().unwrap().unwrap_err();
+  assert_eq!(
+    error.kind().to_string(),
+    "missing element <records> in the document"
+  );
+  assert!(records.next().is_none());
+}
+
+#[test]
+fn iter_children_names() {
+  init();
+
+  #[derive(Debug, PartialEq, YaDeserialize)]
+  #[yaserde(rename = "record")]
+  pub struct Id {
+    #[yaserde(attribute)]
+    id: u32,
+  }
+
+  // The children with another name are skipped, even when invalid or nested
+  let content = r#"<records><record id="1"/><record id="2"/><other/><record id="3"/><other><record/></other></records>"#;
+
+  let ids: Result<Vec<u32>, Error> = iter_children::<_, Id>(content.as_bytes(), "records")
+    .map(|id| id.map(|id| id.id))
+    .collect();
+  assert_eq!(ids.map_err(|error| error.to_string()), Ok(vec![1, 2, 3]));
+
+  // Unless another name is given
+  let content = r#"<records><item id="1"/><record id="2"/><item id="3"/></records>"#;
+
+  let ids: Result<Vec<u32>, Error> = iter_children::<_, Id>(content.as_bytes(), "records")
+    .child(Some("item"))
+    .map(|id| id.map(|id| id.id))
+    .collect();
+  assert_eq!(ids.map_err(|error| error.to_string()), Ok(vec![1, 3]));
+
+  let ids: Vec<Result<u32, Error>> = iter_children::<_, Id>(content.as_bytes(), "records")
+    .child(None)
+    .map(|id| id.map(|id| id.id))
+    .collect();
+  assert_eq!(ids.len(), 3);
+}
@@ -0,0 +1,218 @@
+#[macro_use]
+extern crate yaserde_derive;
+
+use std::io::Read;
+use yaserde::de::{
+  from_reader_with_config, from_str, from_str_with_config, Config, Error, ErrorKind, Limit, Limits,
+};
+
+fn init() {
+  let _ = env_logger::builder().is_test(true).try_init();
+}
+
+#[derive(Debug, PartialEq, YaDeserialize)]
+#[yaserde(rename = "node")]
+pub struct Node {
+  #[yaserde(attribute)]
+  name: Option<String>,
+  node: Option<Box<Node>>,
+  value: Option<String>,
+}
+
+fn config(limits: Limits) -> Config {
+  Config {
+    limits,
+    ..Config::default()
+  }
+}
+
+fn nested(depth: usize) -> String {
+  format!("{}{}", "<node>".repeat(depth), "</node>".repeat(depth))
+}
+
+/// Document starting with `start`, then repeating `a` forever.
+struct Endless {
+  start: &'static [u8],
+  read: usize,
+}
+
+impl Read for Endless {
+  fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+    for byte in buf.iter_mut() {
+      *byte = self.start.get(self.read).copied().unwrap_or(b'a');
+      self.read += 1;
+    }
+    Ok(buf.len())
+  }
+}
+
+fn exceeded(result: Result<Node, Error>) -> Option<(Limit, usize)> {
+  match result.map_err(Error::into_kind) {
+    Err(ErrorKind::LimitExceeded { limit, max }) => Some((limit, max)),
+    _ => None,
+  }
+}
+
+#[test]
+fn limit_depth() {
+  init();
+
+  let limits = Limits {
+    max_depth: 8,
+    ..Limits::default()
+  };
+  assert!(from_str_with_config::<Node>(&nested(8), &config(limits.clone())).is_ok());
+  assert_eq!(
+    exceeded(from_str_with_config(&nested(9), &config(limits))),
+    Some((Limit::Depth, 8))
+  );
+
+  // The default limit stops hostile documents before the recursion blows the stack
+  let error = from_str::<Node>(&nested(100_000)).unwrap_err();
+  assert_eq!(
+    error.kind().to_string(),
+    "element depth exceeds the limit of 128"
+  );
+  assert_eq!(error.line(), Some(1));
+}
+
+#[test]
+fn limit_text_length() {
+  init();
+
+  let limits = Limits {
+    max_text_length: 16,
+    ..Limits::default()
+  };
+
+  let content = format!("<node><value>{}</value></node>", "a".repeat(16));
+  assert!(from_str_with_config::<Node>(&content, &config(limits.clone())).is_ok());
+
+  let content = format!("<node><value>{}</value></node>", "a".repeat(17));
+  assert_eq!(
+    exceeded(from_str_with_config(&content, &config(limits.clone()))),
+    Some((Limit::TextLength, 16))
+  );
+
+  let content = format!(r#"<node name="{}" />"#, "a".repeat(17));
+  assert_eq!(
+    exceeded(from_str_with_config(&content, &config(limits))),
+    Some((Limit::TextLength, 16))
+  );
+}
+
+#[test]
+fn limit_text_length_while_reading() {
+  init();
+
+  let limits = Limits {
+    max_text_length: 1024,
+    ..Limits::default()
+  };
+
+  // The document never ends, it is only read up to the limit
+  for start in [
+    &b"<node><value>"[..],
+    b"<node name=\"",
+    b"<node><!--",
+    b"<no",
+  ] {
+    let reader = Endless { start, read: 0 };
+    assert_eq!(
+      exceeded(from_reader_with_config(reader, &config(limits.clone()))),
+      Some((Limit::TextLength, 1024))
+    );
+  }
+}
+
+#[test]
+fn limit_entity_expansion() {
+  init();
+
+  // The entities declared in the DTD are not expanded, the document is rejected
+  let content = r#"<?xml version="1.0"?>
+<!DOCTYPE node [
+  <!ENTITY lol "lol">
+  <!ENTITY lol1 "&lol;&lol;&lol;&lol;&lol;&lol;&lol;&lol;&lol;&lol;">
+  <!ENTITY lol2 "&lol1;&lol1;&lol1;&lol1;&lol1;&lol1;&lol1;&lol1;&lol1;&lol1;">
+  <!ENTITY lol3 "&lol2;&lol2;&lol2;&lol2;&lol2;&lol2;&lol2;&lol2;&lol2;&lol2;">
+  <!ENTITY lol4 "&lol3;&lol3;&lol3;&lol3;&lol3;&lol3;&lol3;&lol3;&lol3;&lol3;">
+  <!ENTITY lol5 "&lol4;&lol4;&lol4;&lol4;&lol4;&lol4;&lol4;&lol4;&lol4;&lol4;">
+  <!ENTITY lol6 "&lol5;&lol5;&lol5;&lol5;&lol5;&lol5;&lol5;&lol5;&lol5;&lol5;">
+  <!ENTITY lol7 "&lol6;&lol6;&lol6;&lol6;&lol6;&lol6;&lol6;&lol6;&lol6;&lol6;">
+  <!ENTITY lol8 "&lol7;&lol7;&lol7;&lol7;&lol7;&lol7;&lol7;&lol7;&lol7;&lol7;">
+  <!ENTITY lol9 "&lol8;&lol8;&lol8;&lol8;&lol8;&lol8;&lol8;&lol8;&lol8;&lol8;">
+]>
+<node><value>&lol9;</value></node>"#;
+  let error = from_str::<Node>(content).unwrap_err();
+  assert!(matches!(error.kind(), ErrorKind::Syntax(_)));
+  assert_eq!(error.kind().to_string(), "Unexpected entity: lol9");
+
+  // The extra entities are replaced by their value, without expanding it again
+  let mut config = config(Limits {
+    max_text_length: 1024,
+    ..Limits::default()
+  });
+  config
+    .extra_entities
+    .insert("lol".to_string(), "&lol;&lol;".to_string());
+  let node: Node = from_str_with_config("<node><value>&lol;</value></node>", &config).unwrap();
+  assert_eq!(node.value.as_deref(), Some("&lol;&lol;"));
+
+  // And the text with the values is bounded by the limit of the text length
+  config
+    .extra_entities
+    .insert("lol".to_string(), "lol".repeat(100));
+  let content = format!("<node><value>{}</value></node>", "&lol;".repeat(3));
+  assert!(from_str_with_config::<Node>(&content, &config).is_ok());
+  let content = format!("<node><value>{}</value></node>", "&lol;".repeat(4));
+  assert_eq!(
+    exceeded(from_str_with_config(&content, &config)),
+    Some((Limit::TextLength, 1024))
+  );
+}
+
+#[test]
+fn limit_attributes_and_elements() {
+  init();
+
+  let limits = Limits {
+    max_attributes: 2,
+    max_elements: 3,
+    ..Limits::default()
+  };
+
+  let content = r#"<node name="a" lang="en" />"#;
+  assert!(from_str_with_config::<Node>(content, &config(limits.clone())).is_ok());
+
+  let content = r#"<node name="a" lang="en" id="1" />"#;
+  assert_eq!(
+    exceeded(from_str_with_config(content, &config(limits.clone()))),
+    Some((Limit::Attributes, 2))
+  );
+
+  // Namespace declarations are not attributes
+  let content = r#"<node xmlns:a="urn:a" xmlns:b="urn:b" name="a" lang="en" />"#;
+  assert!(from_str_with_config::<Node>(content, &config(limits.clone())).is_ok());
+
+  let content = "<node><node><value>a</value></node></node>";
+  assert!(from_str_with_config::<Node>(content, &config(limits.clone())).is_ok());
+
+  let content = "<node><node><value>a</value><value>b</value></node></node>";
+  assert_eq!(
+    exceeded(from_str_with_config(content, &config(limits))),
+    Some((Limit::Elements, 3))
+  );
+}
+
+#[test]
+fn limit_unlimited() {
+  init();
+
+  let content = nested(200);
+  assert!(from_str::<Node>(&content).is_err());
+
+  let limits = Limits::unlimited();
+  let loaded: Result<Node, Error> = from_str_with_config(&content, &config(limits));
+  assert!(loaded.is_ok());
+}
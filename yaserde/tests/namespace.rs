@@ -1,3 +1,5 @@
+#![allow(clippy::derivable_impls)]
+
 #[macro_use]
 extern crate yaserde;
 #[macro_use]
@@ -190,7 +192,7 @@ fn struct_namespace_nested_defined_at_root() {
   #[derive(Debug, PartialEq, YaDeserialize, YaSerialize)]
   #[yaserde(
     prefix = "nsb",
-    namespace = "nsb: http://www.sample.com/ns/b"
+    namespace = "nsb: http://www.sample.com/ns/b",
     namespace = "nsa: http://www.sample.com/ns/a"
   )]
   struct B {
@@ -462,9 +464,9 @@ fn struct_bad_namespace() {
     </ns:book>
   "#;
 
-  let loaded: Result<Book, String> = yaserde::de::from_str(content);
+  let loaded: Result<Book, yaserde::de::Error> = yaserde::de::from_str(content);
   assert_eq!(
-    loaded,
+    loaded.map_err(|e| e.kind().to_string()),
     Err("bad namespace for book, found http://www.sample.com/ns/domain2".to_string())
   );
 }
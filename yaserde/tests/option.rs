@@ -1,3 +1,5 @@
+#![allow(clippy::derivable_impls)]
+
 #[macro_use]
 extern crate yaserde;
 #[macro_use]
@@ -130,7 +132,7 @@ fn option_bool_no_crash_on_bad_input() {
   }
 
   let content = "<field><content>/<R/";
-  let result: Result<Test, String> = yaserde::de::from_str(content);
+  let result: Result<Test, yaserde::de::Error> = yaserde::de::from_str(content);
 
   assert!(result.is_err());
 }
@@ -0,0 +1,208 @@
+#[macro_use]
+extern crate yaserde_derive;
+
+use yaserde::de::{Config, Error, ErrorKind, Limit, Limits, PushDeserializer};
+
+fn init() {
+  let _ = env_logger::builder().is_test(true).try_init();
+}
+
+#[derive(Debug, PartialEq, YaDeserialize)]
+#[yaserde(rename = "message", namespace = "jabber:client", deny_unknown_fields)]
+pub struct Message {
+  #[yaserde(attribute)]
+  to: String,
+  body: Option<String>,
+}
+
+const STREAM: &str = r#"<?xml version="1.0"?>
+<stream:stream xmlns="jabber:client" xmlns:stream="http://etherx.jabber.org/streams" version="1.0">
+  <!-- <message to="nobody"/> -->
+  <message to="juliet@example.com"><body>Art thou not Romeo?</body></message>
+  <message to="romeo@example.net" />
+  <message to="juliet@example.com"><body><![CDATA[</message> "> <]]></body></message>
+"#;
+
+fn messages() -> Vec<Message> {
+  vec![
+    Message {
+      to: "juliet@example.com".to_string(),
+      body: Some("Art thou not Romeo?".to_string()),
+    },
+    Message {
+      to: "romeo@example.net".to_string(),
+      body: None,
+    },
+    Message {
+      to: "juliet@example.com".to_string(),
+      body: Some(r#"</message> "> <"#.to_string()),
+    },
+  ]
+}
+
+fn feed(deserializer: &mut PushDeserializer<Message>, chunk: &[u8]) -> Vec<Message> {
+  deserializer
+    .feed(chunk)
+    .into_iter()
+    .map(|message| message.unwrap())
+    .collect()
+}
+
+#[test]
+fn push_whole_stream() {
+  init();
+
+  let mut deserializer = PushDeserializer::new();
+  assert_eq!(feed(&mut deserializer, STREAM.as_bytes()), messages());
+  assert!(!deserializer.is_closed());
+
+  assert_eq!(feed(&mut deserializer, b"</stream:stream>"), vec![]);
+  assert!(deserializer.is_closed());
+}
+
+#[test]
+fn push_chunks() {
+  init();
+
+  // Every split point, down to a single byte
+  for size in 1..=16 {
+    let mut deserializer = PushDeserializer::new();
+    let mut received = vec![];
+    for chunk in STREAM.as_bytes().chunks(size) {
+      received.extend(feed(&mut deserializer, chunk));
+    }
+    assert_eq!(received, messages(), "chunks of {} bytes", size);
+  }
+}
+
+#[test]
+fn push_each_child_as_soon_as_complete() {
+  init();
+
+  let mut deserializer = PushDeserializer::new();
+  assert_eq!(
+    feed(
+      &mut deserializer,
+      br#"<stream xmlns="jabber:client"><message to="a"><bo"#
+    ),
+    vec![]
+  );
+  assert_eq!(
+    feed(
+      &mut deserializer,
+      br#"dy>hi</body></message><message to="b"#
+    ),
+    vec![Message {
+      to: "a".to_string(),
+      body: Some("hi".to_string()),
+    }]
+  );
+  assert_eq!(
+    feed(&mut deserializer, br#"" />"#),
+    vec![Message {
+      to: "b".to_string(),
+      body: None,
+    }]
+  );
+}
+
+#[test]
+fn push_large_markup_byte_by_byte() {
+  init();
+
+  // Each byte only scans the new input, not the whole markup received so far
+  let text = "a".repeat(256 * 1024);
+  let stream = format!(
+    r#"<stream xmlns="jabber:client"><message to="{0}"><!-- {0} --><body><![CDATA[{0}]]></body></message>"#,
+    text
+  );
+
+  let mut deserializer = PushDeserializer::new();
+  let mut received = vec![];
+  for byte in stream.as_bytes() {
+    received.extend(feed(&mut deserializer, &[*byte]));
+  }
+  assert_eq!(
+    received,
+    vec![Message {
+      to: text.clone(),
+      body: Some(text),
+    }]
+  );
+}
+
+#[test]
+fn push_errors() {
+  init();
+
+  let mut deserializer = PushDeserializer::<Message>::new();
+  let received: Vec<Result<Message, Error>> = deserializer.feed(
+    br#"<stream xmlns="jabber:client"><message to="a"><subject>hi</subject></message><message to="b" />"#,
+  );
+
+  // A bad child doesn't stop the stream
+  assert_eq!(received.len(), 2);
+  assert_eq!(
+    received[0]
+      .as_ref()
+      .map_err(|error| error.kind().to_string()),
+    Err("unknown element <subject> in message".to_string())
+  );
+  assert!(received[1].is_ok());
+}
+
+#[test]
+fn push_error_position() {
+  init();
+
+  let mut deserializer = PushDeserializer::<Message>::new();
+  let received = deserializer.feed(b"<stream xmlns=\"jabber:client\">\n  <message to=\"a\" />\n");
+  assert_eq!(received.len(), 1);
+
+  // Located in the stream, not in the document built for the child
+  let received = deserializer.feed(b"  <message to=\"b\"><subject>hi</subject></message>\n");
+  let error = received[0].as_ref().unwrap_err();
+  assert_eq!((error.line(), error.column()), (Some(3), Some(19)));
+}
+
+#[test]
+fn push_limits() {
+  init();
+
+  let config = Config {
+    limits: Limits {
+      max_depth: 3,
+      max_text_length: 16,
+      ..Limits::default()
+    },
+    ..Config::default()
+  };
+
+  let exceeded = |chunks: &[&[u8]]| {
+    let mut deserializer = PushDeserializer::<Message>::new_with_config(&config);
+    let mut received = vec![];
+    for chunk in chunks {
+      received.extend(deserializer.feed(chunk));
+    }
+    assert!(deserializer.is_closed());
+    let error = received.pop().unwrap().unwrap_err().into_kind();
+    match error {
+      ErrorKind::LimitExceeded { limit, max } => (received.len(), limit, max),
+      error => panic!("unexpected error {}", error),
+    }
+  };
+
+  // The body is never complete, it is only kept up to the limit
+  let chunks: &[&[u8]] = &[
+    br#"<stream xmlns="jabber:client"><message to="a" /><message to="b"><body>"#,
+    b"aaaaaaaa",
+    b"aaaaaaaa",
+    b"aaaaaaaa",
+  ];
+  assert_eq!(exceeded(chunks), (1, Limit::TextLength, 16));
+
+  assert_eq!(
+    exceeded(&[br#"<stream xmlns="jabber:client"><message to="a"><body><b>"#]),
+    (0, Limit::Depth, 3)
+  );
+}
@@ -0,0 +1,159 @@
+#[macro_use]
+extern crate yaserde;
+#[macro_use]
+extern crate yaserde_derive;
+
+fn init() {
+  let _ = env_logger::builder().is_test(true).try_init();
+}
+
+#[test]
+fn rename_all_struct() {
+  init();
+
+  #[derive(Debug, PartialEq, YaDeserialize, YaSerialize)]
+  #[yaserde(rename = "server", rename_all = "camelCase")]
+  pub struct Server {
+    #[yaserde(attribute)]
+    host_name: String,
+    #[yaserde(attribute, rename = "PORT")]
+    port_number: u16,
+    max_connections: u32,
+    r#type: String,
+  }
+
+  let model = Server {
+    host_name: "localhost".to_string(),
+    port_number: 80,
+    max_connections: 12,
+    r#type: "http".to_string(),
+  };
+  let content = r#"<server hostName="localhost" PORT="80"><maxConnections>12</maxConnections><type>http</type></server>"#;
+
+  serialize_and_validate!(model, content);
+  deserialize_and_validate!(content, model, Server);
+}
+
+#[test]
+fn rename_all_cases() {
+  init();
+
+  #[derive(Debug, PartialEq, YaDeserialize, YaSerialize)]
+  #[yaserde(rename = "pascal", rename_all = "PascalCase")]
+  pub struct Pascal {
+    first_name: String,
+  }
+
+  #[derive(Debug, PartialEq, YaDeserialize, YaSerialize)]
+  #[yaserde(rename = "kebab", rename_all = "kebab-case")]
+  pub struct Kebab {
+    first_name: String,
+  }
+
+  #[derive(Debug, PartialEq, YaDeserialize, YaSerialize)]
+  #[yaserde(rename = "screaming", rename_all = "SCREAMING_SNAKE_CASE")]
+  pub struct Screaming {
+    first_name: String,
+  }
+
+  #[derive(Debug, Default, PartialEq, YaDeserialize, YaSerialize)]
+  #[yaserde(rename_all = "lowercase")]
+  pub enum Level {
+    #[default]
+    DebugTrace,
+    Warning,
+  }
+
+  #[derive(Debug, PartialEq, YaDeserialize, YaSerialize)]
+  #[yaserde(rename = "lower")]
+  pub struct Lower {
+    level: Level,
+  }
+
+  let model = Pascal {
+    first_name: "Ada".to_string(),
+  };
+  let content = "<pascal><FirstName>Ada</FirstName></pascal>";
+  serialize_and_validate!(model, content);
+  deserialize_and_validate!(content, model, Pascal);
+
+  let model = Kebab {
+    first_name: "Ada".to_string(),
+  };
+  let content = "<kebab><first-name>Ada</first-name></kebab>";
+  serialize_and_validate!(model, content);
+  deserialize_and_validate!(content, model, Kebab);
+
+  let model = Screaming {
+    first_name: "Ada".to_string(),
+  };
+  let content = "<screaming><FIRST_NAME>Ada</FIRST_NAME></screaming>";
+  serialize_and_validate!(model, content);
+  deserialize_and_validate!(content, model, Screaming);
+
+  let model = Lower {
+    level: Level::DebugTrace,
+  };
+  let content = "<lower><level>debugtrace</level></lower>";
+  serialize_and_validate!(model, content);
+  deserialize_and_validate!(content, model, Lower);
+}
+
+#[test]
+fn rename_all_enum() {
+  init();
+
+  #[derive(Debug, Default, PartialEq, YaDeserialize, YaSerialize)]
+  #[yaserde(rename_all = "snake_case")]
+  pub enum Shape {
+    #[default]
+    Circle,
+    RoundedSquare,
+    #[yaserde(rename = "tri")]
+    Triangle,
+    CustomPath(String),
+  }
+
+  #[derive(Debug, PartialEq, YaDeserialize, YaSerialize)]
+  #[yaserde(rename = "drawing")]
+  pub struct Drawing {
+    shape: Vec<Shape>,
+  }
+
+  let model = Drawing {
+    shape: vec![
+      Shape::RoundedSquare,
+      Shape::Triangle,
+      Shape::CustomPath("M 0 0".to_string()),
+    ],
+  };
+  let content = "<drawing><shape>rounded_square</shape><shape>tri</shape><shape><custom_path>M 0 0</custom_path></shape></drawing>";
+
+  serialize_and_validate!(model, content);
+  deserialize_and_validate!(content, model, Drawing);
+}
+
+#[test]
+fn rename_all_variant_fields() {
+  init();
+
+  #[derive(Debug, PartialEq, YaDeserialize, YaSerialize)]
+  pub enum Event {
+    #[yaserde(rename = "click", rename_all = "camelCase")]
+    Click { pos_x: u32, pos_y: u32 },
+  }
+
+  #[derive(Debug, PartialEq, YaDeserialize, YaSerialize)]
+  #[yaserde(rename = "log")]
+  pub struct Log {
+    event: Event,
+  }
+
+  let model = Log {
+    event: Event::Click { pos_x: 1, pos_y: 2 },
+  };
+  let content = "<log><event><posX>1</posX><posY>2</posY></event></log>";
+
+  serialize_and_validate!(model, content);
+  deserialize_and_validate!(content, model, Log);
+}
@@ -0,0 +1,150 @@
+#[macro_use]
+extern crate yaserde;
+#[macro_use]
+extern crate yaserde_derive;
+
+use yaserde::de::{from_str, Error};
+
+fn init() {
+  let _ = env_logger::builder().is_test(true).try_init();
+}
+
+#[test]
+fn root_element_name() {
+  init();
+
+  #[derive(Debug, PartialEq, YaDeserialize, YaSerialize)]
+  #[yaserde(root = "book")]
+  pub struct Book {
+    author: String,
+  }
+
+  #[derive(Debug, PartialEq, YaDeserialize, YaSerialize)]
+  #[yaserde(root = "library")]
+  pub struct Library {
+    #[yaserde(rename = "novel")]
+    books: Vec<Book>,
+  }
+
+  let model = Book {
+    author: "Antoine de Saint-Exupéry".to_string(),
+  };
+  let content = "<book><author>Antoine de Saint-Exupéry</author></book>";
+  serialize_and_validate!(model, content);
+  deserialize_and_validate!(content, model, Book);
+
+  // Nested values keep the name of their field
+  let model = Library {
+    books: vec![Book {
+      author: "Antoine de Saint-Exupéry".to_string(),
+    }],
+  };
+  let content = "<library><novel><author>Antoine de Saint-Exupéry</author></novel></library>";
+  serialize_and_validate!(model, content);
+  deserialize_and_validate!(content, model, Library);
+
+  let loaded: Result<Book, Error> = from_str("<novel><author>Antoine</author></novel>");
+  let error = loaded.unwrap_err();
+  assert_eq!(
+    error.kind().to_string(),
+    "unexpected root element <novel>, expected <book>"
+  );
+  assert_eq!(error.line(), Some(1));
+}
+
+#[test]
+fn root_alias() {
+  init();
+
+  #[derive(Clone, Debug, PartialEq, YaDeserialize, YaSerialize)]
+  #[yaserde(root = "configuration", root_alias = "config", root_alias = "settings")]
+  pub struct Configuration {
+    #[yaserde(attribute)]
+    version: u32,
+  }
+
+  let model = Configuration { version: 2 };
+  serialize_and_validate!(model, r#"<configuration version="2" />"#);
+  deserialize_and_validate!(
+    r#"<configuration version="2" />"#,
+    model.clone(),
+    Configuration
+  );
+  deserialize_and_validate!(r#"<config version="2" />"#, model.clone(), Configuration);
+  deserialize_and_validate!(r#"<settings version="2" />"#, model, Configuration);
+
+  let loaded: Result<Configuration, Error> = from_str(r#"<preferences version="2" />"#);
+  assert_eq!(
+    loaded.map_err(|error| error.kind().to_string()),
+    Err(
+      "unexpected root element <preferences>, expected <configuration> or <config> or <settings>"
+        .to_string()
+    )
+  );
+}
+
+#[test]
+fn root_namespace() {
+  init();
+
+  #[derive(Debug, PartialEq, YaDeserialize, YaSerialize)]
+  #[yaserde(
+    root = "book",
+    prefix = "ns",
+    namespace = "ns: http://www.sample.com/ns/domain"
+  )]
+  pub struct Book {
+    #[yaserde(prefix = "ns")]
+    author: String,
+  }
+
+  let model = Book {
+    author: "Antoine".to_string(),
+  };
+  let content = r#"<ns:book xmlns:ns="http://www.sample.com/ns/domain"><ns:author>Antoine</ns:author></ns:book>"#;
+  serialize_and_validate!(model, content);
+  deserialize_and_validate!(content, model, Book);
+
+  let loaded: Result<Book, Error> = from_str("<book><author>Antoine</author></book>");
+  assert_eq!(
+    loaded.map_err(|error| error.kind().to_string()),
+    Err(
+      r#"unexpected root element <book>, expected <book xmlns="http://www.sample.com/ns/domain">"#
+        .to_string()
+    )
+  );
+
+  let loaded: Result<Book, Error> = from_str(
+    r#"<ns:book xmlns:ns="http://www.sample.com/ns/other"><ns:author>Antoine</ns:author></ns:book>"#,
+  );
+  assert_eq!(
+    loaded.map_err(|error| error.kind().to_string()),
+    Err(
+      r#"unexpected root element <book xmlns="http://www.sample.com/ns/other">, expected <book xmlns="http://www.sample.com/ns/domain">"#
+        .to_string()
+    )
+  );
+}
+
+#[test]
+fn root_enum() {
+  init();
+
+  #[derive(Debug, Default, PartialEq, YaDeserialize, YaSerialize)]
+  #[yaserde(root = "color")]
+  pub enum Color {
+    #[default]
+    White,
+    Black,
+  }
+
+  let content = "<color>Black</color>";
+  serialize_and_validate!(Color::Black, content);
+  deserialize_and_validate!(content, Color::Black, Color);
+
+  let loaded: Result<Color, Error> = from_str("<colour>Black</colour>");
+  assert_eq!(
+    loaded.map_err(|error| error.kind().to_string()),
+    Err("unexpected root element <colour>, expected <color>".to_string())
+  );
+}
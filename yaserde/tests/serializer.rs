@@ -1,3 +1,5 @@
+#![allow(clippy::derivable_impls)]
+
 #[macro_use]
 extern crate yaserde;
 #[macro_use]
@@ -397,26 +399,24 @@ fn ser_custom() {
   }
 
   impl YaSerialize for Day {
-    fn serialize<W: Write>(&self, writer: &mut yaserde::ser::Serializer<W>) -> Result<(), String> {
-      let _ret = writer.write(xml::writer::XmlEvent::start_element("DoubleDay"));
-      let _ret = writer.write(xml::writer::XmlEvent::characters(
+    fn serialize<W: Write>(
+      &self,
+      writer: &mut yaserde::ser::Serializer<W>,
+    ) -> Result<(), yaserde::ser::Error> {
+      let _ret = writer.write(yaserde::event::XmlEvent::start_element("DoubleDay"));
+      let _ret = writer.write(yaserde::event::XmlEvent::characters(
         &(self.value * 2).to_string(),
       ));
-      let _ret = writer.write(xml::writer::XmlEvent::end_element());
+      let _ret = writer.write(yaserde::event::XmlEvent::end_element());
       Ok(())
     }
 
     fn serialize_attributes(
       &self,
-      attributes: Vec<xml::attribute::OwnedAttribute>,
-      namespace: xml::namespace::Namespace,
-    ) -> Result<
-      (
-        Vec<xml::attribute::OwnedAttribute>,
-        xml::namespace::Namespace,
-      ),
-      String,
-    > {
+      attributes: Vec<yaserde::event::Attribute>,
+      namespace: yaserde::event::Namespace,
+    ) -> Result<(Vec<yaserde::event::Attribute>, yaserde::event::Namespace), yaserde::ser::Error>
+    {
       Ok((attributes, namespace))
     }
   }
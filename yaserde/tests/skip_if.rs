@@ -1,3 +1,5 @@
+#![allow(clippy::legacy_numeric_constants)]
+
 #[macro_use]
 extern crate yaserde;
 #[macro_use]
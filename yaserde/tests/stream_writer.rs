@@ -0,0 +1,125 @@
+#[macro_use]
+extern crate yaserde_derive;
+
+use yaserde::ser::{Config, StreamWriter};
+
+fn init() {
+  let _ = env_logger::builder().is_test(true).try_init();
+}
+
+#[derive(Debug, Default, PartialEq, YaSerialize)]
+#[yaserde(
+  rename = "records",
+  prefix = "ns",
+  namespace = "ns: http://www.sample.com/ns/records"
+)]
+pub struct Records {
+  #[yaserde(attribute)]
+  source: String,
+  #[yaserde(prefix = "ns", rename = "record")]
+  records: Vec<Record>,
+}
+
+#[derive(Debug, PartialEq, YaSerialize)]
+#[yaserde(
+  rename = "record",
+  prefix = "ns",
+  namespace = "ns: http://www.sample.com/ns/records"
+)]
+pub struct Record {
+  #[yaserde(attribute)]
+  id: u32,
+  #[yaserde(prefix = "ns")]
+  name: String,
+}
+
+fn record(id: u32) -> Record {
+  Record {
+    id,
+    name: format!("record {}", id),
+  }
+}
+
+#[test]
+fn stream_writer_items() {
+  init();
+
+  let container = Records {
+    source: "export".to_string(),
+    records: vec![],
+  };
+  let mut writer =
+    StreamWriter::new_for_container_with_attributes(Vec::new(), &container, &Config::default())
+      .unwrap();
+  for id in 1..=2 {
+    writer.write_item(&record(id)).unwrap();
+  }
+  let content = String::from_utf8(writer.finish().unwrap()).unwrap();
+
+  // Same output as the whole model, without repeating the namespace declaration
+  let model = Records {
+    source: "export".to_string(),
+    records: vec![record(1), record(2)],
+  };
+  assert_eq!(yaserde::ser::to_string(&model).unwrap(), content);
+  assert_eq!(
+    content,
+    r#"<?xml version="1.0" encoding="utf-8"?><ns:records xmlns:ns="http://www.sample.com/ns/records" source="export"><ns:record id="1"><ns:name>record 1</ns:name></ns:record><ns:record id="2"><ns:name>record 2</ns:name></ns:record></ns:records>"#
+  );
+}
+
+#[test]
+fn stream_writer_container_metadata() {
+  init();
+
+  let config = Config {
+    write_document_declaration: false,
+    ..Config::default()
+  };
+
+  // The root element is named and declares its namespaces after the container attributes
+  let mut writer = StreamWriter::new_for_container::<Records>(Vec::new(), &config).unwrap();
+  writer.write_item(&record(1)).unwrap();
+  let content = String::from_utf8(writer.finish().unwrap()).unwrap();
+  assert_eq!(
+    content,
+    r#"<ns:records xmlns:ns="http://www.sample.com/ns/records"><ns:record id="1"><ns:name>record 1</ns:name></ns:record></ns:records>"#
+  );
+
+  let writer = StreamWriter::new_for_container::<Box<Records>>(Vec::new(), &config).unwrap();
+  let content = String::from_utf8(writer.finish().unwrap()).unwrap();
+  assert_eq!(
+    content,
+    r#"<ns:records xmlns:ns="http://www.sample.com/ns/records" />"#
+  );
+
+  // Types without an element of their own can't open a document
+  assert!(StreamWriter::new_for_container::<Vec<Record>>(Vec::new(), &config).is_err());
+}
+
+#[test]
+fn stream_writer_named_items() {
+  init();
+
+  let config = Config {
+    write_document_declaration: false,
+    ..Config::default()
+  };
+  let mut writer = StreamWriter::new(Vec::new(), "names", &config).unwrap();
+  writer
+    .write_named_item("name", &"first".to_string())
+    .unwrap();
+  writer
+    .write_named_item("name", &"second".to_string())
+    .unwrap();
+  let content = String::from_utf8(writer.finish().unwrap()).unwrap();
+
+  assert_eq!(
+    content,
+    "<names><name>first</name><name>second</name></names>"
+  );
+
+  let writer = StreamWriter::new(Vec::new(), "empty", &config).unwrap();
+  let content = String::from_utf8(writer.finish().unwrap()).unwrap();
+  assert_eq!(content, "<empty />");
+}
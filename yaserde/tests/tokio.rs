@@ -0,0 +1,131 @@
+#![cfg(feature = "tokio")]
+
+#[macro_use]
+extern crate yaserde_derive;
+
+use std::io::Write;
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use yaserde::de::{from_async_reader, Error};
+use yaserde::event::{Attribute, Namespace, XmlEvent};
+use yaserde::ser::{to_async_writer, to_async_writer_with_config, Config, Serializer};
+use yaserde::YaSerialize;
+
+#[derive(Debug, PartialEq, YaDeserialize, YaSerialize)]
+#[yaserde(rename = "library")]
+pub struct Library {
+  #[yaserde(attribute)]
+  name: String,
+  #[yaserde(rename = "book")]
+  books: Vec<String>,
+}
+
+fn library() -> Library {
+  Library {
+    name: "Alexandria".to_string(),
+    books: (0..1_000).map(|index| format!("Book {}", index)).collect(),
+  }
+}
+
+#[tokio::test]
+async fn async_round_trip() {
+  let content = to_async_writer(library(), Vec::new()).await.unwrap();
+  assert_eq!(
+    String::from_utf8(content.clone()).unwrap(),
+    yaserde::ser::to_string(&library()).unwrap()
+  );
+
+  let config = Config {
+    perform_indent: true,
+    ..Config::default()
+  };
+  let indented = to_async_writer_with_config(library(), Vec::new(), &config)
+    .await
+    .unwrap();
+  assert_eq!(
+    String::from_utf8(indented).unwrap(),
+    yaserde::ser::to_string_with_config(&library(), &config).unwrap()
+  );
+
+  // The document arrives in small chunks through a pipe
+  let (mut client, server) = tokio::io::duplex(64);
+  let sending = tokio::spawn(async move {
+    for chunk in content.chunks(100) {
+      client.write_all(chunk).await.unwrap();
+      tokio::task::yield_now().await;
+    }
+  });
+
+  let loaded: Result<Library, Error> = from_async_reader(server).await;
+  sending.await.unwrap();
+  assert_eq!(loaded.map_err(|error| error.to_string()), Ok(library()));
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn async_errors() {
+  let content = r#"<library name="Alexandria"><book>Odyssey</library>"#;
+  let loaded: Result<Library, Error> = from_async_reader(content.as_bytes()).await;
+  let error = loaded.unwrap_err();
+  assert_eq!(error.path(), Some("/library/book"));
+
+  let (client, server) = tokio::io::duplex(64);
+  drop(client);
+  let loaded: Result<Library, Error> = from_async_reader(server).await;
+  assert!(loaded.is_err());
+}
+
+/// Document whose end is only serialized once its beginning has been received.
+struct Gated {
+  received: Mutex<mpsc::Receiver<()>>,
+}
+
+impl YaSerialize for Gated {
+  fn serialize<W: Write>(&self, writer: &mut Serializer<W>) -> Result<(), yaserde::ser::Error> {
+    writer.write(XmlEvent::start_element("gated"))?;
+    writer.write(XmlEvent::characters(&"a".repeat(64 * 1024)))?;
+    self
+      .received
+      .lock()
+      .unwrap()
+      .recv_timeout(Duration::from_secs(10))
+      .map_err(|_| yaserde::ser::Error::custom("the beginning was not written"))?;
+    writer.write(XmlEvent::end_element())
+  }
+
+  fn serialize_attributes(
+    &self,
+    attributes: Vec<Attribute>,
+    namespace: Namespace,
+  ) -> Result<(Vec<Attribute>, Namespace), yaserde::ser::Error> {
+    Ok((attributes, namespace))
+  }
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn async_writer_streams() {
+  let (received, receiver) = mpsc::channel();
+  let model = Gated {
+    received: Mutex::new(receiver),
+  };
+
+  let (client, mut server) = tokio::io::duplex(1024);
+  // The pipe is closed once the document is written
+  let writing = tokio::spawn(async move { to_async_writer(model, client).await.map(drop) });
+
+  let mut content = vec![0; 16 * 1024];
+  server.read_exact(&mut content).await.unwrap();
+  received.send(()).unwrap();
+  server.read_to_end(&mut content).await.unwrap();
+  writing.await.unwrap().unwrap();
+
+  // An Arc keeps the model
+  let model = Arc::new(library());
+  let content = to_async_writer(Arc::clone(&model), Vec::new())
+    .await
+    .unwrap();
+  assert_eq!(
+    String::from_utf8(content).unwrap(),
+    yaserde::ser::to_string(&*model).unwrap()
+  );
+}
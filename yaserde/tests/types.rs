@@ -47,3 +47,181 @@ fn ser_type() {
   test_for_attribute_type!(f32, -12.5_f32, Some("-12.5"));
   test_for_attribute_type!(f64, -12.5_f64, Some("-12.5"));
 }
+
+#[test]
+fn de_primitive_type() {
+  init();
+
+  use yaserde::de::{from_str, ErrorKind};
+
+  assert_eq!(from_str::<u32>("<value>12</value>").unwrap(), 12);
+  assert_eq!(from_str::<i128>("<value>-12</value>").unwrap(), -12);
+  assert_eq!(from_str::<usize>("<value>12</value>").unwrap(), 12);
+  assert_eq!(from_str::<char>("<value>c</value>").unwrap(), 'c');
+  assert_eq!(from_str::<f64>("<value>-12.5</value>").unwrap(), -12.5);
+  assert!(from_str::<bool>("<value>true</value>").unwrap());
+  assert!(from_str::<bool>("<value>1</value>").unwrap());
+  assert!(!from_str::<bool>("<value>0</value>").unwrap());
+  assert_eq!(
+    from_str::<String>("<value>test</value>").unwrap(),
+    "test".to_string()
+  );
+  assert_eq!(from_str::<String>("<value />").unwrap(), "".to_string());
+
+  assert_eq!(
+    from_str::<Option<u8>>("<value>12</value>").unwrap(),
+    Some(12)
+  );
+  assert_eq!(from_str::<Vec<u8>>("<value>12</value>").unwrap(), vec![12]);
+  assert_eq!(
+    from_str::<Box<String>>("<value>test</value>").unwrap(),
+    Box::new("test".to_string())
+  );
+  assert_eq!(
+    *from_str::<std::rc::Rc<u8>>("<value>12</value>").unwrap(),
+    12
+  );
+  assert_eq!(
+    *from_str::<std::sync::Arc<u8>>("<value>12</value>").unwrap(),
+    12
+  );
+
+  let error = from_str::<u8>("<value>300</value>").unwrap_err();
+  assert!(matches!(error.kind(), ErrorKind::InvalidValue { .. }));
+  assert_eq!(error.path(), Some("/value"));
+
+  assert_eq!(
+    from_str::<String>("<value>te<inner>ignored</inner>st</value>").unwrap(),
+    "test".to_string()
+  );
+}
+
+#[test]
+fn ser_primitive_type() {
+  init();
+
+  use yaserde::ser::to_string_content;
+
+  assert_eq!(to_string_content(&12_u32).unwrap(), "12");
+  assert_eq!(to_string_content(&-12_i128).unwrap(), "-12");
+  assert_eq!(to_string_content(&"test").unwrap(), "test");
+  assert_eq!(to_string_content(&"test".to_string()).unwrap(), "test");
+  assert_eq!(to_string_content(&Some(12_u8)).unwrap(), "12");
+  assert_eq!(to_string_content(&None::<u8>).unwrap(), "");
+  assert_eq!(to_string_content(&Box::new(12_u8)).unwrap(), "12");
+  assert_eq!(to_string_content(&std::rc::Rc::new(12_u8)).unwrap(), "12");
+  assert_eq!(
+    to_string_content(&std::sync::Arc::new(12_u8)).unwrap(),
+    "12"
+  );
+}
+
+#[test]
+fn ser_wrapper_of_struct() {
+  init();
+
+  #[derive(Debug, PartialEq, YaDeserialize, YaSerialize)]
+  #[yaserde(rename = "item")]
+  pub struct Item {
+    #[yaserde(attribute)]
+    id: u8,
+    value: String,
+  }
+
+  let model = Box::new(Item {
+    id: 1,
+    value: "one".to_string(),
+  });
+  let content = r#"<item id="1"><value>one</value></item>"#;
+
+  serialize_and_validate!(model, content);
+  let loaded: Box<Item> = yaserde::de::from_str(content).unwrap();
+  assert_eq!(loaded, model);
+
+  let loaded: Option<Item> = yaserde::de::from_str(content).unwrap();
+  assert_eq!(loaded, Some(*model));
+}
+
+#[test]
+fn type_dispatch_through_traits() {
+  init();
+
+  use std::borrow::Cow;
+  use std::num::NonZeroU32;
+
+  type Ids = Vec<u32>;
+  type Label = String;
+
+  #[derive(Debug, Default, PartialEq, YaDeserialize, YaSerialize)]
+  #[yaserde(rename = "child")]
+  pub struct Child {
+    #[yaserde(attribute)]
+    id: Option<NonZeroU32>,
+    #[yaserde(text)]
+    label: Label,
+  }
+
+  #[derive(Debug, PartialEq, YaDeserialize, YaSerialize)]
+  #[yaserde(rename = "data")]
+  pub struct Data {
+    #[yaserde(attribute)]
+    code: char,
+    #[yaserde(attribute)]
+    name: Cow<'static, str>,
+    ids: Ids,
+    size: usize,
+    offset: isize,
+    big: u128,
+    child: Box<Child>,
+    optional: Option<Box<Child>>,
+    title: Cow<'static, str>,
+  }
+
+  let model = Data {
+    code: 'x',
+    name: Cow::Borrowed("name"),
+    ids: vec![1, 2],
+    size: 3,
+    offset: -4,
+    big: 340_282_366_920_938_463_463_374_607_431_768_211_455,
+    child: Box::new(Child {
+      id: NonZeroU32::new(5),
+      label: "five".to_string(),
+    }),
+    optional: None,
+    title: Cow::Owned("title".to_string()),
+  };
+
+  let content = r#"<data code="x" name="name"><ids>1</ids><ids>2</ids><size>3</size><offset>-4</offset><big>340282366920938463463374607431768211455</big><child id="5">five</child><title>title</title></data>"#;
+
+  serialize_and_validate!(model, content);
+  deserialize_and_validate!(content, model, Data);
+}
+
+#[test]
+fn repeated_elements_through_pointers() {
+  init();
+
+  use std::rc::Rc;
+  use std::sync::Arc;
+
+  #[derive(Debug, PartialEq, YaDeserialize, YaSerialize)]
+  #[yaserde(rename = "data")]
+  pub struct Data {
+    #[allow(clippy::box_collection)]
+    values: Box<Vec<u8>>,
+    items: Rc<Vec<u8>>,
+    others: Arc<Vec<u8>>,
+  }
+
+  let model = Data {
+    values: Box::new(vec![1, 2]),
+    items: Rc::new(vec![3, 4]),
+    others: Arc::new(vec![5, 6]),
+  };
+
+  let content = r#"<data><values>1</values><values>2</values><items>3</items><items>4</items><others>5</others><others>6</others></data>"#;
+
+  serialize_and_validate!(model, content);
+  deserialize_and_validate!(content, model, Data);
+}
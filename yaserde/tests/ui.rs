@@ -0,0 +1,8 @@
+// trybuild builds the cases with the default features, which need the default backend
+#![cfg(feature = "xml-rs")]
+
+#[test]
+fn ui() {
+  let t = trybuild::TestCases::new();
+  t.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,13 @@
+use std::collections::BTreeMap;
+use yaserde::event::QualifiedName;
+use yaserde_derive::YaDeserialize;
+
+#[derive(YaDeserialize)]
+struct Book {
+  #[yaserde(any_attribute, attribute)]
+  extensions: BTreeMap<QualifiedName, String>,
+  #[yaserde(any_attribute)]
+  others: BTreeMap<QualifiedName, String>,
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use yaserde::dom::Element;
+use yaserde_derive::YaDeserialize;
+
+#[derive(YaDeserialize)]
+struct Book {
+  #[yaserde(any, flatten)]
+  extensions: Vec<Element>,
+  #[yaserde(any)]
+  others: Vec<Element>,
+}
+
+fn main() {}
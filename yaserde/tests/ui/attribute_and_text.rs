@@ -0,0 +1,11 @@
+use yaserde_derive::YaDeserialize;
+
+#[derive(YaDeserialize)]
+struct Book {
+  #[yaserde(attribute, text)]
+  title: String,
+  #[yaserde(text)]
+  summary: String,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use std::borrow::Cow;
+use yaserde_derive::YaDeserialize;
+
+#[derive(YaDeserialize)]
+struct Book<'a> {
+  #[yaserde(borrow, display_fromstr)]
+  title: Cow<'a, str>,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use yaserde_derive::YaDeserialize;
+
+#[derive(YaDeserialize)]
+#[yaserde(rename = "book", rename = "livre")]
+struct Book {
+  title: String,
+}
+
+fn main() {}
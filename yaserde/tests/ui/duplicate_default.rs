@@ -0,0 +1,13 @@
+use yaserde_derive::YaDeserialize;
+
+fn default_title() -> String {
+  "Untitled".to_string()
+}
+
+#[derive(YaDeserialize)]
+struct Book {
+  #[yaserde(default, default = "default_title")]
+  title: String,
+}
+
+fn main() {}
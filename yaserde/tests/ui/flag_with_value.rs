@@ -0,0 +1,9 @@
+use yaserde_derive::YaSerialize;
+
+#[derive(YaSerialize)]
+struct Book {
+  #[yaserde(attribute = "true")]
+  title: String,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use yaserde_derive::YaSerialize;
+
+#[derive(YaSerialize)]
+struct Book {
+  #[yaserde(serialize_with = "not a path")]
+  title: String,
+}
+
+fn main() {}
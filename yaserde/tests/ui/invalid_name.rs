@@ -0,0 +1,10 @@
+use yaserde_derive::YaSerialize;
+
+#[derive(YaSerialize)]
+#[yaserde(rename = "ns:book")]
+struct Book {
+  #[yaserde(rename = "1st title")]
+  title: String,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use yaserde_derive::YaSerialize;
+
+#[derive(YaSerialize)]
+#[yaserde(rename_all = "camel")]
+struct Book {
+  page_count: u32,
+}
+
+fn main() {}
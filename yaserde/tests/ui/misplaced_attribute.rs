@@ -0,0 +1,10 @@
+use yaserde_derive::YaSerialize;
+
+#[derive(YaSerialize)]
+#[yaserde(attribute)]
+struct Book {
+  #[yaserde(rename_all = "camelCase")]
+  title: String,
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use yaserde_derive::YaDeserialize;
+
+#[derive(YaDeserialize)]
+struct Book<'de> {
+  title: &'de str,
+}
+
+fn main() {}
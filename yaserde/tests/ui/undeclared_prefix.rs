@@ -0,0 +1,10 @@
+use yaserde_derive::YaSerialize;
+
+#[derive(YaSerialize)]
+#[yaserde(prefix = "ns", namespace = "ns: http://www.sample.com/ns/domain")]
+struct Book {
+  #[yaserde(prefix = "nss")]
+  title: String,
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+use yaserde_derive::YaSerialize;
+
+#[derive(YaSerialize)]
+#[yaserde(renam = "book")]
+struct Book {
+  title: String,
+}
+
+#[derive(YaSerialize)]
+struct Library {
+  #[yaserde(child)]
+  book: Book,
+}
+
+fn main() {}
@@ -0,0 +1,17 @@
+use yaserde_derive::YaSerialize;
+
+#[derive(YaSerialize)]
+struct Point(u32, u32);
+
+#[derive(YaSerialize)]
+enum Shape {
+  Line(Point, Point),
+}
+
+#[derive(YaSerialize)]
+union Value {
+  int: u32,
+  float: f32,
+}
+
+fn main() {}
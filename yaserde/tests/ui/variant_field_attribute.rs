@@ -0,0 +1,11 @@
+use yaserde_derive::YaDeserialize;
+
+#[derive(YaDeserialize)]
+enum Shape {
+  Circle {
+    #[yaserde(attribute)]
+    radius: u32,
+  },
+}
+
+fn main() {}
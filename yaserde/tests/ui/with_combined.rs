@@ -0,0 +1,15 @@
+use yaserde_derive::YaSerialize;
+
+mod upper {
+  pub fn serialize(value: &str) -> Result<String, String> {
+    Ok(value.to_uppercase())
+  }
+}
+
+#[derive(YaSerialize)]
+struct Book {
+  #[yaserde(with = "upper", serialize_with = "upper::serialize")]
+  title: String,
+}
+
+fn main() {}
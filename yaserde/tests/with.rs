@@ -0,0 +1,156 @@
+#[macro_use]
+extern crate yaserde;
+#[macro_use]
+extern crate yaserde_derive;
+
+use std::io::{Read, Write};
+use std::time::Duration;
+use yaserde::{YaDeserialize, YaSerialize};
+
+fn init() {
+  let _ = env_logger::builder().is_test(true).try_init();
+}
+
+mod seconds {
+  use super::*;
+
+  pub fn serialize<W: Write>(
+    duration: &Duration,
+    writer: &mut yaserde::ser::Serializer<W>,
+  ) -> Result<(), yaserde::ser::Error> {
+    duration.as_secs().serialize(writer)
+  }
+
+  pub fn deserialize<R: Read>(
+    reader: &mut yaserde::de::Deserializer<R>,
+  ) -> Result<Duration, yaserde::de::Error> {
+    u64::deserialize(reader).map(Duration::from_secs)
+  }
+}
+
+fn serialize_words(words: &[String]) -> Result<Option<String>, yaserde::ser::Error> {
+  Ok(Some(words.join(" ")))
+}
+
+fn deserialize_words(value: &str) -> Result<Vec<String>, yaserde::de::Error> {
+  Ok(value.split_whitespace().map(str::to_string).collect())
+}
+
+#[test]
+fn with_module_on_element() {
+  init();
+
+  #[derive(Debug, Default, PartialEq, YaDeserialize, YaSerialize)]
+  #[yaserde(rename = "task")]
+  pub struct Task {
+    #[yaserde(attribute)]
+    name: String,
+    #[yaserde(with = "seconds")]
+    timeout: Duration,
+  }
+
+  let model = Task {
+    name: "build".to_string(),
+    timeout: Duration::from_secs(90),
+  };
+  let content = r#"<task name="build"><timeout>90</timeout></task>"#;
+
+  serialize_and_validate!(model, content);
+  deserialize_and_validate!(content, model, Task);
+}
+
+#[test]
+fn with_functions_on_attribute_and_text() {
+  init();
+
+  #[derive(Debug, Default, PartialEq, YaDeserialize, YaSerialize)]
+  #[yaserde(rename = "sentence")]
+  pub struct Sentence {
+    #[yaserde(
+      attribute,
+      serialize_with = "serialize_words",
+      deserialize_with = "deserialize_words"
+    )]
+    tags: Vec<String>,
+    #[yaserde(
+      text,
+      serialize_with = "serialize_words",
+      deserialize_with = "deserialize_words"
+    )]
+    words: Vec<String>,
+  }
+
+  let model = Sentence {
+    tags: vec!["a".to_string(), "b".to_string()],
+    words: vec!["hello".to_string(), "world".to_string()],
+  };
+  let content = r#"<sentence tags="a b">hello world</sentence>"#;
+
+  serialize_and_validate!(model, content);
+  deserialize_and_validate!(content, model, Sentence);
+}
+
+#[test]
+fn with_module_on_enum_variant() {
+  init();
+
+  #[derive(Debug, Default, PartialEq, YaDeserialize, YaSerialize)]
+  pub enum Limit {
+    #[yaserde(rename = "timeout")]
+    Timeout(#[yaserde(with = "seconds")] Duration),
+    #[default]
+    #[yaserde(rename = "unlimited")]
+    Unlimited,
+  }
+
+  #[derive(Debug, Default, PartialEq, YaDeserialize, YaSerialize)]
+  #[yaserde(rename = "job")]
+  pub struct Job {
+    limit: Limit,
+  }
+
+  let model = Job {
+    limit: Limit::Timeout(Duration::from_secs(5)),
+  };
+  let content = r#"<job><limit><timeout>5</timeout></limit></job>"#;
+
+  serialize_and_validate!(model, content);
+  deserialize_and_validate!(content, model, Job);
+}
+
+#[test]
+fn with_functions_skip_generic_bounds() {
+  init();
+
+  pub struct Opaque<T>(T);
+
+  fn serialize_opaque<T, W: Write>(
+    _value: &Option<Opaque<T>>,
+    writer: &mut yaserde::ser::Serializer<W>,
+  ) -> Result<(), yaserde::ser::Error> {
+    "opaque".serialize(writer)
+  }
+
+  fn deserialize_opaque<T, R: Read>(
+    reader: &mut yaserde::de::Deserializer<R>,
+  ) -> Result<Option<Opaque<T>>, yaserde::de::Error> {
+    String::deserialize(reader).map(|_| None)
+  }
+
+  #[derive(Default, YaDeserialize, YaSerialize)]
+  #[yaserde(rename = "holder")]
+  pub struct Holder<T> {
+    #[yaserde(
+      serialize_with = "serialize_opaque",
+      deserialize_with = "deserialize_opaque"
+    )]
+    value: Option<Opaque<T>>,
+  }
+
+  let model: Holder<std::cell::Cell<u8>> = Holder { value: None };
+  let content = r#"<holder><value>opaque</value></holder>"#;
+
+  serialize_and_validate!(model, content);
+  let loaded: Holder<std::cell::Cell<u8>> = yaserde::de::from_str(content).unwrap();
+  assert!(loaded.value.is_none());
+}
@@ -1,122 +1,333 @@
-use proc_macro2::{token_stream::IntoIter, Delimiter, Ident, TokenStream, TokenTree};
+use proc_macro2::{Ident, TokenStream};
 use quote::quote;
 use std::collections::BTreeMap;
-use syn::Attribute;
+use syn::punctuated::Punctuated;
+use syn::{Attribute, Lit, LitStr, Meta, NestedMeta, Token};
 
+/// Value of a field missing from the document.
 #[derive(Debug, PartialEq, Clone)]
+pub enum DefaultValue {
+  /// `default`: `Default::default()`
+  Trait,
+  /// `default = "function"`
+  Function(String),
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
 pub struct YaSerdeAttribute {
+  pub any: bool,
+  pub any_attribute: bool,
   pub attribute: bool,
-  pub default: Option<String>,
+  pub borrow: bool,
+  pub bound: Option<String>,
+  pub default: Option<DefaultValue>,
   pub default_namespace: Option<String>,
+  pub deny_unknown_fields: bool,
+  pub deserialize_with: Option<String>,
+  pub display_fromstr: bool,
   pub flatten: bool,
   pub namespaces: BTreeMap<Option<String>, String>,
   pub prefix: Option<String>,
   pub rename: Option<String>,
+  pub rename_all: Option<String>,
+  pub root: Option<String>,
+  pub root_aliases: Vec<String>,
+  pub serialize_with: Option<String>,
   pub skip_serializing: bool,
   pub skip_serializing_if: Option<String>,
   pub text: bool,
 }
 
-fn get_value(iter: &mut IntoIter) -> Option<String> {
-  if let (Some(TokenTree::Punct(operator)), Some(TokenTree::Literal(value))) =
-    (iter.next(), iter.next())
-  {
-    if operator.as_char() == '=' {
-      Some(value.to_string().replace('"', ""))
-    } else {
-      None
+/// Keys written alone, e.g. `#[yaserde(attribute)]`.
+const FLAGS: &[&str] = &[
+  "any",
+  "any_attribute",
+  "attribute",
+  "borrow",
+  "default",
+  "deny_unknown_fields",
+  "display_fromstr",
+  "flatten",
+  "skip_serializing",
+  "text",
+];
+
+/// Keys written with a value, e.g. `#[yaserde(rename = "name")]`.
+const VALUES: &[&str] = &[
+  "bound",
+  "default",
+  "default_namespace",
+  "deserialize_with",
+  "namespace",
+  "prefix",
+  "rename",
+  "rename_all",
+  "root",
+  "root_alias",
+  "serialize_with",
+  "skip_serializing_if",
+  "with",
+];
+
+pub const RENAME_ALL_CASES: &[&str] = &[
+  "camelCase",
+  "PascalCase",
+  "kebab-case",
+  "snake_case",
+  "SCREAMING_SNAKE_CASE",
+  "lowercase",
+];
+
+/// A `key` or `key = "value"` item of the `yaserde` attributes.
+pub struct AttributeItem {
+  pub key: Ident,
+  pub value: Option<LitStr>,
+}
+
+impl AttributeItem {
+  pub fn name(&self) -> String {
+    self.key.to_string()
+  }
+}
+
+/// Read the items of the `yaserde` attributes, rejecting unknown keys and malformed items.
+pub fn parse_items(attrs: &[Attribute]) -> syn::Result<Vec<AttributeItem>> {
+  let mut items = vec![];
+
+  for attr in attrs.iter().filter(|a| a.path.is_ident("yaserde")) {
+    let nested = attr.parse_args_with(Punctuated::<NestedMeta, Token![,]>::parse_terminated)?;
+
+    for meta in nested {
+      let item = match meta {
+        NestedMeta::Meta(Meta::Path(path)) => AttributeItem {
+          key: attribute_key(&path)?,
+          value: None,
+        },
+        NestedMeta::Meta(Meta::NameValue(name_value)) => match name_value.lit {
+          Lit::Str(value) => AttributeItem {
+            key: attribute_key(&name_value.path)?,
+            value: Some(value),
+          },
+          lit => return Err(syn::Error::new_spanned(lit, "expected a string literal")),
+        },
+        meta => {
+          return Err(syn::Error::new_spanned(
+            meta,
+            "expected `key` or `key = \"value\"`",
+          ))
+        }
+      };
+
+      let name = item.name();
+      let is_flag = FLAGS.contains(&name.as_str());
+      let is_value = VALUES.contains(&name.as_str());
+
+      match item.value {
+        None if is_flag => {}
+        Some(_) if is_value => {}
+        None if is_value => {
+          return Err(syn::Error::new_spanned(
+            &item.key,
+            format!("expected `{} = \"...\"`", name),
+          ))
+        }
+        Some(ref value) if is_flag => {
+          return Err(syn::Error::new_spanned(
+            value,
+            format!("`{}` doesn't take a value", name),
+          ))
+        }
+        _ => {
+          return Err(syn::Error::new_spanned(
+            &item.key,
+            format!("unknown yaserde attribute `{}`", name),
+          ))
+        }
+      }
+
+      items.push(item);
     }
-  } else {
-    None
   }
+
+  Ok(items)
+}
+
+fn parse_path(value: &LitStr) -> syn::Result<syn::Path> {
+  value.parse().map_err(|_| {
+    syn::Error::new_spanned(
+      value,
+      "expected a path to a function, like `module::function`",
+    )
+  })
+}
+
+fn attribute_key(path: &syn::Path) -> syn::Result<Ident> {
+  path
+    .get_ident()
+    .cloned()
+    .ok_or_else(|| syn::Error::new_spanned(path, "expected a yaserde attribute name"))
+}
+
+fn set_once(slot: &mut Option<String>, item: &AttributeItem, value: &LitStr) -> syn::Result<()> {
+  if slot.is_some() {
+    return Err(duplicate_error(item));
+  }
+
+  *slot = Some(value.value());
+  Ok(())
+}
+
+fn duplicate_error(item: &AttributeItem) -> syn::Error {
+  syn::Error::new_spanned(
+    &item.key,
+    format!("duplicate yaserde attribute `{}`", item.name()),
+  )
+}
+
+fn with_error(item: &AttributeItem) -> syn::Error {
+  syn::Error::new_spanned(
+    &item.key,
+    "`with` can't be combined with `serialize_with` or `deserialize_with`",
+  )
 }
 
 impl YaSerdeAttribute {
+  /// Attributes of an item already validated with [`YaSerdeAttribute::try_parse`].
   pub fn parse(attrs: &[Attribute]) -> YaSerdeAttribute {
-    let mut attribute = false;
-    let mut flatten = false;
-    let mut default = None;
-    let mut default_namespace = None;
-    let mut namespaces = BTreeMap::new();
-    let mut prefix = None;
-    let mut rename = None;
-    let mut skip_serializing = false;
-    let mut skip_serializing_if = None;
-    let mut text = false;
-
-    for attr in attrs.iter().filter(|a| a.path.is_ident("yaserde")) {
-      let mut attr_iter = attr.clone().tokens.into_iter();
-      if let Some(TokenTree::Group(group)) = attr_iter.next() {
-        if group.delimiter() == Delimiter::Parenthesis {
-          let mut attr_iter = group.stream().into_iter();
-
-          while let Some(item) = attr_iter.next() {
-            if let TokenTree::Ident(ident) = item {
-              match ident.to_string().as_str() {
-                "attribute" => {
-                  attribute = true;
-                }
-                "default" => {
-                  default = get_value(&mut attr_iter);
-                }
-                "default_namespace" => {
-                  default_namespace = get_value(&mut attr_iter);
-                }
-                "flatten" => {
-                  flatten = true;
-                }
-                "namespace" => {
-                  if let Some(namespace) = get_value(&mut attr_iter) {
-                    let splitted: Vec<&str> = namespace.split(": ").collect();
-                    if splitted.len() == 2 {
-                      namespaces.insert(Some(splitted[0].to_owned()), splitted[1].to_owned());
-                    }
-                    if splitted.len() == 1 {
-                      namespaces.insert(None, splitted[0].to_owned());
-                    }
-                  }
-                }
-                "prefix" => {
-                  prefix = get_value(&mut attr_iter);
-                }
-                "rename" => {
-                  rename = get_value(&mut attr_iter);
-                }
-                "skip_serializing" => {
-                  skip_serializing = true;
-                }
-                "skip_serializing_if" => {
-                  skip_serializing_if = get_value(&mut attr_iter);
-                }
-                "text" => {
-                  text = true;
-                }
-                _ => {}
-              }
+    Self::try_parse(attrs).expect("yaserde attributes are checked before the expansion")
+  }
+
+  pub fn try_parse(attrs: &[Attribute]) -> syn::Result<YaSerdeAttribute> {
+    let mut attributes = YaSerdeAttribute::default();
+    let mut with = false;
+
+    for item in parse_items(attrs)? {
+      if item.name() == "default" && attributes.default.is_some() {
+        return Err(duplicate_error(&item));
+      }
+      if with && (item.name() == "serialize_with" || item.name() == "deserialize_with") {
+        return Err(with_error(&item));
+      }
+
+      let value = match item.value {
+        Some(ref value) => value,
+        None => {
+          match item.name().as_str() {
+            "any" => attributes.any = true,
+            "any_attribute" => attributes.any_attribute = true,
+            "attribute" => attributes.attribute = true,
+            "borrow" => attributes.borrow = true,
+            "default" => attributes.default = Some(DefaultValue::Trait),
+            "deny_unknown_fields" => attributes.deny_unknown_fields = true,
+            "display_fromstr" => attributes.display_fromstr = true,
+            "flatten" => attributes.flatten = true,
+            "skip_serializing" => attributes.skip_serializing = true,
+            "text" => attributes.text = true,
+            _ => {}
+          }
+          continue;
+        }
+      };
+
+      match item.name().as_str() {
+        "bound" => {
+          syn::parse_str::<syn::WhereClause>(&format!("where {}", value.value()))
+            .map_err(|error| syn::Error::new_spanned(value, format!("invalid bound: {}", error)))?;
+          set_once(&mut attributes.bound, &item, value)?;
+        }
+        "default" => {
+          parse_path(value)?;
+          attributes.default = Some(DefaultValue::Function(value.value()));
+        }
+        "default_namespace" => set_once(&mut attributes.default_namespace, &item, value)?,
+        "deserialize_with" => {
+          parse_path(value)?;
+          set_once(&mut attributes.deserialize_with, &item, value)?;
+        }
+        "namespace" => {
+          let namespace = value.value();
+          let splitted: Vec<&str> = namespace.split(": ").collect();
+          match splitted[..] {
+            [prefix, uri] => {
+              attributes
+                .namespaces
+                .insert(Some(prefix.to_owned()), uri.to_owned());
             }
+            [uri] => {
+              attributes.namespaces.insert(None, uri.to_owned());
+            }
+            _ => {
+              return Err(syn::Error::new_spanned(
+                value,
+                "expected `namespace = \"prefix: uri\"` or `namespace = \"uri\"`",
+              ))
+            }
+          }
+        }
+        "prefix" => set_once(&mut attributes.prefix, &item, value)?,
+        "rename" => set_once(&mut attributes.rename, &item, value)?,
+        "rename_all" => {
+          if !RENAME_ALL_CASES.contains(&value.value().as_str()) {
+            return Err(syn::Error::new_spanned(
+              value,
+              format!(
+                "unknown rename_all value, expected one of {}",
+                RENAME_ALL_CASES
+                  .iter()
+                  .map(|case| format!("\"{}\"", case))
+                  .collect::<Vec<_>>()
+                  .join(", ")
+              ),
+            ));
+          }
+          set_once(&mut attributes.rename_all, &item, value)?;
+        }
+        "root" => set_once(&mut attributes.root, &item, value)?,
+        "root_alias" => attributes.root_aliases.push(value.value()),
+        "serialize_with" => {
+          parse_path(value)?;
+          set_once(&mut attributes.serialize_with, &item, value)?;
+        }
+        "skip_serializing_if" => {
+          value.parse::<Ident>().map_err(|_| {
+            syn::Error::new_spanned(value, "expected the name of a method, like `is_empty`")
+          })?;
+          set_once(&mut attributes.skip_serializing_if, &item, value)?;
+        }
+        "with" => {
+          let module = parse_path(value)?;
+          if with || attributes.serialize_with.is_some() || attributes.deserialize_with.is_some() {
+            return Err(with_error(&item));
           }
+          with = true;
+          let module = quote!(#module).to_string().replace(' ', "");
+          attributes.serialize_with = Some(format!("{}::serialize", module));
+          attributes.deserialize_with = Some(format!("{}::deserialize", module));
         }
+        _ => {}
       }
     }
 
-    YaSerdeAttribute {
-      attribute,
-      default,
-      default_namespace,
-      flatten,
-      namespaces,
-      prefix,
-      rename,
-      skip_serializing,
-      skip_serializing_if,
-      text,
-    }
+    Ok(attributes)
   }
 
   pub fn xml_element_name(&self, ident: &Ident) -> String {
     self.rename.clone().unwrap_or_else(|| ident.to_string())
   }
 
+  /// Name of the document element when the type is the root of a document.
+  pub fn document_element_name(&self, ident: &Ident) -> String {
+    self
+      .root
+      .clone()
+      .unwrap_or_else(|| self.xml_element_name(ident))
+  }
+
+  /// Namespace expected for the element of the container, declared for its prefix.
+  pub fn element_namespace(&self) -> Option<&String> {
+    self.namespaces.get(&self.prefix)
+  }
+
   pub fn prefix_namespace(&self) -> String {
     if self.default_namespace == self.prefix {
       "".to_string()
@@ -158,9 +369,13 @@ impl YaSerdeAttribute {
         match namespace.as_str() {
           #namespaces_matches
           bad_namespace => {
-            let msg =
-              ::std::format!("bad namespace for {}, found {}", #element_name, bad_namespace);
-            return Err(msg);
+            return ::std::result::Result::Err(
+              ::yaserde::de::ErrorKind::BadNamespace {
+                element: #element_name.to_string(),
+                namespace: bad_namespace.to_string(),
+              }
+              .into(),
+            );
           }
         }
       }
@@ -175,13 +390,24 @@ fn parse_empty_attributes() {
 
   assert_eq!(
     YaSerdeAttribute {
+      any: false,
+      any_attribute: false,
       attribute: false,
+      borrow: false,
+      bound: None,
       default: None,
       default_namespace: None,
+      deny_unknown_fields: false,
+      deserialize_with: None,
+      display_fromstr: false,
       flatten: false,
       namespaces: BTreeMap::new(),
       prefix: None,
       rename: None,
+      rename_all: None,
+      root: None,
+      root_aliases: vec![],
+      serialize_with: None,
       skip_serializing: false,
       skip_serializing_if: None,
       text: false,
@@ -225,13 +451,24 @@ fn parse_attributes() {
 
   assert_eq!(
     YaSerdeAttribute {
+      any: false,
+      any_attribute: false,
       attribute: true,
+      borrow: false,
+      bound: None,
       default: None,
       default_namespace: None,
+      deny_unknown_fields: false,
+      deserialize_with: None,
+      display_fromstr: false,
       flatten: false,
       namespaces: BTreeMap::new(),
       prefix: None,
       rename: None,
+      rename_all: None,
+      root: None,
+      root_aliases: vec![],
+      serialize_with: None,
       skip_serializing: false,
       skip_serializing_if: None,
       text: false,
@@ -275,13 +512,24 @@ fn only_parse_yaserde_attributes() {
 
   assert_eq!(
     YaSerdeAttribute {
+      any: false,
+      any_attribute: false,
       attribute: false,
+      borrow: false,
+      bound: None,
       default: None,
       default_namespace: None,
+      deny_unknown_fields: false,
+      deserialize_with: None,
+      display_fromstr: false,
       flatten: false,
       namespaces: BTreeMap::new(),
       prefix: None,
       rename: None,
+      rename_all: None,
+      root: None,
+      root_aliases: vec![],
+      serialize_with: None,
       skip_serializing: false,
       skip_serializing_if: None,
       text: false,
@@ -331,13 +579,24 @@ fn parse_attributes_with_values() {
 
   assert_eq!(
     YaSerdeAttribute {
+      any: false,
+      any_attribute: false,
       attribute: true,
+      borrow: false,
+      bound: None,
       default: None,
       default_namespace: Some("example".to_string()),
+      deny_unknown_fields: false,
+      deserialize_with: None,
+      display_fromstr: false,
       flatten: true,
       namespaces,
       prefix: None,
       rename: None,
+      rename_all: None,
+      root: None,
+      root_aliases: vec![],
+      serialize_with: None,
       skip_serializing: false,
       skip_serializing_if: None,
       text: false,
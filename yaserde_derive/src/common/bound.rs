@@ -0,0 +1,109 @@
+use crate::common::{YaSerdeAttribute, YaSerdeField};
+use proc_macro2::{Ident, TokenStream, TokenTree};
+use quote::ToTokens;
+use std::collections::BTreeSet;
+
+/// All the fields of a struct or of the variants of an enum.
+pub fn all_fields(data: &syn::Data) -> Vec<YaSerdeField> {
+  match data {
+    syn::Data::Struct(data_struct) => data_struct
+      .fields
+      .iter()
+      .map(|field| YaSerdeField::new(field.clone()))
+      .collect(),
+    syn::Data::Enum(data_enum) => data_enum
+      .variants
+      .iter()
+      .flat_map(|variant| variant.fields.iter())
+      .map(|field| YaSerdeField::new(field.clone()))
+      .collect(),
+    syn::Data::Union(_) => vec![],
+  }
+}
+
+/// Add the bounds required by the generated implementation to the generics of the container.
+///
+/// Type parameters used in elements are bound by `element_bound`, the ones used in attributes
+/// or text content by `value_bound`. Fields handled by a custom function (`is_custom`) are not
+/// bound. The `bound` container attribute replaces these bounds.
+pub fn with_bounds(
+  ast: &syn::DeriveInput,
+  root_attributes: &YaSerdeAttribute,
+  element_bound: TokenStream,
+  value_bound: TokenStream,
+  is_custom: impl Fn(&YaSerdeField) -> bool,
+) -> syn::Result<syn::Generics> {
+  let mut generics = ast.generics.clone();
+
+  if let Some(bound) = &root_attributes.bound {
+    let where_clause: syn::WhereClause = syn::parse_str(&format!("where {}", bound))?;
+    generics
+      .make_where_clause()
+      .predicates
+      .extend(where_clause.predicates);
+    return Ok(generics);
+  }
+
+  let type_parameters: Vec<Ident> = ast
+    .generics
+    .type_params()
+    .map(|param| param.ident.clone())
+    .collect();
+
+  let mut element_parameters = BTreeSet::new();
+  let mut value_parameters = BTreeSet::new();
+
+  for field in all_fields(&ast.data) {
+    if is_custom(&field) {
+      continue;
+    }
+    let used = used_type_parameters(field.get_type(), &type_parameters);
+    if field.is_attribute() || field.is_text_content() {
+      value_parameters.extend(used);
+    } else {
+      element_parameters.extend(used);
+    }
+  }
+
+  let where_clause = generics.make_where_clause();
+  for parameter in element_parameters {
+    where_clause
+      .predicates
+      .push(syn::parse_quote!(#parameter: #element_bound));
+  }
+  for parameter in value_parameters {
+    where_clause
+      .predicates
+      .push(syn::parse_quote!(#parameter: #value_bound));
+  }
+
+  Ok(generics)
+}
+
+/// Whether `field_type` refers to one of the type parameters of the container.
+pub fn is_generic(field_type: &syn::Type, generics: &syn::Generics) -> bool {
+  let type_parameters: Vec<Ident> = generics
+    .type_params()
+    .map(|param| param.ident.clone())
+    .collect();
+
+  !used_type_parameters(field_type, &type_parameters).is_empty()
+}
+
+fn used_type_parameters(field_type: &syn::Type, type_parameters: &[Ident]) -> BTreeSet<Ident> {
+  fn visit(tokens: TokenStream, type_parameters: &[Ident], used: &mut BTreeSet<Ident>) {
+    for token in tokens {
+      match token {
+        TokenTree::Ident(ident) if type_parameters.contains(&ident) => {
+          used.insert(ident);
+        }
+        TokenTree::Group(group) => visit(group.stream(), type_parameters, used),
+        _ => {}
+      }
+    }
+  }
+
+  let mut used = BTreeSet::new();
+  visit(field_type.to_token_stream(), type_parameters, &mut used);
+  used
+}
@@ -0,0 +1,342 @@
+use crate::common::attribute::{parse_items, AttributeItem};
+use crate::common::YaSerdeAttribute;
+use syn::{Data, DeriveInput, Fields};
+
+/// Keys accepted on the struct or enum deriving the traits.
+const CONTAINER_KEYS: &[&str] = &[
+  "bound",
+  "default_namespace",
+  "deny_unknown_fields",
+  "display_fromstr",
+  "flatten",
+  "namespace",
+  "prefix",
+  "rename",
+  "rename_all",
+  "root",
+  "root_alias",
+];
+
+/// Keys accepted on enum variants.
+const VARIANT_KEYS: &[&str] = &["flatten", "prefix", "rename", "rename_all"];
+
+/// Keys accepted on struct and variant fields.
+const FIELD_KEYS: &[&str] = &[
+  "any",
+  "any_attribute",
+  "attribute",
+  "borrow",
+  "default",
+  "deserialize_with",
+  "display_fromstr",
+  "flatten",
+  "namespace",
+  "prefix",
+  "rename",
+  "serialize_with",
+  "skip_serializing",
+  "skip_serializing_if",
+  "text",
+  "with",
+];
+
+/// Keys accepted on the named fields of enum variants, which are only read from child elements.
+const VARIANT_FIELD_KEYS: &[&str] = &[
+  "borrow",
+  "default",
+  "deserialize_with",
+  "display_fromstr",
+  "namespace",
+  "prefix",
+  "rename",
+  "serialize_with",
+  "skip_serializing",
+  "skip_serializing_if",
+  "with",
+];
+
+/// Check the derive input before the expansion: attributes have to be known, allowed where
+/// they are written and consistent, names have to be valid XML names and prefixes declared.
+///
+/// All the errors found are reported together.
+pub fn check_input(ast: &DeriveInput) -> syn::Result<()> {
+  let mut errors = Errors::default();
+
+  let root_attributes = errors.attributes(&ast.attrs, "a container", CONTAINER_KEYS);
+  let root_attributes = root_attributes.as_ref();
+
+  if let Some(attributes) = root_attributes {
+    errors.check_prefix(&ast.attrs, attributes);
+  }
+  errors.check_name(&ast.attrs, "rename");
+  errors.check_name(&ast.attrs, "root");
+  errors.check_name(&ast.attrs, "root_alias");
+
+  match ast.data {
+    Data::Struct(ref data_struct) => {
+      if let Fields::Unnamed(ref fields) = data_struct.fields {
+        errors.push(syn::Error::new_spanned(
+          fields,
+          "tuple structs are not supported by yaserde, use named fields",
+        ));
+      }
+      errors.check_fields(&data_struct.fields, root_attributes, "a field", FIELD_KEYS);
+    }
+    Data::Enum(ref data_enum) => {
+      for variant in data_enum.variants.iter() {
+        errors.attributes(&variant.attrs, "an enum variant", VARIANT_KEYS);
+        if let Some(attributes) = root_attributes {
+          errors.check_prefix(&variant.attrs, attributes);
+        }
+        // Unit variants are also matched as text values, which are not XML names
+        if !matches!(variant.fields, Fields::Unit) {
+          errors.check_name(&variant.attrs, "rename");
+        }
+
+        if let Fields::Unnamed(ref fields) = variant.fields {
+          if fields.unnamed.len() > 1 {
+            errors.push(syn::Error::new_spanned(
+              fields,
+              "enum variants with multiple fields are not supported by yaserde",
+            ));
+          }
+        }
+        match variant.fields {
+          Fields::Named(_) => errors.check_fields(
+            &variant.fields,
+            root_attributes,
+            "a named field of an enum variant",
+            VARIANT_FIELD_KEYS,
+          ),
+          _ => errors.check_fields(&variant.fields, root_attributes, "a field", FIELD_KEYS),
+        }
+      }
+    }
+    Data::Union(ref data_union) => {
+      errors.push(syn::Error::new_spanned(
+        data_union.union_token,
+        "unions are not supported by yaserde",
+      ));
+    }
+  }
+
+  errors.into_result()
+}
+
+#[derive(Default)]
+struct Errors(Option<syn::Error>);
+
+impl Errors {
+  fn push(&mut self, error: syn::Error) {
+    match self.0 {
+      Some(ref mut errors) => errors.combine(error),
+      None => self.0 = Some(error),
+    }
+  }
+
+  fn into_result(self) -> syn::Result<()> {
+    match self.0 {
+      Some(error) => Err(error),
+      None => Ok(()),
+    }
+  }
+
+  /// Parse the attributes of an item, checking that their keys are allowed on it.
+  fn attributes(
+    &mut self,
+    attrs: &[syn::Attribute],
+    location: &str,
+    allowed_keys: &[&str],
+  ) -> Option<YaSerdeAttribute> {
+    if let Ok(items) = parse_items(attrs) {
+      for item in items {
+        if !allowed_keys.contains(&item.name().as_str()) {
+          self.push(syn::Error::new_spanned(
+            &item.key,
+            format!("`{}` can't be used on {}", item.name(), location),
+          ));
+        }
+      }
+    }
+
+    match YaSerdeAttribute::try_parse(attrs) {
+      Ok(attributes) => Some(attributes),
+      Err(error) => {
+        self.push(error);
+        None
+      }
+    }
+  }
+
+  fn check_fields(
+    &mut self,
+    fields: &Fields,
+    root_attributes: Option<&YaSerdeAttribute>,
+    location: &str,
+    allowed_keys: &[&str],
+  ) {
+    let mut text_field = false;
+    let mut any_field = false;
+    let mut any_attribute_field = false;
+
+    for field in fields.iter() {
+      let attributes = match self.attributes(&field.attrs, location, allowed_keys) {
+        Some(attributes) => attributes,
+        None => continue,
+      };
+      let items = parse_items(&field.attrs).unwrap_or_default();
+
+      if attributes.attribute && attributes.text {
+        self.push(item_error(
+          &items,
+          "text",
+          "a field can't be both an attribute and the text content",
+        ));
+      }
+      if attributes.text && attributes.flatten {
+        self.push(item_error(
+          &items,
+          "flatten",
+          "the text content can't be flattened",
+        ));
+      }
+      if attributes.any {
+        if let Some(item) = items
+          .iter()
+          .find(|item| ["attribute", "text", "flatten"].contains(&item.name().as_str()))
+        {
+          self.push(syn::Error::new_spanned(
+            &item.key,
+            format!("`{}` can't be combined with `any`", item.name()),
+          ));
+        }
+        if any_field {
+          self.push(item_error(
+            &items,
+            "any",
+            "only one field can collect the unknown elements",
+          ));
+        }
+        any_field = true;
+      }
+      if attributes.any_attribute {
+        if let Some(item) = items
+          .iter()
+          .find(|item| ["any", "attribute", "text", "flatten"].contains(&item.name().as_str()))
+        {
+          self.push(syn::Error::new_spanned(
+            &item.key,
+            format!("`{}` can't be combined with `any_attribute`", item.name()),
+          ));
+        }
+        if any_attribute_field {
+          self.push(item_error(
+            &items,
+            "any_attribute",
+            "only one field can collect the unknown attributes",
+          ));
+        }
+        any_attribute_field = true;
+      }
+      if attributes.display_fromstr
+        && (attributes.serialize_with.is_some() || attributes.deserialize_with.is_some())
+      {
+        self.push(item_error(
+          &items,
+          "display_fromstr",
+          "`display_fromstr` can't be combined with `with`, `serialize_with` or `deserialize_with`",
+        ));
+      }
+      if attributes.borrow && (attributes.deserialize_with.is_some() || attributes.display_fromstr)
+      {
+        self.push(item_error(
+          &items,
+          "borrow",
+          "`borrow` can't be combined with `with`, `deserialize_with` or `display_fromstr`",
+        ));
+      }
+      if attributes.text {
+        if text_field {
+          self.push(item_error(
+            &items,
+            "text",
+            "only one field can be the text content",
+          ));
+        }
+        text_field = true;
+      } else {
+        self.check_name(&field.attrs, "rename");
+      }
+
+      if let Some(root_attributes) = root_attributes {
+        self.check_prefix(&field.attrs, root_attributes);
+      }
+    }
+  }
+
+  /// Element and attribute names have to be valid XML names without prefix.
+  fn check_name(&mut self, attrs: &[syn::Attribute], key: &str) {
+    let items = parse_items(attrs).unwrap_or_default();
+
+    for item in items.iter().filter(|item| item.name() == key) {
+      if let Some(ref value) = item.value {
+        if !is_ncname(&value.value()) {
+          self.push(syn::Error::new_spanned(
+            value,
+            format!(
+              "\"{}\" is not a valid XML name, use `prefix` for namespaces",
+              value.value()
+            ),
+          ));
+        }
+      }
+    }
+  }
+
+  /// Prefixes have to be declared by a `namespace` of the container.
+  fn check_prefix(&mut self, attrs: &[syn::Attribute], root_attributes: &YaSerdeAttribute) {
+    let items = parse_items(attrs).unwrap_or_default();
+
+    for item in items
+      .iter()
+      .filter(|item| item.name() == "prefix" || item.name() == "default_namespace")
+    {
+      if let Some(ref value) = item.value {
+        let prefix = value.value();
+        let declared = prefix == "xml"
+          || root_attributes
+            .namespaces
+            .contains_key(&Some(prefix.clone()));
+
+        if !declared {
+          self.push(syn::Error::new_spanned(
+            value,
+            format!(
+              "prefix \"{0}\" is not declared, add `namespace = \"{0}: uri\"` to the container",
+              prefix
+            ),
+          ));
+        }
+      }
+    }
+  }
+}
+
+fn item_error(items: &[AttributeItem], key: &str, message: &str) -> syn::Error {
+  match items.iter().find(|item| item.name() == key) {
+    Some(item) => syn::Error::new_spanned(&item.key, message),
+    None => syn::Error::new(items[0].key.span(), message),
+  }
+}
+
+/// Whether `name` is an XML name without colon, as defined by Namespaces in XML.
+fn is_ncname(name: &str) -> bool {
+  let mut chars = name.chars();
+
+  match chars.next() {
+    Some(first) if first.is_alphabetic() || first == '_' => {}
+    _ => return false,
+  }
+
+  chars.all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.')
+}
@@ -1,12 +1,9 @@
-use crate::common::attribute::YaSerdeAttribute;
-use heck::ToUpperCamelCase;
+use crate::common::attribute::{DefaultValue, YaSerdeAttribute};
 use proc_macro2::Span;
 use proc_macro2::{Ident, TokenStream};
-use quote::{format_ident, quote};
-use std::fmt;
+use quote::quote;
 use syn::ext::IdentExt;
 use syn::spanned::Spanned;
-use syn::Type::Path;
 
 #[derive(Debug)]
 pub struct YaSerdeField {
@@ -36,6 +33,16 @@ impl YaSerdeField {
     self.attributes.flatten
   }
 
+  /// Whether the field collects the child elements no other field reads.
+  pub fn is_any(&self) -> bool {
+    self.attributes.any
+  }
+
+  /// Whether the field collects the attributes no other field reads.
+  pub fn is_any_attribute(&self) -> bool {
+    self.attributes.any_attribute
+  }
+
   pub fn label(&self) -> Option<Ident> {
     self.syn_field.ident.clone()
   }
@@ -52,6 +59,16 @@ impl YaSerdeField {
       .map(|ident| syn::Ident::new(&format!("__{}_value", ident.unraw()), ident.span()))
   }
 
+  /// Same as `get_value_label` for a field of the enum variant `variant`.
+  pub fn get_variant_value_label(&self, variant: &Ident) -> Option<syn::Ident> {
+    self.syn_field.ident.clone().map(|ident| {
+      syn::Ident::new(
+        &format!("__{}_{}_value", variant.unraw(), ident.unraw()),
+        ident.span(),
+      )
+    })
+  }
+
   pub fn renamed_label_without_namespace(&self) -> String {
     self
       .attributes
@@ -76,44 +93,113 @@ impl YaSerdeField {
     format!("{}{}", prefix, label)
   }
 
-  pub fn get_visitor_ident(&self, struct_name: Option<&syn::Path>) -> Ident {
-    let label = self.renamed_label_without_namespace();
+  pub fn get_type(&self) -> &syn::Type {
+    &self.syn_field.ty
+  }
 
-    let struct_id = struct_name.map_or_else(
-      || "".to_string(),
-      |struct_name| {
-        struct_name
-          .segments
-          .iter()
-          .map(|s| s.ident.to_string())
-          .collect()
-      },
-    );
-
-    Ident::new(
-      &format!(
-        "__Visitor_{}_{}",
-        label.replace('.', "_").to_upper_camel_case(),
-        struct_id
-      ),
-      self.get_span(),
-    )
+  pub fn get_span(&self) -> Span {
+    self.syn_field.span()
   }
 
-  pub fn get_type(&self) -> Field {
-    Field::from(&self.syn_field)
+  pub fn get_default_function(&self) -> Option<syn::Path> {
+    match self.attributes.default {
+      Some(DefaultValue::Function(ref default)) => Some(parse_function_path(default)),
+      _ => None,
+    }
   }
 
-  pub fn get_span(&self) -> Span {
-    self.syn_field.span()
+  pub fn has_default_trait(&self) -> bool {
+    self.attributes.default == Some(DefaultValue::Trait)
   }
 
-  pub fn get_default_function(&self) -> Option<Ident> {
+  /// Expression of the value used when the field is missing from the document, if any.
+  pub fn get_default_value(&self) -> Option<TokenStream> {
+    let field_type = self.get_type();
+
     self
       .attributes
       .default
       .as_ref()
-      .map(|default| Ident::new(default, self.get_span()))
+      .map(|default| match default {
+        DefaultValue::Trait => quote!(<#field_type as ::std::default::Default>::default()),
+        DefaultValue::Function(_) => {
+          let default_function = self.get_default_function();
+          quote!(#default_function())
+        }
+      })
+  }
+
+  pub fn get_serialize_with_function(&self) -> Option<syn::Path> {
+    if self.attributes.display_fromstr {
+      return Some(if self.is_attribute() || self.is_text_content() {
+        syn::parse_quote!(::yaserde::ser::to_string_value)
+      } else {
+        syn::parse_quote!(::yaserde::ser::serialize_display)
+      });
+    }
+
+    self
+      .attributes
+      .serialize_with
+      .as_ref()
+      .map(|serialize_with| parse_function_path(serialize_with))
+  }
+
+  pub fn get_deserialize_with_function(&self) -> Option<syn::Path> {
+    if self.attributes.display_fromstr {
+      return Some(if self.is_attribute() || self.is_text_content() {
+        syn::parse_quote!(::yaserde::de::from_str_value)
+      } else {
+        syn::parse_quote!(::yaserde::de::deserialize_from_str)
+      });
+    }
+
+    self
+      .attributes
+      .deserialize_with
+      .as_ref()
+      .map(|deserialize_with| parse_function_path(deserialize_with))
+  }
+
+  /// Expression reading the flattened field from `events`, the unused ones of its container.
+  pub fn deserialize_flattened(&self, events: TokenStream) -> TokenStream {
+    let field_type = self.get_type();
+    quote!(<#field_type as ::yaserde::YaDeserialize<'de>>::deserialize_flattened(reader, #events)?)
+  }
+
+  /// Expression reading the field from the element at the position of `reader`.
+  pub fn deserialize_element(&self) -> TokenStream {
+    let field_type = self.get_type();
+
+    if self.attributes.borrow {
+      quote!(::yaserde::de::deserialize_borrowed_cow(reader)?)
+    } else {
+      quote!(<#field_type as ::yaserde::YaDeserialize>::deserialize(reader)?)
+    }
+  }
+
+  /// Expression reading the field from `value`, the text of an attribute or of the content
+  /// just read by `reader`.
+  pub fn deserialize_value(&self, value: TokenStream) -> TokenStream {
+    let field_type = self.get_type();
+
+    if let Some(deserialize_with) = self.get_deserialize_with_function() {
+      quote!(#deserialize_with(#value)?)
+    } else if self.attributes.borrow {
+      quote!(::yaserde::de::borrow_cow_value(#value, reader)?)
+    } else {
+      quote!(
+        <#field_type as ::yaserde::YaDeserializeValue>::deserialize_borrowed_value(#value, reader)?
+      )
+    }
+  }
+
+  /// Expression writing the field `value` as the text of an attribute or of the content.
+  pub fn serialize_value(&self, value: TokenStream) -> TokenStream {
+    self
+      .get_serialize_with_function()
+      .map(|serialize_with| quote!(#serialize_with(#value)?))
+      .unwrap_or_else(|| quote!(::yaserde::YaSerializeValue::serialize_value(#value)?))
   }
 
   pub fn get_skip_serializing_if_function(&self) -> Option<Ident> {
@@ -130,6 +216,11 @@ impl YaSerdeField {
     element_namespace: TokenStream,
     element_name: TokenStream,
   ) -> TokenStream {
+    // Without an explicit prefix the namespace of the element is left to its own type
+    if self.attributes.prefix.is_none() {
+      return quote!();
+    }
+
     root_attributes.get_namespace_matching(
       &self.attributes.prefix,
       element_namespace,
@@ -138,6 +229,12 @@ impl YaSerdeField {
     )
   }
 
+  /// URI of the namespace bound to the prefix of the field, if it has one.
+  pub fn get_namespace(&self, root_attributes: &YaSerdeAttribute) -> Option<String> {
+    let prefix = self.attributes.prefix.clone()?;
+    root_attributes.namespaces.get(&Some(prefix)).cloned()
+  }
+
   pub fn ser_wrap_default_attribute(
     &self,
     builder: Option<TokenStream>,
@@ -174,132 +271,7 @@ impl YaSerdeField {
   }
 }
 
-#[derive(Debug)]
-pub enum Field {
-  FieldString,
-  FieldBool,
-  FieldI8,
-  FieldU8,
-  FieldI16,
-  FieldU16,
-  FieldI32,
-  FieldU32,
-  FieldI64,
-  FieldU64,
-  FieldF32,
-  FieldF64,
-  FieldOption { data_type: Box<Field> },
-  FieldVec { data_type: Box<Field> },
-  FieldStruct { struct_name: syn::Path },
-}
-
-impl Field {
-  pub fn get_simple_type_visitor(&self) -> Ident {
-    format_ident!("visit_{}", self.to_string())
-  }
-}
-
-impl From<&syn::Path> for Field {
-  fn from(path: &syn::Path) -> Self {
-    let result = if let Some(segment) = path.segments.last() {
-      match segment.ident.to_string().as_str() {
-        "String" => Some(Field::FieldString),
-        "bool" => Some(Field::FieldBool),
-        "i8" => Some(Field::FieldI8),
-        "u8" => Some(Field::FieldU8),
-        "i16" => Some(Field::FieldI16),
-        "u16" => Some(Field::FieldU16),
-        "i32" => Some(Field::FieldI32),
-        "u32" => Some(Field::FieldU32),
-        "i64" => Some(Field::FieldI64),
-        "u64" => Some(Field::FieldU64),
-        "f32" => Some(Field::FieldF32),
-        "f64" => Some(Field::FieldF64),
-        "Option" => Some(Field::FieldOption {
-          data_type: Box::new(Field::from(segment)),
-        }),
-        "Vec" => Some(Field::FieldVec {
-          data_type: Box::new(Field::from(segment)),
-        }),
-        _ => None,
-      }
-    } else {
-      None
-    };
-
-    result.unwrap_or_else(|| Field::FieldStruct {
-      struct_name: path.clone(),
-    })
-  }
-}
-
-impl From<&syn::Field> for Field {
-  fn from(field: &syn::Field) -> Self {
-    let mut ty = &field.ty;
-    while let syn::Type::Group(g) = ty {
-      ty = &g.elem;
-    }
-    match ty {
-      Path(ref path) => Field::from(&path.path),
-      _ => panic!("unable to match {:?}", field.ty),
-    }
-  }
-}
-
-impl From<&syn::PathSegment> for Field {
-  fn from(path_segment: &syn::PathSegment) -> Self {
-    if let syn::PathArguments::AngleBracketed(ref args) = path_segment.arguments {
-      if let Some(syn::GenericArgument::Type(Path(ref path))) = args.args.first() {
-        return Field::from(&path.path);
-      }
-    }
-    unimplemented!()
-  }
-}
-
-impl From<Field> for proc_macro2::TokenStream {
-  fn from(field: Field) -> proc_macro2::TokenStream {
-    match field {
-      Field::FieldString => quote! { ::std::string::String },
-      Field::FieldBool => quote! { bool },
-      Field::FieldI8 => quote! { i8 },
-      Field::FieldU8 => quote! { u8 },
-      Field::FieldI16 => quote! { i16 },
-      Field::FieldU16 => quote! { u16 },
-      Field::FieldI32 => quote! { i32 },
-      Field::FieldU32 => quote! { u32 },
-      Field::FieldI64 => quote! { i64 },
-      Field::FieldU64 => quote! { u64 },
-      Field::FieldF32 => quote! { f32 },
-      Field::FieldF64 => quote! { f64 },
-      _ => panic!("Not a simple type: {:?}", field),
-    }
-  }
-}
-
-impl From<&Field> for String {
-  fn from(field: &Field) -> String {
-    match field {
-      Field::FieldString => "str".to_string(),
-      Field::FieldBool => "bool".to_string(),
-      Field::FieldI8 => "i8".to_string(),
-      Field::FieldU8 => "u8".to_string(),
-      Field::FieldI16 => "i16".to_string(),
-      Field::FieldU16 => "u16".to_string(),
-      Field::FieldI32 => "i32".to_string(),
-      Field::FieldU32 => "u32".to_string(),
-      Field::FieldI64 => "i64".to_string(),
-      Field::FieldU64 => "u64".to_string(),
-      Field::FieldF32 => "f32".to_string(),
-      Field::FieldF64 => "f64".to_string(),
-      _ => panic!("Not a simple type: {:?}", field),
-    }
-  }
-}
-
-impl fmt::Display for Field {
-  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-    let string_representation: String = self.into();
-    write!(f, "{}", string_representation)
-  }
+/// Function paths are checked when the attributes are parsed.
+fn parse_function_path(path: &str) -> syn::Path {
+  syn::parse_str(path).expect("yaserde function paths are checked before the expansion")
 }
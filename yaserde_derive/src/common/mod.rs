@@ -1,5 +1,8 @@
 mod attribute;
+pub mod bound;
+pub mod check;
 mod field;
+pub mod rename;
 
 pub use attribute::YaSerdeAttribute;
-pub use field::{Field, YaSerdeField};
+pub use field::YaSerdeField;
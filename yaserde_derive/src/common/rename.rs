@@ -0,0 +1,59 @@
+use crate::common::YaSerdeAttribute;
+use heck::{ToKebabCase, ToLowerCamelCase, ToShoutySnakeCase, ToSnakeCase, ToUpperCamelCase};
+use syn::ext::IdentExt;
+
+/// Apply the `rename_all` attributes of the container and of the enum variants.
+///
+/// Fields and variants without an explicit `rename` get one built from their identifier, so
+/// the expansions don't have to know about `rename_all`.
+pub fn apply_rename_all(ast: &mut syn::DeriveInput) {
+  let rename_all = YaSerdeAttribute::parse(&ast.attrs).rename_all;
+
+  match ast.data {
+    syn::Data::Struct(ref mut data_struct) => {
+      rename_fields(&mut data_struct.fields, &rename_all);
+    }
+    syn::Data::Enum(ref mut data_enum) => {
+      for variant in data_enum.variants.iter_mut() {
+        rename(&variant.ident, &mut variant.attrs, &rename_all);
+
+        let variant_rename_all = YaSerdeAttribute::parse(&variant.attrs).rename_all;
+        rename_fields(&mut variant.fields, &variant_rename_all);
+      }
+    }
+    syn::Data::Union(_) => {}
+  }
+}
+
+fn rename_fields(fields: &mut syn::Fields, rename_all: &Option<String>) {
+  for field in fields.iter_mut() {
+    if let Some(ident) = field.ident.clone() {
+      rename(&ident, &mut field.attrs, rename_all);
+    }
+  }
+}
+
+fn rename(ident: &syn::Ident, attrs: &mut Vec<syn::Attribute>, rename_all: &Option<String>) {
+  let rename_all = match rename_all {
+    Some(rename_all) => rename_all,
+    None => return,
+  };
+
+  if YaSerdeAttribute::parse(attrs).rename.is_some() {
+    return;
+  }
+
+  let name = ident.unraw().to_string();
+  let renamed = match rename_all.as_str() {
+    "camelCase" => name.to_lower_camel_case(),
+    "PascalCase" => name.to_upper_camel_case(),
+    "kebab-case" => name.to_kebab_case(),
+    "snake_case" => name.to_snake_case(),
+    "SCREAMING_SNAKE_CASE" => name.to_shouty_snake_case(),
+    "lowercase" => name.to_lowercase(),
+    // Other values are rejected when the attributes are parsed
+    _ => name,
+  };
+
+  attrs.push(syn::parse_quote!(#[yaserde(rename = #renamed)]));
+}
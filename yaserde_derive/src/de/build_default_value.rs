@@ -2,24 +2,67 @@ use crate::common::YaSerdeField;
 use proc_macro2::TokenStream;
 use quote::quote;
 
-pub fn build_default_value(
-  field: &YaSerdeField,
-  field_type: Option<TokenStream>,
-  value: TokenStream,
-) -> Option<TokenStream> {
-  let label = field.get_value_label();
+/// Value of a field missing from the document: its `default`, the empty value of optional
+/// types and sequences, or a missing field error.
+pub fn build_default_value(field: &YaSerdeField) -> TokenStream {
+  if let Some(default_value) = field.get_default_value() {
+    return default_value;
+  }
 
-  let default_value = field
-    .get_default_function()
-    .map(|default_function| quote!(#default_function()))
-    .unwrap_or_else(|| quote!(#value));
+  // The map collecting the unknown attributes is empty without them
+  if field.is_any_attribute() {
+    return quote!(::std::default::Default::default());
+  }
 
-  let field_type = field_type
-    .map(|field_type| quote!(: #field_type))
-    .unwrap_or_default();
+  let field_type = field.get_type();
+  let custom = field.get_deserialize_with_function().is_some();
 
-  Some(quote! {
-    #[allow(unused_mut)]
-    let mut #label #field_type = #default_value;
-  })
+  if field.is_text_content() {
+    // An element without characters has an empty text content
+    let empty_text = field.deserialize_value(quote!(""));
+
+    return if custom {
+      empty_text
+    } else {
+      quote! {
+        match <#field_type as ::yaserde::YaDeserializeValue>::missing_value() {
+          ::std::option::Option::Some(value) => value,
+          ::std::option::Option::None => #empty_text,
+        }
+      }
+    };
+  }
+
+  let label_name = field.renamed_label_without_namespace();
+  let (missing_value, missing_field) = if field.is_attribute() {
+    (
+      quote!(<#field_type as ::yaserde::YaDeserializeValue>::missing_value()),
+      format!("attribute {}", label_name),
+    )
+  } else {
+    (
+      quote!(<#field_type as ::yaserde::YaDeserialize>::missing()),
+      format!("element <{}>", label_name),
+    )
+  };
+  let missing_value = if custom {
+    quote!(::std::option::Option::None::<#field_type>)
+  } else {
+    missing_value
+  };
+
+  quote! {
+    match #missing_value {
+      ::std::option::Option::Some(value) => value,
+      ::std::option::Option::None => {
+        return ::std::result::Result::Err(
+          ::yaserde::de::ErrorKind::MissingField {
+            field: #missing_field.to_string(),
+            container: named_element.clone(),
+          }
+          .into(),
+        );
+      }
+    }
+  }
 }
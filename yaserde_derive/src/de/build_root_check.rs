@@ -0,0 +1,24 @@
+use crate::common::YaSerdeAttribute;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::Ident;
+
+/// Check of the document element for containers with a `root` or `root_alias`: its name and
+/// namespace have to match when the type is the root of the document.
+pub fn build_root_check(root_attributes: &YaSerdeAttribute, name: &Ident) -> TokenStream {
+  if root_attributes.root.is_none() && root_attributes.root_aliases.is_empty() {
+    return quote!();
+  }
+
+  let mut names = vec![root_attributes.document_element_name(name)];
+  names.extend(root_attributes.root_aliases.iter().cloned());
+
+  let namespace = match root_attributes.element_namespace() {
+    Some(namespace) => quote!(::std::option::Option::Some(#namespace)),
+    None => quote!(::std::option::Option::None),
+  };
+
+  quote! {
+    ::yaserde::de::check_root(reader, &[#(#names),*], #namespace)?;
+  }
+}
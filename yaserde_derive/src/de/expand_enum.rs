@@ -1,11 +1,17 @@
-use crate::common::{Field, YaSerdeAttribute, YaSerdeField};
-use proc_macro2::{Span, TokenStream};
+use crate::common::{YaSerdeAttribute, YaSerdeField};
+use crate::de::build_default_value::build_default_value;
+use crate::de::build_root_check::build_root_check;
+use crate::de::expand_struct::read_element;
+use crate::de::{build_indexed_name_table, build_name_table, with_de_lifetime};
+use crate::ser::label::build_label_name;
+use proc_macro2::TokenStream;
 use quote::quote;
 use syn::{DataEnum, Fields, Ident};
 
 pub fn parse(
   data_enum: &DataEnum,
   name: &Ident,
+  generics: &syn::Generics,
   root: &str,
   root_attributes: &YaSerdeAttribute,
 ) -> TokenStream {
@@ -16,290 +22,295 @@ pub fn parse(
     true,
   );
 
-  let match_to_enum: TokenStream = data_enum
+  let root_check = build_root_check(root_attributes, name);
+
+  let variants: Vec<ReadVariant> = data_enum
     .variants
     .iter()
-    .filter_map(|variant| parse_variant(variant, name))
+    .flat_map(|variant| parse_variant(variant, name))
+    .collect();
+
+  // The fields of the named variants are read from the child elements of the enum, the first
+  // variant with one of them is built from them
+  let named_variants: Vec<(&Ident, Vec<YaSerdeField>)> = data_enum
+    .variants
+    .iter()
+    .filter(|variant| matches!(variant.fields, Fields::Named(_)))
+    .map(|variant| {
+      let fields = variant
+        .fields
+        .iter()
+        .map(|field| YaSerdeField::new(field.clone()))
+        .collect();
+      (&variant.ident, fields)
+    })
+    .collect();
+
+  let variables: TokenStream = named_variants
+    .iter()
+    .flat_map(|(label, fields)| {
+      fields.iter().map(move |field| {
+        let value_label = field.get_variant_value_label(label);
+        let field_type = field.get_type();
+
+        quote! {
+          #[allow(unused_mut, non_snake_case)]
+          let mut #value_label: ::std::option::Option<#field_type> = ::std::option::Option::None;
+        }
+      })
+    })
+    .collect();
+
+  let build_named_variants: TokenStream = named_variants
+    .iter()
+    .filter(|(_, fields)| !fields.is_empty())
+    .map(|(label, fields)| {
+      let value_labels: Vec<_> = fields
+        .iter()
+        .map(|field| field.get_variant_value_label(label))
+        .collect();
+      let variant_builder = fields.iter().map(|field| {
+        let field_label = field.label();
+        let value_label = field.get_variant_value_label(label);
+        let default_value = build_default_value(field);
+
+        quote! {
+          #field_label: match #value_label {
+            ::std::option::Option::Some(value) => value,
+            ::std::option::Option::None => #default_value,
+          },
+        }
+      });
+
+      quote! {
+        if enum_value.is_none() && (#(#value_labels.is_some())||*) {
+          enum_value = ::std::option::Option::Some(#name::#label { #(#variant_builder)* });
+        }
+      }
+    })
     .collect();
 
+  let match_to_enum: TokenStream = variants
+    .iter()
+    .zip(0usize..)
+    .map(|(ReadVariant { read_variant, .. }, index)| {
+      quote! {
+        ::yaserde::de::Visit::Child(#index) => {
+          #read_variant
+        }
+      }
+    })
+    .collect();
+
+  let unit_variants: Vec<&syn::Variant> = data_enum
+    .variants
+    .iter()
+    .filter(|variant| matches!(variant.fields, Fields::Unit))
+    .collect();
+
+  let match_text_to_enum: TokenStream = unit_variants
+    .iter()
+    .zip(0usize..)
+    .map(|(variant, index)| {
+      let label = &variant.ident;
+
+      quote! {
+        ::yaserde::de::Visit::Value(#index) => {
+          enum_value = ::std::option::Option::Some(#name::#label);
+        }
+      }
+    })
+    .collect();
+
+  let variant_names = build_name_table(
+    &variants
+      .iter()
+      .map(|variant| variant.element_name.clone())
+      .collect::<Vec<_>>(),
+  );
+  // The unit variants are written with their prefix, see `ser::expand_enum`
+  let value_names = build_indexed_name_table(
+    unit_variants
+      .iter()
+      .zip(0usize..)
+      .flat_map(|(variant, index)| {
+        let attributes = YaSerdeAttribute::parse(&variant.attrs);
+        let name = attributes.xml_element_name(&variant.ident);
+        let label = build_label_name(
+          &variant.ident,
+          &attributes,
+          &root_attributes.default_namespace,
+        );
+        let label = Some(label).filter(|label| *label != name);
+        std::iter::once((name, index)).chain(label.map(|label| (label, index)))
+      })
+      .collect(),
+  );
+  let flattened_variants = variants
+    .iter()
+    .zip(0usize..)
+    .filter_map(|(variant, index)| {
+      variant.flattened.as_ref().map(|flattened_type| {
+        quote!((#index, <#flattened_type as ::yaserde::YaDeserialize<'de>>::flattened))
+      })
+    });
+
   let flatten = root_attributes.flatten;
+  let deny_unknown_fields = root_attributes.deny_unknown_fields;
+
+  let info = quote! {
+    ::yaserde::de::EnumInfo {
+      name: ::std::stringify!(#name),
+      root: #root,
+      variants: #variant_names,
+      values: #value_names,
+      flattened: &[#(#flattened_variants),*],
+      flatten: #flatten,
+      deny_unknown_fields: #deny_unknown_fields,
+    }
+  };
+
+  let de_generics = with_de_lifetime(generics);
+  let (impl_generics, _, where_clause) = de_generics.split_for_impl();
+  let (_, ty_generics, _) = generics.split_for_impl();
 
   quote! {
-    impl ::yaserde::YaDeserialize for #name {
+    impl #impl_generics ::yaserde::YaDeserialize<'de> for #name #ty_generics #where_clause {
       #[allow(unused_variables)]
-      fn deserialize<R: ::std::io::Read>(
-        reader: &mut ::yaserde::de::Deserializer<R>,
-      ) -> ::std::result::Result<Self, ::std::string::String> {
-        let (named_element, enum_namespace) =
-          if let ::yaserde::__xml::reader::XmlEvent::StartElement{ name, .. } = reader.peek()?.to_owned() {
-            (name.local_name.to_owned(), name.namespace.clone())
-          } else {
-            (::std::string::String::from(#root), ::std::option::Option::None)
-          };
-
-        let start_depth = reader.depth();
-        ::yaserde::__derive_debug!("Enum {} @ {}: start to parse {:?}", stringify!(#name), start_depth, named_element);
-
-        #namespaces_matching
-
-        #[allow(unused_assignments, unused_mut)]
-        let mut enum_value = ::std::option::Option::None;
-
-        loop {
-          let event = reader.peek()?.to_owned();
-          ::yaserde::__derive_trace!("Enum {} @ {}: matching {:?}", stringify!(#name), start_depth, event);
-          match event {
-            ::yaserde::__xml::reader::XmlEvent::StartElement { ref name, ref attributes, .. } => {
-              match name.local_name.as_str() {
-                #match_to_enum
-                _named_element => {
-                  let _root = reader.next_event();
-                }
-              }
+      fn deserialize<__R: ::std::io::Read>(
+        reader: &mut ::yaserde::de::Deserializer<'de, __R>,
+      ) -> ::std::result::Result<Self, ::yaserde::de::Error> {
+        #root_check
+
+        if let ::yaserde::event::XmlEvent::StartElement { name, .. } = reader.peek()? {
+          let (named_element, enum_namespace) = (&name.local_name, &name.namespace);
+          #namespaces_matching
+        }
 
-              if let ::yaserde::__xml::reader::XmlEvent::Characters(content) = reader.peek()?.to_owned() {
-                match content.as_str() {
-                  #match_to_enum
-                  _ => {}
-                }
-              }
-            }
-            ::yaserde::__xml::reader::XmlEvent::EndElement { ref name } => {
-              if name.local_name == named_element && reader.depth() == start_depth + 1 {
-                break;
-              }
-              let _root = reader.next_event();
-            }
-            ::yaserde::__xml::reader::XmlEvent::Characters(ref text_content) => {
-              let _root = reader.next_event();
+        #[allow(unused_mut)]
+        let mut enum_value: ::std::option::Option<Self> = ::std::option::Option::None;
+        #variables
+
+        let named_element = ::yaserde::de::deserialize_enum(
+          reader,
+          &#info,
+          |reader, visit| {
+            match visit {
+              #match_to_enum
+              #match_text_to_enum
+              _ => {}
             }
-            ::yaserde::__xml::reader::XmlEvent::EndDocument => {
-              if #flatten {
-                break;
-              }
+            ::std::result::Result::Ok(())
+          },
+        )?;
 
-              return ::std::result::Result::Err(
-                ::std::format!("End of document, missing some content ?"),
-              );
-            }
-            event => {
-              return ::std::result::Result::Err(::std::format!("unknown event {:?}", event))
-            }
-          }
-        }
+        #build_named_variants
 
-        ::yaserde::__derive_debug!("Enum {} @ {}: success", stringify!(#name), start_depth);
         match enum_value {
           ::std::option::Option::Some(value) => ::std::result::Result::Ok(value),
-          ::std::option::Option::None => {
-            ::std::result::Result::Ok(<#name as ::std::default::Default>::default())
-          },
+          ::std::option::Option::None => ::std::result::Result::Err(
+            ::yaserde::de::ErrorKind::MissingField {
+              field: ::std::format!("variant of {}", ::std::stringify!(#name)),
+              container: named_element,
+            }
+            .into(),
+          ),
         }
       }
+
+      fn flattened() -> ::std::option::Option<::yaserde::de::Flattened> {
+        ::std::option::Option::Some(::yaserde::de::Flattened::Enum(&#info))
+      }
     }
   }
 }
 
-fn parse_variant(variant: &syn::Variant, name: &Ident) -> Option<TokenStream> {
-  let xml_element_name = YaSerdeAttribute::parse(&variant.attrs).xml_element_name(&variant.ident);
+/// A unit or newtype variant, or a field of a named variant.
+struct ReadVariant {
+  element_name: String,
+  /// Type of the flattened newtype variants, written with the element of the type.
+  flattened: Option<syn::Type>,
+  /// Code reading the variant.
+  read_variant: TokenStream,
+}
+
+fn parse_variant(variant: &syn::Variant, name: &Ident) -> Vec<ReadVariant> {
+  let variant_attributes = YaSerdeAttribute::parse(&variant.attrs);
+  let xml_element_name = variant_attributes.xml_element_name(&variant.ident);
+  let mut flattened = None;
 
   let variant_name = {
     let label = &variant.ident;
     quote! { #name::#label }
   };
 
-  match variant.fields {
+  let read_variant = match variant.fields {
     Fields::Unit => Some(quote! {
-      #xml_element_name => {
-        enum_value = ::std::option::Option::Some(#variant_name);
-      }
+      enum_value = ::std::option::Option::Some(#variant_name);
+      reader.next_event()?;
     }),
     Fields::Unnamed(ref fields) => {
-      let field_visitors = build_unnamed_field_visitors(fields);
-      let call_visitors = build_unnamed_visitor_calls(fields, &variant_name);
-
-      if fields.unnamed.len() > 1 {
-        unimplemented!("enum variant with multiple fields")
-      }
+      fields.unnamed.iter().take(1).next().map(|field| {
+        let field = YaSerdeField::new(field.clone());
+        let deserialize = field.deserialize_element();
+        if variant_attributes.flatten || field.is_flatten() {
+          flattened = Some(field.get_type().clone());
+        }
 
-      Some(
-        fields
-          .unnamed
-          .iter()
-          .take(1)
-          .map(|_field| {
-            quote! {
-              #xml_element_name => {
-                #field_visitors
-                #call_visitors
+        let set_value = match field.get_deserialize_with_function() {
+          Some(deserialize_with) => quote! {
+            let value = #deserialize_with(reader)?;
+            enum_value = ::std::option::Option::Some(#variant_name(value));
+          },
+          None => quote! {
+            let value = #deserialize;
+            #[allow(unreachable_patterns)]
+            match enum_value {
+              ::std::option::Option::Some(#variant_name(ref mut current)) => {
+                ::yaserde::YaDeserialize::merge(current, value);
+              }
+              _ => {
+                enum_value = ::std::option::Option::Some(#variant_name(value));
               }
             }
-          })
-          .collect(),
-      )
-    }
-    _ => None,
-  }
-}
-
-fn build_unnamed_field_visitors(fields: &syn::FieldsUnnamed) -> TokenStream {
-  fields
-    .unnamed
-    .iter()
-    .map(|field| YaSerdeField::new(field.clone()))
-    .enumerate()
-    .filter_map(|(idx, field)| {
-      let visitor_label = Ident::new(&format!("__Visitor_{}", idx), field.get_span());
+          },
+        };
 
-      let make_visitor = |visitor: &Ident, field_type: &TokenStream, fn_body: &TokenStream| {
         quote! {
-          #[allow(non_snake_case, non_camel_case_types)]
-          struct #visitor_label;
-          impl<'de> ::yaserde::Visitor<'de> for #visitor_label {
-            type Value = #field_type;
-
-            fn #visitor(
-              self,
-              v: &str,
-            ) -> ::std::result::Result<Self::Value, ::std::string::String> {
-              #fn_body
-            }
-          }
+          #set_value
+          // read EndElement
+          reader.next_event()?;
         }
-      };
-
-      let simple_type_visitor = |simple_type: Field| {
-        let visitor = simple_type.get_simple_type_visitor();
-        let field_type = simple_type.into();
-
-        make_visitor(
-          &visitor,
-          &field_type,
-          &quote! { ::std::result::Result::Ok(#field_type::from_str(v).unwrap()) },
-        )
-      };
-
-      match field.get_type() {
-        Field::FieldStruct { struct_name } => {
-          let struct_id: String = struct_name
-            .segments
-            .iter()
-            .map(|s| s.ident.to_string())
-            .collect();
-
-          Some(make_visitor(
-            &Ident::new("visit_str", Span::call_site()),
-            &quote! { #struct_name },
-            &quote! {
-              let content = "<".to_string() + #struct_id + ">" + v + "</" + #struct_id + ">";
-              let value: ::std::result::Result<#struct_name, ::std::string::String> =
-                ::yaserde::de::from_str(&content);
-              value
-            },
-          ))
-        }
-        Field::FieldOption { data_type } | Field::FieldVec { data_type } => match *data_type {
-          Field::FieldStruct { .. } => None,
-          simple_type => Some(simple_type_visitor(simple_type)),
-        },
-        simple_type => Some(simple_type_visitor(simple_type)),
-      }
-    })
-    .collect()
-}
-
-fn build_unnamed_visitor_calls(
-  fields: &syn::FieldsUnnamed,
-  variant_name: &TokenStream,
-) -> TokenStream {
-  fields
-    .unnamed
-    .iter()
-    .map(|field| YaSerdeField::new(field.clone()))
-    .enumerate()
-    .filter_map(|(idx, field)| {
-      let visitor_label = Ident::new(&format!("__Visitor_{}", idx), field.get_span());
-
-      let call_simple_type_visitor = |simple_type: Field, action| {
-        let visitor = simple_type.get_simple_type_visitor();
-        let field_type: TokenStream = simple_type.into();
-
-        let label_name = format!("field_{}", idx);
-
-        Some(quote! {
-          let visitor = #visitor_label{};
-
-          let result = reader.read_inner_value::<#field_type, _>(|reader| {
-            if let ::yaserde::__xml::reader::XmlEvent::EndElement { .. } = *reader.peek()? {
-              return visitor.#visitor("");
-            }
-
-            if let ::std::result::Result::Ok(::yaserde::__xml::reader::XmlEvent::Characters(s))
-              = reader.next_event()
-            {
-              visitor.#visitor(&s)
-            } else {
-              ::std::result::Result::Err(
-                ::std::format!("unable to parse content for {}", #label_name),
-              )
-            }
-          });
-
-          if let ::std::result::Result::Ok(value) = result {
-            #action
-          }
-        })
-      };
-
-      let call_struct_visitor = |struct_name, action| {
-        Some(quote! {
-          match <#struct_name as ::yaserde::YaDeserialize>::deserialize(reader) {
-            Ok(value) => {
-              #action;
-              let _root = reader.next_event();
-            },
-            Err(msg) => {
-              return Err(msg);
+      })
+    }
+    Fields::Named(ref fields) => {
+      return fields
+        .named
+        .iter()
+        .map(|field| {
+          let field = YaSerdeField::new(field.clone());
+          let read_value = read_element(&field, field.get_variant_value_label(&variant.ident));
+
+          ReadVariant {
+            element_name: field.renamed_label_without_namespace(),
+            flattened: None,
+            read_variant: quote! {
+              #read_value
+              // read EndElement
+              reader.next_event()?;
             },
           }
         })
-      };
-
-      let set_val = quote! {
-        enum_value = ::std::option::Option::Some(#variant_name(value))
-      };
-      let set_opt = quote! {
-        enum_value = ::std::option::Option::Some(#variant_name(::std::option::Option::Some(value)))
-      };
-      let set_vec = quote! {
-        match enum_value {
-          Some(ref mut v) => match v {
-            #variant_name(ref mut v) => v.push(value),
-            _ => {
-              return ::std::result::Result::Err(
-                ::std::string::String::from("Got sequence of different types"),
-              );
-            }
-          }
-          None => {
-            enum_value = ::std::option::Option::Some(#variant_name(vec![value]));
-          }
-        }
-      };
-
-      match field.get_type() {
-        Field::FieldStruct { struct_name } => call_struct_visitor(struct_name, set_val),
-        Field::FieldOption { data_type } => match *data_type {
-          Field::FieldStruct { struct_name } => call_struct_visitor(struct_name, set_opt),
-          simple_type => call_simple_type_visitor(simple_type, set_opt),
-        },
-        Field::FieldVec { data_type } => match *data_type {
-          Field::FieldStruct { struct_name } => call_struct_visitor(struct_name, set_vec),
-          simple_type => call_simple_type_visitor(simple_type, set_vec),
-        },
-
-        simple_type => call_simple_type_visitor(simple_type, set_val),
-      }
+        .collect();
+    }
+  };
+
+  read_variant
+    .map(|read_variant| ReadVariant {
+      element_name: xml_element_name,
+      flattened,
+      read_variant,
     })
+    .into_iter()
     .collect()
 }
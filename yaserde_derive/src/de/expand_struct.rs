@@ -1,13 +1,15 @@
-use crate::common::{Field, YaSerdeAttribute, YaSerdeField};
+use crate::common::{YaSerdeAttribute, YaSerdeField};
 use crate::de::build_default_value::build_default_value;
-use heck::ToUpperCamelCase;
-use proc_macro2::{Span, TokenStream};
+use crate::de::build_root_check::build_root_check;
+use crate::de::{build_name_table, with_de_lifetime};
+use proc_macro2::TokenStream;
 use quote::quote;
 use syn::{DataStruct, Ident};
 
 pub fn parse(
   data_struct: &DataStruct,
   name: &Ident,
+  generics: &syn::Generics,
   root: &str,
   root_attributes: &YaSerdeAttribute,
 ) -> TokenStream {
@@ -18,303 +20,219 @@ pub fn parse(
     true,
   );
 
+  let root_check = build_root_check(root_attributes, name);
+
   let variables: TokenStream = data_struct
     .fields
     .iter()
     .map(|field| YaSerdeField::new(field.clone()))
-    .filter_map(|field| match field.get_type() {
-      Field::FieldStruct { struct_name } => build_default_value(
-        &field,
-        Some(quote!(#struct_name)),
-        quote!(<#struct_name as ::std::default::Default>::default()),
-      ),
-      Field::FieldOption { .. } => {
-        build_default_value(&field, None, quote!(::std::option::Option::None))
-      }
-      Field::FieldVec { data_type } => match *data_type {
-        Field::FieldStruct { ref struct_name } => build_default_value(
-          &field,
-          Some(quote!(::std::vec::Vec<#struct_name>)),
-          quote!(::std::vec![]),
-        ),
-        Field::FieldOption { .. } | Field::FieldVec { .. } => {
-          unimplemented!();
-        }
-        simple_type => {
-          let type_token: TokenStream = simple_type.into();
-
-          build_default_value(
-            &field,
-            Some(quote!(::std::vec::Vec<#type_token>)),
-            quote!(::std::vec![]),
-          )
-        }
-      },
-      simple_type => {
-        let type_token: TokenStream = simple_type.into();
-        let value_builder = quote!(<#type_token as ::std::default::Default>::default());
+    .map(|field| {
+      let value_label = field.get_value_label();
+      let field_type = field.get_type();
 
-        build_default_value(&field, Some(type_token), value_builder)
+      quote! {
+        #[allow(unused_mut)]
+        let mut #value_label: ::std::option::Option<#field_type> = ::std::option::Option::None;
       }
     })
     .collect();
 
-  let field_visitors: TokenStream = data_struct
+  let flatten = root_attributes.flatten;
+
+  let child_fields: Vec<YaSerdeField> = data_struct
     .fields
     .iter()
     .map(|field| YaSerdeField::new(field.clone()))
-    .filter_map(|field| {
-      let struct_visitor = |struct_name: syn::Path| {
-        let struct_id: String = struct_name
-          .segments
-          .iter()
-          .map(|s| s.ident.to_string())
-          .collect();
-
-        let visitor_label = field.get_visitor_ident(Some(&struct_name));
+    .filter(|field| {
+      !field.is_attribute()
+        && !field.is_text_content()
+        && !field.is_flatten()
+        && !field.is_any()
+        && !field.is_any_attribute()
+    })
+    .collect();
 
-        Some(quote! {
-          #[allow(non_snake_case, non_camel_case_types)]
-          struct #visitor_label;
-          impl<'de> ::yaserde::Visitor<'de> for #visitor_label {
-            type Value = #struct_name;
-
-            fn visit_str(
-              self,
-              v: &str,
-            ) -> ::std::result::Result<Self::Value, ::std::string::String> {
-              let content = "<".to_string() + #struct_id + ">" + v + "</" + #struct_id + ">";
-              ::yaserde::de::from_str(&content)
-            }
-          }
-        })
-      };
+  let any_field = data_struct
+    .fields
+    .iter()
+    .map(|field| YaSerdeField::new(field.clone()))
+    .find(|field| field.is_any());
 
-      let simple_type_visitor = |simple_type: Field| {
-        let visitor = simple_type.get_simple_type_visitor();
-        let visitor_label = field.get_visitor_ident(None);
-        let field_type = TokenStream::from(simple_type);
-
-        let map_if_bool = if field_type.to_string() == "bool" {
-          quote!(match v {
-            "1" => "true",
-            "0" => "false",
-            _ => v,
-          })
-        } else {
-          quote!(v)
-        };
+  let call_any_visitor = any_field.as_ref().map(|field| {
+    let read_value = read_element(field, field.get_value_label());
+    quote! {
+      ::yaserde::de::Visit::Any => {
+        #read_value
+      }
+    }
+  });
 
+  let call_visitors: TokenStream = child_fields
+    .iter()
+    .zip(0usize..)
+    .map(|(field, index)| {
+      let namespaces_matching = field.get_namespace_matching(
+        root_attributes,
+        quote!(name.namespace.as_ref()),
+        quote!(name.local_name.as_str()),
+      );
+
+      let check_namespace = if namespaces_matching.is_empty() {
+        None
+      } else {
         Some(quote! {
-          #[allow(non_snake_case, non_camel_case_types)]
-          struct #visitor_label;
-          impl<'de> ::yaserde::Visitor<'de> for #visitor_label {
-            type Value = #field_type;
-
-            fn #visitor(
-              self,
-              v: &str,
-            ) -> ::std::result::Result<Self::Value, ::std::string::String> {
-              #field_type::from_str(#map_if_bool).map_err(|e| e.to_string())
-            }
+          if let ::yaserde::event::XmlEvent::StartElement { name, .. } = reader.peek()? {
+            #namespaces_matching
           }
         })
       };
 
-      match field.get_type() {
-        Field::FieldStruct { struct_name } => struct_visitor(struct_name),
-        Field::FieldOption { data_type } => match *data_type {
-          Field::FieldStruct { struct_name } => struct_visitor(struct_name),
-          Field::FieldOption { .. } | Field::FieldVec { .. } => None,
-          simple_type => simple_type_visitor(simple_type),
-        },
-        Field::FieldVec { data_type } => match *data_type {
-          Field::FieldStruct { struct_name } => struct_visitor(struct_name),
-          Field::FieldOption { .. } | Field::FieldVec { .. } => None,
-          simple_type => simple_type_visitor(simple_type),
-        },
-        simple_type => simple_type_visitor(simple_type),
+      let read_value = read_element(field, field.get_value_label());
+
+      quote! {
+        ::yaserde::de::Visit::Child(#index) => {
+          #check_namespace
+          #read_value
+        }
       }
     })
     .collect();
 
-  let call_visitors: TokenStream = data_struct
+  let children = build_name_table(
+    &child_fields
+      .iter()
+      .map(|field| field.renamed_label_without_namespace())
+      .collect::<Vec<_>>(),
+  );
+  // The type of a field read with a function doesn't have to implement YaDeserialize
+  let repeated = child_fields.iter().map(|field| {
+    let field_type = field.get_type();
+    match field.get_deserialize_with_function() {
+      Some(_) => quote!(false),
+      None => quote!(<#field_type as ::yaserde::YaDeserialize<'de>>::REPEATED),
+    }
+  });
+
+  let flatten_fields: Vec<YaSerdeField> = data_struct
     .fields
     .iter()
     .map(|field| YaSerdeField::new(field.clone()))
-    .filter(|field| !field.is_attribute() || !field.is_flatten())
-    .filter_map(|field| {
-      let value_label = field.get_value_label();
-      let label_name = field.renamed_label_without_namespace();
+    .filter(|field| !field.is_attribute() && field.is_flatten())
+    .collect();
 
-      let visit_struct = |struct_name: syn::Path, action: TokenStream| {
-        Some(quote! {
-          #label_name => {
-            if depth == 0 {
-              // Don't count current struct's StartElement as substruct's StartElement
-              let _root = reader.next_event();
-            }
-            if let Ok(::yaserde::__xml::reader::XmlEvent::StartElement { .. }) = reader.peek() {
-              // If substruct's start element found then deserialize substruct
-              let value = <#struct_name as ::yaserde::YaDeserialize>::deserialize(reader)?;
-              #value_label #action;
-              // read EndElement
-              let _event = reader.next_event()?;
-            }
-          }
-        })
-      };
+  // Without flattened fields, the unknown elements are collected while the struct is read
+  let call_unclaimed_visitor = any_field
+    .as_ref()
+    .filter(|_| !flatten_fields.is_empty())
+    .map(|field| {
+      let value_label = field.get_value_label();
+      let flattened_types = flatten_fields.iter().map(|field| field.get_type());
 
-      let visit_simple = |simple_type: Field, action: TokenStream| {
-        let field_visitor = simple_type.get_simple_type_visitor();
-        let field_type = TokenStream::from(simple_type);
-        build_call_visitor(
-          &field_type,
-          &field_visitor,
-          &action,
-          &field,
-          root_attributes,
-        )
-      };
+      quote! {
+        #value_label = reader.replay_unclaimed(
+          ::std::clone::Clone::clone(&unused_events),
+          &[#(<#flattened_types as ::yaserde::YaDeserialize<'de>>::flattened()),*],
+        )?;
+      }
+    });
 
-      let visit_sub = |sub_type: Box<Field>, action: TokenStream| match *sub_type {
-        Field::FieldOption { .. } | Field::FieldVec { .. } => unimplemented!(),
-        Field::FieldStruct { struct_name } => visit_struct(struct_name, action),
-        simple_type => visit_simple(simple_type, action),
+  // Flattened fields are read from the events of the struct which no other field has read
+  let call_flatten_visitors: TokenStream = flatten_fields
+    .iter()
+    .enumerate()
+    .map(|(index, field)| {
+      let value_label = field.get_value_label();
+      let events = if index + 1 == flatten_fields.len() {
+        quote!(unused_events)
+      } else {
+        quote!(::std::clone::Clone::clone(&unused_events))
       };
+      let value = field.deserialize_flattened(events);
 
-      match field.get_type() {
-        Field::FieldStruct { struct_name } => visit_struct(struct_name, quote! { = value }),
-        Field::FieldOption { data_type } => {
-          visit_sub(data_type, quote! { = ::std::option::Option::Some(value) })
-        }
-        Field::FieldVec { data_type } => visit_sub(data_type, quote! { .push(value) }),
-        simple_type => visit_simple(simple_type, quote! { = value }),
+      quote! {
+        #value_label = ::std::option::Option::Some(#value);
       }
     })
     .collect();
 
-  let call_flatten_visitors: TokenStream = data_struct
+  let other_attributes_loading = data_struct
     .fields
     .iter()
     .map(|field| YaSerdeField::new(field.clone()))
-    .filter(|field| !field.is_attribute() && field.is_flatten())
-    .filter_map(|field| {
+    .find(|field| field.is_any_attribute())
+    .map(|field| {
       let value_label = field.get_value_label();
 
-      match field.get_type() {
-        Field::FieldStruct { .. } => Some(quote! {
-          #value_label = ::yaserde::de::from_str(&unused_xml_elements)?;
-        }),
-        Field::FieldOption { data_type } => match *data_type {
-          Field::FieldStruct { .. } => Some(quote! {
-            #value_label = ::yaserde::de::from_str(&unused_xml_elements).ok();
-          }),
-          field_type => unimplemented!(r#""flatten" is not implemented for {:?}"#, field_type),
-        },
-        field_type => unimplemented!(r#""flatten" is not implemented for {:?}"#, field_type),
+      quote! {
+        ::yaserde::de::Visit::OtherAttribute(attribute) => {
+          ::std::iter::Extend::extend(
+            #value_label.get_or_insert_with(::std::default::Default::default),
+            ::std::iter::once((
+              ::std::clone::Clone::clone(&attribute.name),
+              ::std::clone::Clone::clone(&attribute.value),
+            )),
+          );
+        }
       }
-    })
-    .collect();
+    });
+  let has_any_attribute = other_attributes_loading.is_some();
 
-  let attributes_loading: TokenStream = data_struct
+  let attribute_fields: Vec<YaSerdeField> = data_struct
     .fields
     .iter()
     .map(|field| YaSerdeField::new(field.clone()))
     .filter(|field| field.is_attribute())
-    .filter_map(|field| {
-      let label = field.get_value_label();
-      let label_name = field.renamed_label_without_namespace();
-      let visitor_label = build_visitor_ident(&label_name, field.get_span(), None);
-
-      let visit = |action: &TokenStream, visitor: &Ident, visitor_label: &Ident| {
-        Some(quote! {
-          for attr in attributes {
-            if attr.name.local_name == #label_name {
-              let visitor = #visitor_label{};
-              let value = visitor.#visitor(&attr.value)?;
-              #label #action;
-            }
-          }
-        })
-      };
-
-      let visit_string = || {
-        Some(quote! {
-          for attr in attributes {
-            if attr.name.local_name == #label_name {
-              #label = attr.value.to_owned();
-            }
-          }
-        })
-      };
-
-      let visit_struct = |struct_name: syn::Path, action: TokenStream| {
-        visit(
-          &action,
-          &Ident::new("visit_str", Span::call_site()),
-          &build_visitor_ident(&label_name, field.get_span(), Some(&struct_name)),
-        )
-      };
-
-      let visit_simple = |simple_type: Field, action: TokenStream| {
-        visit(
-          &action,
-          &simple_type.get_simple_type_visitor(),
-          &visitor_label,
-        )
-      };
+    .collect();
 
-      let visit_sub = |sub_type: Box<Field>, action: TokenStream| match *sub_type {
-        Field::FieldOption { .. } | Field::FieldVec { .. } => unimplemented!(),
-        Field::FieldStruct { struct_name } => visit_struct(struct_name, action),
-        simple_type => visit_simple(simple_type, action),
-      };
+  let attributes_loading: TokenStream = attribute_fields
+    .iter()
+    .zip(0usize..)
+    .map(|(field, index)| {
+      let value_label = field.get_value_label();
+      let value = field.deserialize_value(quote!(value));
 
-      match field.get_type() {
-        Field::FieldString => visit_string(),
-        Field::FieldOption { data_type } => {
-          visit_sub(data_type, quote! { = ::std::option::Option::Some(value) })
+      quote! {
+        ::yaserde::de::Visit::Attribute(#index, value) => {
+          #value_label = ::std::option::Option::Some(#value);
         }
-        Field::FieldVec { .. } => unimplemented!(),
-        Field::FieldStruct { struct_name } => visit_struct(struct_name, quote! { = value }),
-        simple_type => visit_simple(simple_type, quote! { = value }),
       }
     })
     .collect();
 
+  let attributes = build_name_table(
+    &attribute_fields
+      .iter()
+      .map(|field| field.renamed_label_without_namespace())
+      .collect::<Vec<_>>(),
+  );
+
+  let attribute_namespaces =
+    attribute_fields
+      .iter()
+      .map(|field| match field.get_namespace(root_attributes) {
+        Some(namespace) => quote!(::std::option::Option::Some(#namespace)),
+        None => quote!(::std::option::Option::None),
+      });
+
   let set_text: TokenStream = data_struct
     .fields
     .iter()
     .map(|field| YaSerdeField::new(field.clone()))
-    .filter_map(|field| {
-      let label = field.get_value_label();
-
-      let set_text = |action: &TokenStream| {
-        if field.is_text_content() {
-          Some(quote! { #label = #action; })
-        } else {
-          None
-        }
-      };
+    .filter(|field| field.is_text_content())
+    .map(|field| {
+      let value_label = field.get_value_label();
+      let value = field.deserialize_value(quote!(text_content));
 
-      match field.get_type() {
-        Field::FieldString => set_text(&quote! { text_content.to_owned() }),
-        Field::FieldOption { data_type } => match *data_type {
-          Field::FieldString => set_text(
-            &quote! { if text_content.is_empty() { None } else { Some(text_content.to_owned()) }},
-          ),
-          _ => None,
-        },
-        Field::FieldStruct { .. } | Field::FieldVec { .. } => None,
-        simple_type => {
-          let type_token = TokenStream::from(simple_type);
-          set_text(&quote! { #type_token::from_str(text_content).unwrap() })
-        }
+      quote! {
+        #value_label = ::std::option::Option::Some(#value);
       }
     })
     .collect();
 
+  let deny_unknown_fields = root_attributes.deny_unknown_fields;
+  let has_text = !set_text.is_empty();
+  let has_any = any_field.is_some();
+
   let struct_builder: TokenStream = data_struct
     .fields
     .iter()
@@ -322,198 +240,119 @@ pub fn parse(
     .map(|field| {
       let label = &field.label();
       let value_label = field.get_value_label();
+      let default_value = build_default_value(&field);
 
-      quote! { #label: #value_label, }
+      quote! {
+        #label: match #value_label {
+          ::std::option::Option::Some(value) => value,
+          ::std::option::Option::None => #default_value,
+        },
+      }
     })
     .collect();
 
-  let (init_unused, write_unused, visit_unused) = if call_flatten_visitors.is_empty() {
-    (None, None, None)
+  let (init_unused, unused) = if flatten_fields.is_empty() {
+    (None, quote!(::std::option::Option::None))
   } else {
-    build_code_for_unused_xml_events(&call_flatten_visitors)
+    (
+      Some(quote!(let mut unused_events = ::yaserde::de::EventBuffer::new();)),
+      quote!(::std::option::Option::Some(&mut unused_events)),
+    )
   };
 
-  let flatten = root_attributes.flatten;
+  let flattened_types = flatten_fields.iter().map(|field| field.get_type());
+  let info = quote! {
+    ::yaserde::de::StructInfo {
+      name: ::std::stringify!(#name),
+      root: #root,
+      children: #children,
+      attributes: #attributes,
+      attribute_namespaces: &[#(#attribute_namespaces),*],
+      text: #has_text,
+      any: #has_any,
+      any_attribute: #has_any_attribute,
+      flattened: &[#(<#flattened_types as ::yaserde::YaDeserialize<'de>>::flattened),*],
+      flatten: #flatten,
+      deny_unknown_fields: #deny_unknown_fields,
+    }
+  };
+
+  let de_generics = with_de_lifetime(generics);
+  let (impl_generics, _, where_clause) = de_generics.split_for_impl();
+  let (_, ty_generics, _) = generics.split_for_impl();
 
   quote! {
-    impl ::yaserde::YaDeserialize for #name {
+    impl #impl_generics ::yaserde::YaDeserialize<'de> for #name #ty_generics #where_clause {
       #[allow(unused_variables)]
-      fn deserialize<R: ::std::io::Read>(
-        reader: &mut ::yaserde::de::Deserializer<R>,
-      ) -> ::std::result::Result<Self, ::std::string::String> {
-        let (named_element, struct_namespace) =
-          if let ::yaserde::__xml::reader::XmlEvent::StartElement { name, .. } = reader.peek()?.to_owned() {
-            (name.local_name.to_owned(), name.namespace.clone())
-          } else {
-            (::std::string::String::from(#root), ::std::option::Option::None)
-          };
-        let start_depth = reader.depth();
-        ::yaserde::__derive_debug!("Struct {} @ {}: start to parse {:?}", stringify!(#name), start_depth,
-               named_element);
+      fn deserialize<__R: ::std::io::Read>(
+        reader: &mut ::yaserde::de::Deserializer<'de, __R>,
+      ) -> ::std::result::Result<Self, ::yaserde::de::Error> {
+        #root_check
 
         if reader.depth() == 0 {
-          #namespaces_matching
+          if let ::yaserde::event::XmlEvent::StartElement { name, .. } = reader.peek()? {
+            let (named_element, struct_namespace) = (&name.local_name, &name.namespace);
+            #namespaces_matching
+          }
         }
 
         #variables
-        #field_visitors
         #init_unused
 
-        let mut depth = 0;
-
-        loop {
-          let event = reader.peek()?.to_owned();
-          ::yaserde::__derive_trace!(
-            "Struct {} @ {}: matching {:?}",
-            stringify!(#name), start_depth, event,
-          );
-          match event {
-            ::yaserde::__xml::reader::XmlEvent::StartElement{ref name, ref attributes, ..} => {
-              if depth == 0 && name.local_name == #root {
-                // Consume root element. We must do this first. In the case it shares a name with a child element, we don't
-                // want to prematurely match the child element below.
-                let event = reader.next_event()?;
-                #write_unused
-              } else {
-                match name.local_name.as_str() {
-                  #call_visitors
-                  _ => {
-                    let event = reader.next_event()?;
-                    #write_unused
-
-                    if depth > 0 { // Don't skip root element
-                      reader.skip_element(|event| {
-                        #write_unused
-                      })?;
-                    }
-                  }
-                }
+        let named_element = ::yaserde::de::deserialize_struct(
+          reader,
+          &#info,
+          #unused,
+          |reader, visit| {
+            match visit {
+              #call_visitors
+              #call_any_visitor
+              #attributes_loading
+              #other_attributes_loading
+              ::yaserde::de::Visit::Text(text_content) => {
+                #set_text
               }
-              if depth == 0 { // Look for attributes only at element start
-                #attributes_loading
-              }
-              depth += 1;
+              _ => {}
             }
-            ::yaserde::__xml::reader::XmlEvent::EndElement { ref name } => {
-              if name.local_name == named_element && reader.depth() == start_depth + 1 {
-                #write_unused
-                break;
-              }
-              let event = reader.next_event()?;
-              #write_unused
-              depth -= 1;
-            }
-            ::yaserde::__xml::reader::XmlEvent::EndDocument => {
-              if #flatten {
-                break;
-              }
-            }
-            ::yaserde::__xml::reader::XmlEvent::Characters(ref text_content) => {
-              #set_text
-              let event = reader.next_event()?;
-              #write_unused
-            }
-            event => {
-              return ::std::result::Result::Err(::std::format!("unknown event {:?}", event));
-            }
-          }
-        }
+            ::std::result::Result::Ok(())
+          },
+        )?;
 
-        #visit_unused
+        #call_unclaimed_visitor
+        #call_flatten_visitors
 
-        ::yaserde::__derive_debug!("Struct {} @ {}: success", stringify!(#name), start_depth);
         ::std::result::Result::Ok(#name{#struct_builder})
       }
-    }
-  }
-}
-
-fn build_call_visitor(
-  field_type: &TokenStream,
-  visitor: &Ident,
-  action: &TokenStream,
-  field: &YaSerdeField,
-  root_attributes: &YaSerdeAttribute,
-) -> Option<TokenStream> {
-  let value_label = field.get_value_label();
-  let label_name = field.renamed_label_without_namespace();
-  let visitor_label = build_visitor_ident(&label_name, field.get_span(), None);
-
-  let namespaces_matching = field.get_namespace_matching(
-    root_attributes,
-    quote!(name.namespace.as_ref()),
-    quote!(name.local_name.as_str()),
-  );
-
-  Some(quote! {
-    #label_name => {
-      let visitor = #visitor_label{};
-
-      #namespaces_matching
 
-      let result = reader.read_inner_value::<#field_type, _>(|reader| {
-        if let ::std::result::Result::Ok(::yaserde::__xml::reader::XmlEvent::Characters(s)) = reader.peek() {
-          let val = visitor.#visitor(&s);
-          let _event = reader.next_event()?;
-          val
-        } else {
-          ::std::result::Result::Err(::std::format!("unable to parse content for {}", #label_name))
-        }
-      });
-
-      if let ::std::result::Result::Ok(value) = result {
-        #value_label#action
+      fn flattened() -> ::std::option::Option<::yaserde::de::Flattened> {
+        ::std::option::Option::Some(::yaserde::de::Flattened::Struct {
+          info: &#info,
+          repeated: &[#(#repeated),*],
+        })
       }
     }
-  })
+  }
 }
 
-fn build_visitor_ident(label: &str, span: Span, struct_name: Option<&syn::Path>) -> Ident {
-  let struct_id = struct_name.map_or_else(
-    || "".to_string(),
-    |struct_name| {
-      struct_name
-        .segments
-        .iter()
-        .map(|s| s.ident.to_string())
-        .collect()
-    },
-  );
+/// Code reading `field` from the child element at the reader position, merged into the value
+/// read from its previous elements in `value_label`.
+pub fn read_element(field: &YaSerdeField, value_label: Option<Ident>) -> TokenStream {
+  let deserialize = field.deserialize_element();
 
-  Ident::new(
-    &format!(
-      "__Visitor_{}_{}",
-      label.replace('.', "_").to_upper_camel_case(),
-      struct_id
-    ),
-    span,
-  )
-}
-
-fn build_code_for_unused_xml_events(
-  call_flatten_visitors: &TokenStream,
-) -> (
-  Option<TokenStream>,
-  Option<TokenStream>,
-  Option<TokenStream>,
-) {
-  (
-    Some(quote! {
-      let mut buf = ::std::vec![];
-      let mut writer = ::std::option::Option::Some(::yaserde::__xml::writer::EventWriter::new(&mut buf));
-    }),
-    Some(quote! {
-      if let ::std::option::Option::Some(ref mut w) = writer {
-        if w.write(event.as_writer_event().unwrap()).is_err() {
-          writer = ::std::option::Option::None;
+  match field.get_deserialize_with_function() {
+    Some(deserialize_with) => quote! {
+      #value_label = ::std::option::Option::Some(#deserialize_with(reader)?);
+    },
+    None => quote! {
+      let value = #deserialize;
+      match #value_label {
+        ::std::option::Option::Some(ref mut current) => {
+          ::yaserde::YaDeserialize::merge(current, value);
+        }
+        ::std::option::Option::None => {
+          #value_label = ::std::option::Option::Some(value);
         }
       }
-    }),
-    Some(quote! {
-      if writer.is_some() {
-        let unused_xml_elements = ::std::string::String::from_utf8(buf).unwrap();
-        #call_flatten_visitors
-      }
-    }),
-  )
+    },
+  }
 }
@@ -1,12 +1,13 @@
 pub mod build_default_value;
+pub mod build_root_check;
 pub mod expand_enum;
 pub mod expand_struct;
 
-use crate::common::YaSerdeAttribute;
-use proc_macro2::{Ident, TokenStream};
+use crate::common::{bound, YaSerdeAttribute};
+use proc_macro2::TokenStream;
 use quote::quote;
 
-pub fn expand_derive_deserialize(ast: &syn::DeriveInput) -> Result<TokenStream, String> {
+pub fn expand_derive_deserialize(ast: &syn::DeriveInput) -> syn::Result<TokenStream> {
   let name = &ast.ident;
   let attrs = &ast.attrs;
   let data = &ast.data;
@@ -16,28 +17,147 @@ pub fn expand_derive_deserialize(ast: &syn::DeriveInput) -> Result<TokenStream,
   let root_name = format!(
     "{}{}",
     root_attributes.prefix_namespace(),
-    root_attributes.xml_element_name(name)
+    root_attributes.document_element_name(name)
   );
 
+  if let Some(lifetime) = ast
+    .generics
+    .lifetimes()
+    .find(|param| param.lifetime.ident == "de")
+  {
+    return Err(syn::Error::new_spanned(
+      lifetime,
+      "the lifetime 'de is reserved for the document by YaDeserialize, rename it",
+    ));
+  }
+
+  if root_attributes.display_fromstr {
+    return Ok(expand_from_str(ast));
+  }
+
+  let mut generics = bound::with_bounds(
+    ast,
+    &root_attributes,
+    quote!(::yaserde::YaDeserialize<'de>),
+    quote!(::yaserde::YaDeserializeValue<'de>),
+    |field| field.get_deserialize_with_function().is_some(),
+  )?;
+
+  // Missing fields marked with `default` fall back to `Default::default()`
+  let default_types: Vec<syn::Type> = match *data {
+    syn::Data::Struct(_) => bound::all_fields(data)
+      .into_iter()
+      .filter(|field| field.has_default_trait())
+      .map(|field| field.get_type().clone())
+      .filter(|field_type| bound::is_generic(field_type, &ast.generics))
+      .collect(),
+    _ => vec![],
+  };
+  let where_clause = generics.make_where_clause();
+  for default_type in default_types {
+    where_clause
+      .predicates
+      .push(syn::parse_quote!(#default_type: ::std::default::Default));
+  }
   let impl_block = match *data {
     syn::Data::Struct(ref data_struct) => {
-      expand_struct::parse(data_struct, name, &root_name, &root_attributes)
+      expand_struct::parse(data_struct, name, &generics, &root_name, &root_attributes)
     }
     syn::Data::Enum(ref data_enum) => {
-      expand_enum::parse(data_enum, name, &root_name, &root_attributes)
+      expand_enum::parse(data_enum, name, &generics, &root_name, &root_attributes)
     }
-    syn::Data::Union(ref _data_union) => unimplemented!(),
+    syn::Data::Union(_) => unreachable!("unions are rejected by the input checks"),
   };
 
-  let dummy_const = Ident::new(&format!("_IMPL_YA_DESERIALIZE_FOR_{}", name), name.span());
+  let type_name = name.to_string();
+
+  // The value is read from a document of its own, which it cannot borrow from
+  let mut value_generics = with_de_lifetime(&generics);
+  value_generics
+    .make_where_clause()
+    .predicates
+    .push(syn::parse_quote!(Self: ::yaserde::YaDeserializeOwned));
+  let (impl_generics, _, where_clause) = value_generics.split_for_impl();
+  let (_, ty_generics, _) = generics.split_for_impl();
 
   Ok(quote! {
     #[allow(non_upper_case_globals, unused_attributes, unused_qualifications)]
-    const #dummy_const: () = {
-      use ::std::str::FromStr as _;
-      use ::yaserde::Visitor as _;
-
+    const _: () = {
       #impl_block
+
+      impl #impl_generics ::yaserde::YaDeserializeValue<'de> for #name #ty_generics #where_clause {
+        fn deserialize_value(value: &str) -> ::std::result::Result<Self, ::yaserde::de::Error> {
+          ::yaserde::de::from_str_content(#type_name, value)
+        }
+      }
     };
   })
 }
+
+/// Read the whole value with `FromStr`, for containers marked with `display_fromstr`.
+fn expand_from_str(ast: &syn::DeriveInput) -> TokenStream {
+  let name = &ast.ident;
+  let (_, ty_generics, _) = ast.generics.split_for_impl();
+
+  let mut generics = ast.generics.clone();
+  let where_clause = generics.make_where_clause();
+  where_clause
+    .predicates
+    .push(syn::parse_quote!(#name #ty_generics: ::std::str::FromStr));
+  where_clause.predicates.push(syn::parse_quote!(
+    <#name #ty_generics as ::std::str::FromStr>::Err: ::std::fmt::Display
+  ));
+  let de_generics = with_de_lifetime(&generics);
+  let (impl_generics, _, where_clause) = de_generics.split_for_impl();
+  let (_, ty_generics, _) = generics.split_for_impl();
+
+  quote! {
+    #[allow(non_upper_case_globals, unused_attributes, unused_qualifications)]
+    const _: () = {
+      impl #impl_generics ::yaserde::YaDeserialize<'de> for #name #ty_generics #where_clause {
+        fn deserialize<__R: ::std::io::Read>(
+          reader: &mut ::yaserde::de::Deserializer<'de, __R>,
+        ) -> ::std::result::Result<Self, ::yaserde::de::Error> {
+          ::yaserde::de::deserialize_from_str(reader)
+        }
+      }
+
+      impl #impl_generics ::yaserde::YaDeserializeValue<'de> for #name #ty_generics #where_clause {
+        fn deserialize_value(value: &str) -> ::std::result::Result<Self, ::yaserde::de::Error> {
+          ::yaserde::de::from_str_value(value)
+        }
+      }
+    };
+  }
+}
+
+/// Add the lifetime `'de` of the document to `generics`, outliving the lifetimes of the
+/// container so that its fields can borrow from the document.
+pub fn with_de_lifetime(generics: &syn::Generics) -> syn::Generics {
+  let mut de_lifetime: syn::LifetimeDef = syn::parse_quote!('de);
+  de_lifetime.bounds = generics
+    .lifetimes()
+    .map(|param| param.lifetime.clone())
+    .collect();
+
+  let mut generics = generics.clone();
+  generics
+    .params
+    .insert(0, syn::GenericParam::Lifetime(de_lifetime));
+  generics
+}
+
+/// Table of names with their index, sorted by name for the lookups of
+/// `yaserde::de::deserialize_struct` and `yaserde::de::deserialize_enum`.
+pub fn build_name_table(names: &[String]) -> TokenStream {
+  build_indexed_name_table(names.iter().cloned().zip(0..).collect())
+}
+
+/// Same as [`build_name_table`], with the index of each name, e.g. to give several names to an
+/// index.
+pub fn build_indexed_name_table(mut entries: Vec<(String, usize)>) -> TokenStream {
+  entries.sort();
+
+  let (names, indexes): (Vec<_>, Vec<_>) = entries.into_iter().unzip();
+  quote!(&[#((#names, #indexes)),*])
+}
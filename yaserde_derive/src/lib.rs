@@ -8,21 +8,29 @@ mod de;
 mod ser;
 
 use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
 
 #[proc_macro_derive(YaDeserialize, attributes(yaserde))]
 pub fn derive_deserialize(input: TokenStream) -> TokenStream {
-  let ast = syn::parse(input).unwrap();
-  match de::expand_derive_deserialize(&ast) {
-    Ok(expanded) => expanded.into(),
-    Err(msg) => panic!("{}", msg),
-  }
+  let ast = parse_macro_input!(input as DeriveInput);
+  expand(ast, de::expand_derive_deserialize)
 }
 
 #[proc_macro_derive(YaSerialize, attributes(yaserde))]
 pub fn derive_serialize(input: TokenStream) -> TokenStream {
-  let ast = syn::parse(input).unwrap();
-  match ser::expand_derive_serialize(&ast) {
-    Ok(expanded) => expanded.into(),
-    Err(msg) => panic!("{}", msg),
-  }
+  let ast = parse_macro_input!(input as DeriveInput);
+  expand(ast, ser::expand_derive_serialize)
+}
+
+fn expand(
+  mut ast: DeriveInput,
+  expand: fn(&DeriveInput) -> syn::Result<proc_macro2::TokenStream>,
+) -> TokenStream {
+  common::check::check_input(&ast)
+    .and_then(|()| {
+      common::rename::apply_rename_all(&mut ast);
+      expand(&ast)
+    })
+    .unwrap_or_else(|error| error.to_compile_error())
+    .into()
 }
@@ -1,4 +1,4 @@
-use crate::common::{Field, YaSerdeAttribute, YaSerdeField};
+use crate::common::{YaSerdeAttribute, YaSerdeField};
 use crate::ser::{implement_serializer::implement_serializer, label::build_label_name};
 use proc_macro2::TokenStream;
 use quote::quote;
@@ -9,74 +9,40 @@ use syn::Ident;
 pub fn serialize(
   data_enum: &DataEnum,
   name: &Ident,
+  generics: &syn::Generics,
   root: &str,
   root_attributes: &YaSerdeAttribute,
 ) -> TokenStream {
   let inner_enum_inspector = inner_enum_inspector(data_enum, name, root_attributes);
 
-  let get_id = |field: &YaSerdeField| {
-    field
-      .label()
-      .unwrap_or(field.get_type().get_simple_type_visitor())
-  };
-
   let variant_matches: TokenStream = data_enum
     .variants
     .iter()
     .map(|variant| -> TokenStream {
-      let _attrs = crate::common::YaSerdeAttribute::parse(&variant.attrs);
+      let label = &variant.ident;
 
-      let all_fields = variant
+      variant
         .fields
         .iter()
-        .map(|field| YaSerdeField::new(field.clone()));
-
-      let attribute_fields: Vec<_> = all_fields
-        .clone()
-        .into_iter()
-        .filter(|field| {
-          field.is_attribute()
-            || (field.is_flatten() && matches!(field.get_type(), Field::FieldStruct { .. }))
-        })
-        .collect();
-
-      attribute_fields
-        .iter()
+        .map(|field| YaSerdeField::new(field.clone()))
+        .filter(|field| root_attributes.flatten && field.is_flatten())
         .map(|field| {
-          let label = variant.ident.clone();
-          let var = get_id(field);
-          let name = name.clone();
-
-          let destructure = if field.get_value_label().is_some() {
-            quote! {{#var, ..}}
-          } else {
-            quote! {(#var, ..)}
+          let destructure = match field.label() {
+            Some(field_label) => quote! {{ #field_label: ref item, .. }},
+            None => quote! {(ref item, ..)},
           };
 
-          if field.is_attribute() {
-            quote! { #name::#label { .. } => { }, }
-          } else {
-            match field.get_type() {
-              Field::FieldStruct { .. } => {
-                if root_attributes.flatten {
-                  quote! {
-                    match self {
-                      #name::#label #destructure => {
-                        let (attributes, namespace) = #var.serialize_attributes(
-                          child_attributes,
-                          child_attributes_namespace,
-                        )?;
-                        child_attributes_namespace.extend(&namespace);
-                        child_attributes.extend(attributes);
-                      },
-                      _ => {}
-                    }
-                  }
-                } else {
-                  quote! {}
-                }
-              }
-              _ => quote! { #name::#label { .. } => { },},
+          quote! {
+            match self {
+              #name::#label #destructure => {
+                let (attributes, namespace) = item.serialize_attributes(
+                  child_attributes,
+                  child_attributes_namespace,
+                )?;
+                child_attributes_namespace.extend(&namespace);
+                child_attributes.extend(attributes);
+              },
+              _ => {}
             }
           }
         })
@@ -86,6 +52,7 @@ pub fn serialize(
 
   implement_serializer(
     name,
+    generics,
     root,
     root_attributes,
     quote!(#variant_matches),
@@ -108,12 +75,12 @@ fn inner_enum_inspector(
 
       let label = &variant.ident;
       let label_name = build_label_name(label, &variant_attrs, &root_attributes.default_namespace);
-      
+
       match variant.fields {
         Fields::Unit => quote! {
           &#name::#label => {
-            let data_event = ::yaserde::__xml::writer::XmlEvent::characters(#label_name);
-            writer.write(data_event).map_err(|e| e.to_string())?;
+            let data_event = ::yaserde::event::XmlEvent::characters(#label_name);
+            writer.write(data_event)?;
           }
         },
         Fields::Named(ref fields) => {
@@ -122,76 +89,37 @@ fn inner_enum_inspector(
             .iter()
             .map(|field| YaSerdeField::new(field.clone()))
             .filter(|field| !field.is_attribute())
-            .filter_map(|field| {
+            .map(|field| {
               let field_label = field.label();
 
-              if field.is_text_content() {
-                return Some(quote!(
-                  let data_event = ::yaserde::__xml::writer::XmlEvent::characters(&self.#field_label);
-                  writer.write(data_event).map_err(|e| e.to_string())?;
-                ));
-              }
+              let write = if field.is_text_content() {
+                let value = field.serialize_value(quote!(#field_label));
 
-              let field_label_name = field.renamed_label(root_attributes);
-
-              match field.get_type() {
-                Field::FieldString
-                | Field::FieldBool
-                | Field::FieldU8
-                | Field::FieldI8
-                | Field::FieldU16
-                | Field::FieldI16
-                | Field::FieldU32
-                | Field::FieldI32
-                | Field::FieldF32
-                | Field::FieldU64
-                | Field::FieldI64
-                | Field::FieldF64 => Some({
-                  quote! {
-                    match self {
-                      &#name::#label { ref #field_label, .. } => {
-                        let struct_start_event =
-                          ::yaserde::__xml::writer::XmlEvent::start_element(#field_label_name);
-                        writer.write(struct_start_event).map_err(|e| e.to_string())?;
+                quote! {
+                  let yaserde_value = #value.unwrap_or_default();
+                  let data_event = ::yaserde::event::XmlEvent::characters(&yaserde_value);
+                  writer.write(data_event)?;
+                }
+              } else {
+                let field_label_name = field.renamed_label(root_attributes);
+                let write_value = write_element_value(&field, quote!(#field_label));
 
-                        let string_value = #field_label.to_string();
-                        let data_event = ::yaserde::__xml::writer::XmlEvent::characters(&string_value);
-                        writer.write(data_event).map_err(|e| e.to_string())?;
+                quote! {
+                  writer.set_start_event_name(
+                    ::std::option::Option::Some(#field_label_name.to_string()),
+                  );
+                  writer.set_skip_start_end(false);
+                  #write_value
+                }
+              };
 
-                        let struct_end_event = ::yaserde::__xml::writer::XmlEvent::end_element();
-                        writer.write(struct_end_event).map_err(|e| e.to_string())?;
-                      },
-                      _ => {},
-                    }
-                  }
-                }),
-                Field::FieldStruct { .. } => Some(quote! {
-                  match self {
-                    &#name::#label{ref #field_label, ..} => {
-                      writer.set_start_event_name(
-                        ::std::option::Option::Some(#field_label_name.to_string()),
-                      );
-                      writer.set_skip_start_end(false);
-                      ::yaserde::YaSerialize::serialize(#field_label, writer)?;
-                    },
-                    _ => {}
-                  }
-                }),
-                Field::FieldVec { .. } => Some(quote! {
-                  match self {
-                    &#name::#label { ref #field_label, .. } => {
-                      for item in #field_label {
-                        writer.set_start_event_name(
-                          ::std::option::Option::Some(#field_label_name.to_string()),
-                        );
-                        writer.set_skip_start_end(false);
-                        ::yaserde::YaSerialize::serialize(item, writer)?;
-                      }
-                    },
-                    _ => {}
-                  }
-                }),
-                Field::FieldOption { .. } => None,
+              quote! {
+                match self {
+                  &#name::#label { ref #field_label, .. } => {
+                    #write
+                  },
+                  _ => {}
+                }
               }
             })
             .collect();
@@ -209,81 +137,25 @@ fn inner_enum_inspector(
             .map(|field| YaSerdeField::new(field.clone()))
             .filter(|field| !field.is_attribute())
             .map(|field| {
-              let write_element = |action: &TokenStream| {
-                quote! {
-                  let struct_start_event = ::yaserde::__xml::writer::XmlEvent::start_element(#label_name);
-                  writer.write(struct_start_event).map_err(|e| e.to_string())?;
-
-                  #action
-
-                  let struct_end_event = ::yaserde::__xml::writer::XmlEvent::end_element();
-                  writer.write(struct_end_event).map_err(|e| e.to_string())?;
-                }
-              };
-
-              let write_string_chars = quote! {
-                let data_event = ::yaserde::__xml::writer::XmlEvent::characters(item);
-                writer.write(data_event).map_err(|e| e.to_string())?;
-              };
+              let write_value = write_element_value(&field, quote!(item));
 
-              let write_simple_type = write_element(&quote! {
-                let s = item.to_string();
-                let data_event = ::yaserde::__xml::writer::XmlEvent::characters(&s);
-                writer.write(data_event).map_err(|e| e.to_string())?;
-              });
-
-              let serialize = quote! {
-                writer.set_start_event_name(::std::option::Option::None);
-                writer.set_skip_start_end(true);
-                ::yaserde::YaSerialize::serialize(item, writer)?;
-              };
-
-              let write_sub_type = |data_type| {
-                write_element(match data_type {
-                  Field::FieldString => &write_string_chars,
-                  _ => &serialize,
-                })
-              };
-
-              let match_field = |write: &TokenStream| {
+              let write = if variant_attrs.flatten || field.is_flatten() {
+                write_value
+              } else {
                 quote! {
-                  match self {
-                    &#name::#label(ref item) => {
-                      #write
-                    },
-                    _ => {},
-                  }
+                  writer.set_start_event_name(::std::option::Option::Some(#label_name.to_string()));
+                  writer.set_skip_start_end(false);
+                  #write_value
                 }
               };
 
-              match field.get_type() {
-                Field::FieldOption { data_type } => {
-                  let write = write_sub_type(*data_type);
-
-                  match_field(&quote! {
-                    if let ::std::option::Option::Some(item) = item {
-                      #write
-                    }
-                  })
+              quote! {
+                match self {
+                  &#name::#label(ref item) => {
+                    #write
+                  },
+                  _ => {},
                 }
-                Field::FieldVec { data_type } => {
-                  let write = write_sub_type(*data_type);
-
-                  match_field(&quote! {
-                    for item in item {
-                      #write
-                    }
-                  })
-                }
-                Field::FieldStruct { .. } => {
-                  if variant_attrs.flatten || field.is_flatten() {
-                     match_field(&quote!{ ::yaserde::YaSerialize::serialize(item, writer)?})
-                   } else {
-                     write_element(&match_field(&serialize))
-                   }
-                }
-                Field::FieldString => match_field(&write_element(&write_string_chars)),
-                _simple_type => match_field(&write_simple_type),
               }
             })
             .collect();
@@ -298,3 +170,10 @@ fn inner_enum_inspector(
     })
     .collect()
 }
+
+fn write_element_value(field: &YaSerdeField, value: TokenStream) -> TokenStream {
+  match field.get_serialize_with_function() {
+    Some(serialize_with) => quote!(#serialize_with(#value, writer)?;),
+    None => quote!(::yaserde::YaSerialize::serialize(#value, writer)?;),
+  }
+}
@@ -6,6 +6,7 @@ use quote::quote;
 
 pub fn implement_serializer(
   name: &Ident,
+  generics: &syn::Generics,
   root: &str,
   attributes: &YaSerdeAttribute,
   append_attributes: TokenStream,
@@ -13,46 +14,41 @@ pub fn implement_serializer(
 ) -> TokenStream {
   let namespaces_definition = generate_namespaces_definition(attributes);
   let flatten = attributes.flatten;
+  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
   quote! {
-    impl ::yaserde::YaSerialize for #name {
+    impl #impl_generics ::yaserde::YaSerialize for #name #ty_generics #where_clause {
       #[allow(unused_variables)]
-      fn serialize<W: ::std::io::Write>(
+      fn serialize<__W: ::std::io::Write>(
         &self,
-        writer: &mut ::yaserde::ser::Serializer<W>,
-      ) -> ::std::result::Result<(), ::std::string::String> {
+        writer: &mut ::yaserde::ser::Serializer<__W>,
+      ) -> ::std::result::Result<(), ::yaserde::ser::Error> {
         let skip = writer.skip_start_end();
 
         if !#flatten && !skip {
           let mut child_attributes = ::std::vec![];
-          let mut child_attributes_namespace = ::yaserde::__xml::namespace::Namespace::empty();
+          let mut child_attributes_namespace = ::yaserde::event::Namespace::empty();
 
           let yaserde_label = writer.get_start_event_name().unwrap_or_else(|| #root.to_string());
           let struct_start_event =
-            ::yaserde::__xml::writer::XmlEvent::start_element(yaserde_label.as_ref()) #namespaces_definition;
+            ::yaserde::event::XmlEvent::start_element(yaserde_label.as_ref()) #namespaces_definition;
           #append_attributes
 
-          let event: ::yaserde::__xml::writer::events::XmlEvent = struct_start_event.into();
+          let event: ::yaserde::event::XmlEvent = struct_start_event.into();
 
-          if let ::yaserde::__xml::writer::events::XmlEvent::StartElement {
+          if let ::yaserde::event::XmlEvent::StartElement {
             name,
-            attributes,
-            namespace,
+            mut attributes,
+            mut namespace,
           } = event {
-            let mut attributes: ::std::vec::Vec<::yaserde::__xml::attribute::OwnedAttribute> =
-              attributes.into_owned().to_vec().iter().map(|k| k.to_owned()).collect();
             attributes.extend(child_attributes);
+            namespace.extend(&child_attributes_namespace);
 
-            let all_attributes = attributes.iter().map(|ca| ca.borrow()).collect();
-
-            let mut all_namespaces = namespace.into_owned();
-            all_namespaces.extend(&child_attributes_namespace);
-
-            writer.write(::yaserde::__xml::writer::events::XmlEvent::StartElement{
+            writer.write(::yaserde::event::XmlEvent::StartElement {
               name,
-              attributes: ::std::borrow::Cow::Owned(all_attributes),
-              namespace: ::std::borrow::Cow::Owned(all_namespaces)
-            }).map_err(|e| e.to_string())?;
+              attributes,
+              namespace,
+            })?;
           } else {
             unreachable!()
           }
@@ -61,8 +57,8 @@ pub fn implement_serializer(
         #inner_inspector
 
         if !#flatten && !skip {
-          let struct_end_event = ::yaserde::__xml::writer::XmlEvent::end_element();
-          writer.write(struct_end_event).map_err(|e| e.to_string())?;
+          let struct_end_event = ::yaserde::event::XmlEvent::end_element();
+          writer.write(struct_end_event)?;
         }
 
         ::std::result::Result::Ok(())
@@ -70,29 +66,27 @@ pub fn implement_serializer(
 
       fn serialize_attributes(
         &self,
-        mut source_attributes: ::std::vec::Vec<::yaserde::__xml::attribute::OwnedAttribute>,
-        mut source_namespace: ::yaserde::__xml::namespace::Namespace,
+        mut source_attributes: ::std::vec::Vec<::yaserde::event::Attribute>,
+        mut source_namespace: ::yaserde::event::Namespace,
       ) -> ::std::result::Result<
-        (::std::vec::Vec<::yaserde::__xml::attribute::OwnedAttribute>, ::yaserde::__xml::namespace::Namespace),
-        ::std::string::String
+        (::std::vec::Vec<::yaserde::event::Attribute>, ::yaserde::event::Namespace),
+        ::yaserde::ser::Error
       > {
-        let mut child_attributes = ::std::vec::Vec::<::yaserde::__xml::attribute::OwnedAttribute>::new();
-        let mut child_attributes_namespace = ::yaserde::__xml::namespace::Namespace::empty();
+        let mut child_attributes = ::std::vec::Vec::<::yaserde::event::Attribute>::new();
+        let mut child_attributes_namespace = ::yaserde::event::Namespace::empty();
 
         let struct_start_event =
-          ::yaserde::__xml::writer::XmlEvent::start_element("temporary_element_to_generate_attributes")
+          ::yaserde::event::XmlEvent::start_element("temporary_element_to_generate_attributes")
           #namespaces_definition;
 
         #append_attributes
-        let event: ::yaserde::__xml::writer::events::XmlEvent = struct_start_event.into();
+        let event: ::yaserde::event::XmlEvent = struct_start_event.into();
 
-        if let ::yaserde::__xml::writer::events::XmlEvent::StartElement { attributes, namespace, .. } = event {
-          source_namespace.extend(&namespace.into_owned());
+        if let ::yaserde::event::XmlEvent::StartElement { attributes, namespace, .. } = event {
+          source_namespace.extend(&namespace);
           source_namespace.extend(&child_attributes_namespace);
 
-          let a: ::std::vec::Vec<::yaserde::__xml::attribute::OwnedAttribute> =
-            attributes.into_owned().to_vec().iter().map(|k| k.to_owned()).collect();
-          source_attributes.extend(a);
+          source_attributes.extend(attributes);
           source_attributes.extend(child_attributes);
 
           ::std::result::Result::Ok((source_attributes, source_namespace))
@@ -100,6 +94,16 @@ pub fn implement_serializer(
           unreachable!();
         }
       }
+
+      fn root_element() -> ::std::option::Option<::yaserde::event::XmlEvent> {
+        if #flatten {
+          return ::std::option::Option::None;
+        }
+
+        let struct_start_event = ::yaserde::event::XmlEvent::start_element(#root)
+          #namespaces_definition;
+        ::std::option::Option::Some(struct_start_event.into())
+      }
     }
   }
 }
@@ -5,11 +5,11 @@ pub mod implement_serializer;
 pub mod label;
 pub mod namespace;
 
-use crate::common::YaSerdeAttribute;
-use proc_macro2::{Ident, TokenStream};
+use crate::common::{bound, YaSerdeAttribute};
+use proc_macro2::TokenStream;
 use quote::quote;
 
-pub fn expand_derive_serialize(ast: &syn::DeriveInput) -> Result<TokenStream, String> {
+pub fn expand_derive_serialize(ast: &syn::DeriveInput) -> syn::Result<TokenStream> {
   let name = &ast.ident;
   let attrs = &ast.attrs;
   let data = &ast.data;
@@ -19,27 +19,93 @@ pub fn expand_derive_serialize(ast: &syn::DeriveInput) -> Result<TokenStream, St
   let root_name = format!(
     "{}{}",
     root_attributes.prefix_namespace(),
-    root_attributes.xml_element_name(name)
+    root_attributes.document_element_name(name)
   );
 
+  if root_attributes.display_fromstr {
+    return Ok(expand_display(ast, &root_name));
+  }
+
+  let generics = bound::with_bounds(
+    ast,
+    &root_attributes,
+    quote!(::yaserde::YaSerialize),
+    quote!(::yaserde::YaSerializeValue),
+    |field| field.get_serialize_with_function().is_some(),
+  )?;
+  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
   let impl_block = match *data {
     syn::Data::Struct(ref data_struct) => {
-      expand_struct::serialize(data_struct, name, &root_name, &root_attributes)
+      expand_struct::serialize(data_struct, name, &generics, &root_name, &root_attributes)
     }
     syn::Data::Enum(ref data_enum) => {
-      expand_enum::serialize(data_enum, name, &root_name, &root_attributes)
+      expand_enum::serialize(data_enum, name, &generics, &root_name, &root_attributes)
     }
-    syn::Data::Union(ref _data_union) => unimplemented!(),
+    syn::Data::Union(_) => unreachable!("unions are rejected by the input checks"),
   };
 
-  let dummy_const = Ident::new(&format!("_IMPL_YA_SERIALIZE_FOR_{}", name), name.span());
-
   Ok(quote! {
     #[allow(non_upper_case_globals, unused_attributes, unused_qualifications)]
-    const #dummy_const: () = {
-      use ::std::str::FromStr as _;
-
+    const _: () = {
       #impl_block
+
+      impl #impl_generics ::yaserde::YaSerializeValue for #name #ty_generics #where_clause {
+        fn serialize_value(
+          &self,
+        ) -> ::std::result::Result<::std::option::Option<::std::string::String>, ::yaserde::ser::Error> {
+          ::yaserde::ser::to_string_content(self).map(::std::option::Option::Some)
+        }
+      }
     };
   })
 }
+
+/// Write the whole value with `Display`, for containers marked with `display_fromstr`.
+fn expand_display(ast: &syn::DeriveInput, root_name: &str) -> TokenStream {
+  let name = &ast.ident;
+  let (_, ty_generics, _) = ast.generics.split_for_impl();
+
+  let mut generics = ast.generics.clone();
+  generics
+    .make_where_clause()
+    .predicates
+    .push(syn::parse_quote!(#name #ty_generics: ::std::fmt::Display));
+  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+  quote! {
+    #[allow(non_upper_case_globals, unused_attributes, unused_qualifications)]
+    const _: () = {
+      impl #impl_generics ::yaserde::YaSerialize for #name #ty_generics #where_clause {
+        fn serialize<__W: ::std::io::Write>(
+          &self,
+          writer: &mut ::yaserde::ser::Serializer<__W>,
+        ) -> ::std::result::Result<(), ::yaserde::ser::Error> {
+          if writer.get_start_event_name().is_none() && !writer.skip_start_end() {
+            writer.set_start_event_name(::std::option::Option::Some(#root_name.to_string()));
+          }
+          ::yaserde::ser::serialize_display(self, writer)
+        }
+
+        fn serialize_attributes(
+          &self,
+          attributes: ::std::vec::Vec<::yaserde::event::Attribute>,
+          namespace: ::yaserde::event::Namespace,
+        ) -> ::std::result::Result<
+          (::std::vec::Vec<::yaserde::event::Attribute>, ::yaserde::event::Namespace),
+          ::yaserde::ser::Error
+        > {
+          ::std::result::Result::Ok((attributes, namespace))
+        }
+      }
+
+      impl #impl_generics ::yaserde::YaSerializeValue for #name #ty_generics #where_clause {
+        fn serialize_value(
+          &self,
+        ) -> ::std::result::Result<::std::option::Option<::std::string::String>, ::yaserde::ser::Error> {
+          ::yaserde::ser::to_string_value(self)
+        }
+      }
+    };
+  }
+}